@@ -0,0 +1,378 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use log::warn;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::modules::user::types::Email;
+
+/// Read when `APP_CONFIG_FILE` is unset, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "planit.toml";
+
+/// Only meant for local development; used only when the environment is explicitly development.
+const DEFAULT_JWT_SECRET: &str = "fd183e2e-4d6c-47cd-89c8-619e8c0e9694";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("Invalid config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("Invalid value {value:?} for {name}")]
+    InvalidValue { name: &'static str, value: String },
+
+    #[error("{0} must be set")]
+    Missing(&'static str),
+
+    #[error("JWT_SECRET must be set unless APP_ENV is development")]
+    DefaultJwtSecret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Development,
+    Production,
+}
+
+impl FromStr for Environment {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "development" => Ok(Environment::Development),
+            "production" => Ok(Environment::Production),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where rate limit buckets are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// Only accurate with a single instance.
+    #[default]
+    Memory,
+    /// Shared between instances.
+    MongoDb,
+}
+
+impl FromStr for RateLimitBackend {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "memory" => Ok(RateLimitBackend::Memory),
+            "mongodb" => Ok(RateLimitBackend::MongoDb),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MongoConfig {
+    pub uri: String,
+    pub database: String,
+}
+
+impl Default for MongoConfig {
+    fn default() -> Self {
+        MongoConfig {
+            uri: String::new(),
+            database: "planite".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub require_email_verification: bool,
+    /// Makes the user-exists and signup endpoints give every email the same answer, so they
+    /// cannot be used to discover registered addresses.
+    pub user_exists_anti_enumeration: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            jwt_secret: DEFAULT_JWT_SECRET.to_string(),
+            require_email_verification: false,
+            user_exists_anti_enumeration: false,
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub store: RateLimitBackend,
+    /// Only safe behind a proxy that overwrites `X-Forwarded-For`.
+    pub trust_proxy_headers: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub retention_days: u64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            retention_days: 180,
+        }
+    }
+}
+
+impl AuditConfig {
+    /// How long events are kept.
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.retention_days * 24 * 60 * 60)
+    }
+}
+
+/// Verified accounts using one of `emails` are made administrators at startup, so the first
+/// administrator does not have to be set in the database by hand.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    pub emails: Vec<Email>,
+}
+
+/// Emails are sent over SMTP only when `host` is set.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub tls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        SmtpConfig {
+            host: None,
+            port: None,
+            tls: true,
+            username: None,
+            password: None,
+            from: "PlanIt <no-reply@planit.local>".to_string(),
+        }
+    }
+}
+
+/// Settings for the whole application, read once at startup.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub environment: Option<Environment>, // None until set, which counts as production
+    pub host: String,
+    pub mongodb: MongoConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub audit: AuditConfig,
+    pub admin: AdminConfig,
+    pub smtp: SmtpConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            environment: None,
+            host: "127.0.0.1:8080".to_string(),
+            mongodb: MongoConfig::default(),
+            auth: AuthConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            audit: AuditConfig::default(),
+            admin: AdminConfig::default(),
+            smtp: SmtpConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Starts from the TOML file named by `APP_CONFIG_FILE` (or `planit.toml` when present),
+    /// overrides it with environment variables and validates the result. Empty variables
+    /// count as unset.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env_value("APP_CONFIG_FILE") {
+            Some(path) => Self::from_file(Path::new(&path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => AppConfig::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        override_option_with(&mut self.environment, "APP_ENV")?;
+        override_with(&mut self.host, "APP_HOST")?;
+
+        override_with(&mut self.mongodb.uri, "MONGO_DB_URI")?;
+        override_with(&mut self.mongodb.database, "MONGO_DB_NAME")?;
+
+        override_with(&mut self.auth.jwt_secret, "JWT_SECRET")?;
+        override_with(
+            &mut self.auth.require_email_verification,
+            "REQUIRE_EMAIL_VERIFICATION",
+        )?;
+        override_with(
+            &mut self.auth.user_exists_anti_enumeration,
+            "USER_EXISTS_ANTI_ENUMERATION",
+        )?;
+
+        override_with(&mut self.rate_limit.store, "RATE_LIMIT_STORE")?;
+        override_with(
+            &mut self.rate_limit.trust_proxy_headers,
+            "TRUST_PROXY_HEADERS",
+        )?;
+
+        override_with(&mut self.audit.retention_days, "AUDIT_RETENTION_DAYS")?;
+
+        override_list_with(&mut self.admin.emails, "ADMIN_EMAILS")?;
+
+        override_option_with(&mut self.smtp.host, "SMTP_HOST")?;
+        override_option_with(&mut self.smtp.port, "SMTP_PORT")?;
+        override_with(&mut self.smtp.tls, "SMTP_TLS")?;
+        override_option_with(&mut self.smtp.username, "SMTP_USERNAME")?;
+        override_option_with(&mut self.smtp.password, "SMTP_PASSWORD")?;
+        override_with(&mut self.smtp.from, "SMTP_FROM")?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.mongodb.uri.is_empty() {
+            return Err(ConfigError::Missing("MONGO_DB_URI"));
+        }
+        if self.mongodb.database.is_empty() {
+            return Err(ConfigError::Missing("MONGO_DB_NAME"));
+        }
+        if self.auth.jwt_secret.is_empty() {
+            return Err(ConfigError::Missing("JWT_SECRET"));
+        }
+        if self.auth.jwt_secret == DEFAULT_JWT_SECRET {
+            if self.environment != Some(Environment::Development) {
+                return Err(ConfigError::DefaultJwtSecret);
+            }
+            warn!("JWT_SECRET is not set, tokens are signed with the development secret");
+        }
+        if self.audit.retention_days == 0 {
+            return Err(ConfigError::InvalidValue {
+                name: "AUDIT_RETENTION_DAYS",
+                value: "0".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn override_with<T: FromStr>(field: &mut T, name: &'static str) -> Result<(), ConfigError> {
+    if let Some(value) = env_value(name) {
+        *field = value
+            .parse()
+            .map_err(|_| ConfigError::InvalidValue { name, value })?;
+    }
+    Ok(())
+}
+
+/// Reads a comma-separated list.
+fn override_list_with<T: FromStr>(
+    field: &mut Vec<T>,
+    name: &'static str,
+) -> Result<(), ConfigError> {
+    if let Some(value) = env_value(name) {
+        *field = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.parse().map_err(|_| ConfigError::InvalidValue {
+                    name,
+                    value: item.to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(())
+}
+
+fn override_option_with<T: FromStr>(
+    field: &mut Option<T>,
+    name: &'static str,
+) -> Result<(), ConfigError> {
+    if let Some(value) = env_value(name) {
+        *field = Some(
+            value
+                .parse()
+                .map_err(|_| ConfigError::InvalidValue { name, value })?,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(environment: Option<Environment>, jwt_secret: &str) -> AppConfig {
+        let mut config = AppConfig {
+            environment,
+            ..AppConfig::default()
+        };
+        config.mongodb.uri = "mongodb://localhost:27017".to_string();
+        config.auth.jwt_secret = jwt_secret.to_string();
+        config
+    }
+
+    #[test]
+    fn the_default_jwt_secret_needs_an_explicit_development_environment() {
+        assert!(config(Some(Environment::Development), DEFAULT_JWT_SECRET)
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            config(None, DEFAULT_JWT_SECRET).validate(),
+            Err(ConfigError::DefaultJwtSecret)
+        ));
+        assert!(matches!(
+            config(Some(Environment::Production), DEFAULT_JWT_SECRET).validate(),
+            Err(ConfigError::DefaultJwtSecret)
+        ));
+        assert!(config(None, "a-real-secret").validate().is_ok());
+    }
+}
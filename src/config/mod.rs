@@ -1,2 +1,4 @@
 
+pub mod app;
 pub mod mongodb;
+pub mod repositories;
@@ -1,18 +1,166 @@
-use mongodb::{bson::doc, options::ClientOptions, Client, Database};
-use std::env;
+use std::time::Duration;
 
-pub async fn get_database() -> Database {
-    let uri = env::var("MONGO_DB_URI").expect("ENV: MONGO_DB_URI must be set");
-    let client_options = ClientOptions::parse(uri)
+use mongodb::{
+    bson::{doc, Document},
+    error::Error,
+    options::{ClientOptions, IndexOptions},
+    Client, Database, IndexModel,
+};
+
+use crate::modules::{
+    auth::service::FAILED_LOGIN_WINDOW_HOURS, rate_limit::limiter::IDLE_BUCKET_TTL,
+    user::repository::email_collation,
+};
+
+use super::app::MongoConfig;
+
+pub async fn get_database(config: &MongoConfig) -> Database {
+    let client_options = ClientOptions::parse(&config.uri)
         .await
         .expect("Failed to parse MONGO_DB_URI");
     let client = Client::with_options(client_options).expect("Failed to create MongoDB client");
 
     client
-        .database("planite")
+        .database(&config.database)
         .run_command(doc! {"ping": 1})
         .await
         .expect("Failed to ping MongoDB server");
 
-    client.database("planite")
+    client.database(&config.database)
+}
+
+fn index(keys: Document, options: IndexOptions) -> IndexModel {
+    IndexModel::builder().keys(keys).options(options).build()
+}
+
+/// Creates the unique and query indexes the repositories rely on. Existing indexes are left
+/// as they are, so this is safe to run on every startup.
+pub async fn ensure_indexes(db: &Database) -> Result<(), Error> {
+    // Same collation as the lookups, so emails differing only in case are duplicates.
+    db.collection::<Document>("users")
+        .create_index(index(
+            doc! { "email": 1 },
+            IndexOptions::builder()
+                .name("email_unique".to_string())
+                .unique(true)
+                .collation(email_collation())
+                .build(),
+        ))
+        .await?;
+
+    // Calendar feeds are found by token hash, which must point at a single account. Partial
+    // rather than sparse, since accounts without a feed store the field as null.
+    db.collection::<Document>("users")
+        .create_index(index(
+            doc! { "feed_token_hash": 1 },
+            IndexOptions::builder()
+                .name("feed_token_hash_unique".to_string())
+                .unique(true)
+                .partial_filter_expression(doc! { "feed_token_hash": { "$type": "string" } })
+                .build(),
+        ))
+        .await?;
+
+    // Titles are unique per scope: per user among personal records (whose `workspace_id` is
+    // null), and per workspace among shared ones.
+    for collection in ["tasks", "categories"] {
+        db.collection::<Document>(collection)
+            .create_indexes([
+                index(
+                    doc! { "user_id": 1, "workspace_id": 1, "title": 1 },
+                    IndexOptions::builder()
+                        .name("user_title_unique".to_string())
+                        .unique(true)
+                        .build(),
+                ),
+                index(
+                    doc! { "workspace_id": 1, "title": 1 },
+                    IndexOptions::builder()
+                        .name("workspace_title_unique".to_string())
+                        .unique(true)
+                        .partial_filter_expression(doc! { "workspace_id": { "$type": "objectId" } })
+                        .build(),
+                ),
+            ])
+            .await?;
+    }
+
+    // Polled by the notification scheduler every minute.
+    db.collection::<Document>("tasks")
+        .create_index(index(
+            doc! { "notification.scheduled_time": 1, "notification.sent": 1 },
+            IndexOptions::builder()
+                .name("notification_schedule".to_string())
+                .build(),
+        ))
+        .await?;
+
+    // Expired refresh tokens are removed by MongoDB; the service checks expiry itself too.
+    // Tokens are looked up by hash on every refresh, and a session is revoked by family.
+    db.collection::<Document>("refresh_tokens")
+        .create_indexes([
+            index(
+                doc! { "expires_at": 1 },
+                IndexOptions::builder()
+                    .name("expires_at_ttl".to_string())
+                    .expire_after(Duration::ZERO)
+                    .build(),
+            ),
+            index(
+                doc! { "token_hash": 1 },
+                IndexOptions::builder()
+                    .name("token_hash_unique".to_string())
+                    .unique(true)
+                    .build(),
+            ),
+            index(
+                doc! { "family_id": 1 },
+                IndexOptions::builder().name("family_id".to_string()).build(),
+            ),
+        ])
+        .await?;
+
+    db.collection::<Document>("one_time_tokens")
+        .create_index(index(
+            doc! { "token_hash": 1 },
+            IndexOptions::builder()
+                .name("token_hash_unique".to_string())
+                .unique(true)
+                .build(),
+        ))
+        .await?;
+
+    // Failed logins are counted per email and client IP, and stop counting after the window.
+    db.collection::<Document>("login_attempts")
+        .create_indexes([
+            index(
+                doc! { "email": 1, "ip": 1 },
+                IndexOptions::builder()
+                    .name("email_ip_unique".to_string())
+                    .unique(true)
+                    .build(),
+            ),
+            index(
+                doc! { "last_failure_at": 1 },
+                IndexOptions::builder()
+                    .name("last_failure_at_ttl".to_string())
+                    .expire_after(Duration::from_secs(FAILED_LOGIN_WINDOW_HOURS as u64 * 60 * 60))
+                    .build(),
+            ),
+        ])
+        .await?;
+
+    // A bucket left alone for longer than any limit's period is full again, the same as a
+    // missing one.
+    db.collection::<Document>("rate_limits")
+        .create_index(index(
+            doc! { "updated_at": 1 },
+            IndexOptions::builder()
+                .name("updated_at_ttl".to_string())
+                .expire_after(IDLE_BUCKET_TTL)
+                .build(),
+        ))
+        .await?;
+
+    Ok(())
 }
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use mongodb::Database;
+
+#[cfg(test)]
+use crate::modules::{
+    audit::memory::InMemoryAuditRepository,
+    auth::memory::{
+        InMemoryLoginAttemptRepository, InMemoryOneTimeTokenRepository,
+        InMemoryRefreshTokenRepository,
+    },
+    category::memory::InMemoryCategoryRepository,
+    goal::memory::InMemoryGoalRepository,
+    task::memory::{
+        InMemoryTaskActivityRepository, InMemoryTaskCommentRepository, InMemoryTaskRepository,
+    },
+    user::memory::InMemoryUserRepository,
+    workspace::memory::InMemoryWorkspaceRepository,
+};
+use crate::modules::{
+    audit::repository::{AuditRepository, MongoAuditRepository},
+    auth::repository::{
+        LoginAttemptRepository, MongoLoginAttemptRepository, MongoOneTimeTokenRepository,
+        MongoRefreshTokenRepository, OneTimeTokenRepository, RefreshTokenRepository,
+    },
+    category::repository::{CategoryRepository, MongoCategoryRepository},
+    goal::repository::{GoalRepository, MongoGoalRepository},
+    task::repository::{
+        MongoTaskActivityRepository, MongoTaskCommentRepository, MongoTaskRepository,
+        TaskActivityRepository, TaskCommentRepository, TaskRepository,
+    },
+    user::repository::{MongoUserRepository, UserRepository},
+    workspace::repository::{MongoWorkspaceRepository, WorkspaceRepository},
+};
+
+/// The repositories shared by every request.
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub workspaces: Arc<dyn WorkspaceRepository>,
+    pub categories: Arc<dyn CategoryRepository>,
+    pub goals: Arc<dyn GoalRepository>,
+    pub tasks: Arc<dyn TaskRepository>,
+    pub task_comments: Arc<dyn TaskCommentRepository>,
+    pub task_activity: Arc<dyn TaskActivityRepository>,
+    pub refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    pub one_time_tokens: Arc<dyn OneTimeTokenRepository>,
+    pub login_attempts: Arc<dyn LoginAttemptRepository>,
+    pub audit_events: Arc<dyn AuditRepository>,
+}
+
+impl Repositories {
+    pub fn mongodb(db: &Database) -> Self {
+        Repositories {
+            users: Arc::new(MongoUserRepository::new(db)),
+            workspaces: Arc::new(MongoWorkspaceRepository::new(db)),
+            categories: Arc::new(MongoCategoryRepository::new(db)),
+            goals: Arc::new(MongoGoalRepository::new(db)),
+            tasks: Arc::new(MongoTaskRepository::new(db)),
+            task_comments: Arc::new(MongoTaskCommentRepository::new(db)),
+            task_activity: Arc::new(MongoTaskActivityRepository::new(db)),
+            refresh_tokens: Arc::new(MongoRefreshTokenRepository::new(db)),
+            one_time_tokens: Arc::new(MongoOneTimeTokenRepository::new(db)),
+            login_attempts: Arc::new(MongoLoginAttemptRepository::new(db)),
+            audit_events: Arc::new(MongoAuditRepository::new(db)),
+        }
+    }
+
+    /// Lost when dropped; for tests of the services.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let categories: Arc<dyn CategoryRepository> =
+            Arc::new(InMemoryCategoryRepository::default());
+        Repositories {
+            users: Arc::new(InMemoryUserRepository::default()),
+            workspaces: Arc::new(InMemoryWorkspaceRepository::default()),
+            goals: Arc::new(InMemoryGoalRepository::default()),
+            tasks: Arc::new(InMemoryTaskRepository::new(categories.clone())),
+            task_comments: Arc::new(InMemoryTaskCommentRepository::default()),
+            task_activity: Arc::new(InMemoryTaskActivityRepository::default()),
+            refresh_tokens: Arc::new(InMemoryRefreshTokenRepository::default()),
+            one_time_tokens: Arc::new(InMemoryOneTimeTokenRepository::default()),
+            login_attempts: Arc::new(InMemoryLoginAttemptRepository::default()),
+            audit_events: Arc::new(InMemoryAuditRepository::default()),
+            categories,
+        }
+    }
+}
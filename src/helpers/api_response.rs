@@ -1,9 +1,10 @@
 use axum::{
-    http::{header, StatusCode},
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
+use std::time::Duration;
 
 #[derive(Serialize)]
 #[serde(untagged)]
@@ -13,6 +14,12 @@ pub enum ApiResponse {
         message: String,
         data: Option<serde_json::Value>,
     },
+    Page {
+        status: String,
+        message: String,
+        data: Option<serde_json::Value>,
+        next_cursor: Option<String>,
+    },
     Created {
         status: String,
         message: String,
@@ -27,6 +34,10 @@ pub enum ApiResponse {
         status: String,
         message: String,
     },
+    Forbidden {
+        status: String,
+        message: String,
+    },
     UnprocessableEntity {
         status: String,
         message: String,
@@ -41,6 +52,12 @@ pub enum ApiResponse {
         status: String,
         message: String,
     },
+    TooManyRequests {
+        status: String,
+        message: String,
+        #[serde(skip)]
+        retry_after: Duration,
+    },
 }
 
 impl ApiResponse {
@@ -52,6 +69,15 @@ impl ApiResponse {
         }
     }
 
+    pub fn page<T: Serialize>(message: &str, data: Vec<T>, next_cursor: Option<String>) -> Self {
+        ApiResponse::Page {
+            status: "success".to_string(),
+            message: message.to_string(),
+            data: Some(serde_json::to_value(data).unwrap()),
+            next_cursor,
+        }
+    }
+
     pub fn created<T: Serialize>(message: &str, data: Option<T>) -> Self {
         ApiResponse::Created {
             status: "success".to_string(),
@@ -75,6 +101,13 @@ impl ApiResponse {
         }
     }
 
+    pub fn forbidden(message: &str) -> Self {
+        ApiResponse::Forbidden {
+            status: "error".to_string(),
+            message: message.to_string(),
+        }
+    }
+
     pub fn unprocessable_entity<T: Serialize>(message: &str, errors: Option<T>) -> Self {
         ApiResponse::UnprocessableEntity {
             status: "error".to_string(),
@@ -97,26 +130,64 @@ impl ApiResponse {
             message: message.to_string(),
         }
     }
+
+    pub fn too_many_requests(message: &str, retry_after: Duration) -> Self {
+        ApiResponse::TooManyRequests {
+            status: "error".to_string(),
+            message: message.to_string(),
+            retry_after,
+        }
+    }
 }
 
 impl IntoResponse for ApiResponse {
     fn into_response(self) -> Response {
         let status_code: StatusCode = match &self {
             ApiResponse::Ok { .. } => StatusCode::OK,
+            ApiResponse::Page { .. } => StatusCode::OK,
             ApiResponse::Created { .. } => StatusCode::CREATED,
             ApiResponse::BadRequestError { .. } => StatusCode::BAD_REQUEST,
             ApiResponse::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiResponse::Forbidden { .. } => StatusCode::FORBIDDEN,
             ApiResponse::UnprocessableEntity { .. } => StatusCode::UNPROCESSABLE_ENTITY,
             ApiResponse::ServerError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             ApiResponse::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiResponse::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+        };
+        // The header only takes whole seconds; never tell a client to retry immediately.
+        let retry_after = match &self {
+            ApiResponse::TooManyRequests { retry_after, .. } => Some(retry_after.as_secs().max(1)),
+            _ => None,
         };
 
-        let json_response = Json(self);
-        (
+        let mut response = (
             status_code,
             [(header::CONTENT_TYPE, "application/json")],
-            json_response,
+            Json(self),
         )
-            .into_response()
+            .into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_after(wait: Duration) -> HeaderValue {
+        let response = ApiResponse::too_many_requests("Slow down", wait).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        response.headers()[header::RETRY_AFTER].clone()
+    }
+
+    #[test]
+    fn limited_responses_tell_when_to_retry() {
+        assert_eq!(retry_after(Duration::from_secs(90)), "90");
+        assert_eq!(retry_after(Duration::ZERO), "1");
     }
 }
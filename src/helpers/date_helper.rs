@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, Bson};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The BSON date of `date`, for filters and `$set` updates. BSON dates keep milliseconds.
+pub fn to_bson_datetime(date: DateTime<Utc>) -> bson::DateTime {
+    bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+fn from_bson(value: Bson) -> Result<DateTime<Utc>, String> {
+    match value {
+        Bson::DateTime(date) => DateTime::from_timestamp_millis(date.timestamp_millis())
+            .ok_or_else(|| String::from("date out of range")),
+        // Dates were stored as RFC 3339 strings before they were BSON dates.
+        Bson::String(date) => DateTime::parse_from_rfc3339(&date)
+            .map(|date| date.with_timezone(&Utc))
+            .map_err(|err| err.to_string()),
+        _ => Err(String::from("expected a date")),
+    }
+}
+
+pub fn serialize_bson_datetime<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    to_bson_datetime(*date).serialize(serializer)
+}
+
+pub fn deserialize_bson_datetime<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    from_bson(Bson::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+pub fn serialize_option_bson_datetime<S>(
+    date: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    date.map(to_bson_datetime).serialize(serializer)
+}
+
+pub fn deserialize_option_bson_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Bson::deserialize(deserializer)? {
+        Bson::Null => Ok(None),
+        value => from_bson(value).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use mongodb::bson::{doc, from_document, to_document};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Stamped {
+        #[serde(
+            serialize_with = "serialize_bson_datetime",
+            deserialize_with = "deserialize_bson_datetime"
+        )]
+        at: DateTime<Utc>,
+        #[serde(
+            default,
+            serialize_with = "serialize_option_bson_datetime",
+            deserialize_with = "deserialize_option_bson_datetime"
+        )]
+        until: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn dates_round_trip_as_bson_dates() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 2, 10, 0, 0).unwrap()
+            + chrono::Duration::milliseconds(123);
+        let document = to_document(&Stamped {
+            at,
+            until: Some(at),
+        })
+        .unwrap();
+        assert!(matches!(document.get("at"), Some(Bson::DateTime(_))));
+        assert!(matches!(document.get("until"), Some(Bson::DateTime(_))));
+
+        let stamped: Stamped = from_document(document).unwrap();
+        assert_eq!(stamped.at, at);
+        assert_eq!(stamped.until, Some(at));
+
+        let stamped: Stamped =
+            from_document(doc! { "at": to_bson_datetime(at), "until": null }).unwrap();
+        assert_eq!(stamped.until, None);
+    }
+
+    #[test]
+    fn dates_stored_as_strings_still_read() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 2, 10, 0, 0).unwrap();
+        for stored in [
+            "2024-03-02T10:00:00Z",
+            "2024-03-02T10:00:00+00:00",
+            "2024-03-02T10:00:00.000Z",
+            "2024-03-02T07:00:00-03:00",
+        ] {
+            let stamped: Stamped = from_document(doc! { "at": stored, "until": stored }).unwrap();
+            assert_eq!(stamped.at, at, "{}", stored);
+            assert_eq!(stamped.until, Some(at), "{}", stored);
+        }
+
+        assert!(from_document::<Stamped>(doc! { "at": "2 March 2024" }).is_err());
+    }
+}
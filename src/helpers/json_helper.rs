@@ -0,0 +1,79 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use super::api_response::ApiResponse;
+
+/// `Json`, but a body that does not deserialize (a field of the wrong type, or a value such
+/// as an email address rejected while parsing) is answered like any other validation error
+/// instead of with axum's plain-text rejection.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(JsonBody(value)),
+            Err(JsonRejection::JsonDataError(err)) => Err(ApiResponse::bad_request(
+                "Validation failed",
+                Some(err.body_text()),
+            )
+            .into_response()),
+            Err(rejection) => Err(ApiResponse::bad_request(
+                "Invalid JSON body",
+                Some(rejection.body_text()),
+            )
+            .into_response()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, StatusCode},
+    };
+
+    use super::*;
+    use crate::modules::user::types::Email;
+
+    async fn rejection(body: &'static str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let response = match JsonBody::<Email>::from_request(request, &()).await {
+            Ok(_) => panic!("{} was accepted", body),
+            Err(response) => response,
+        };
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn rejected_bodies_are_bad_requests() {
+        let (status, body) = rejection("\"not an email\"").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Validation failed");
+        assert!(body["errors"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid email address"));
+
+        let (status, body) = rejection("]").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "Invalid JSON body");
+    }
+}
@@ -0,0 +1,233 @@
+use std::{cmp::Ordering, marker::PhantomData, sync::Mutex};
+
+use mongodb::bson::{doc, from_document, oid::ObjectId, to_document, Bson, Document};
+use mongodb::error::{Error, ErrorKind, WriteError, WriteFailure};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Records of one collection kept in process memory, for the in-memory repositories.
+///
+/// Records are stored as the documents MongoDB would hold, so every read goes through the
+/// same deserialization, and an update only counts as a modification when the stored
+/// document actually changes. Unique indexes reject inserts and updates with the
+/// duplicate key error MongoDB returns.
+pub struct MemoryCollection<T> {
+    documents: Mutex<Vec<Document>>,
+    unique: Vec<UniqueIndex<T>>,
+    records: PhantomData<fn() -> T>,
+}
+
+/// The key a record takes in a unique index, or None when the index does not cover it (like
+/// a partial filter).
+type UniqueIndex<T> = (&'static str, fn(&T) -> Option<Bson>);
+
+impl<T> Default for MemoryCollection<T> {
+    fn default() -> Self {
+        MemoryCollection {
+            documents: Mutex::new(Vec::new()),
+            unique: Vec::new(),
+            records: PhantomData,
+        }
+    }
+}
+
+impl<T> MemoryCollection<T> {
+    pub fn unique(mut self, name: &'static str, key: fn(&T) -> Option<Bson>) -> Self {
+        self.unique.push((name, key));
+        self
+    }
+}
+
+/// The error MongoDB reports when a write breaks a unique index.
+fn duplicate_key(index: &str) -> Error {
+    let write_error: WriteError = from_document(doc! {
+        "code": 11000,
+        "codeName": "DuplicateKey",
+        "errmsg": format!("E11000 duplicate key error index: {}", index),
+    })
+    .unwrap();
+    Error::from(ErrorKind::Write(WriteFailure::WriteError(write_error)))
+}
+
+/// Counts reported by an update, like `UpdateResult`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateCounts {
+    pub matched: u64,
+    pub modified: u64,
+}
+
+impl<T: Serialize + DeserializeOwned> MemoryCollection<T> {
+    /// Stores the record, generating an `_id` when it has none.
+    pub fn insert(&self, record: &T) -> Result<ObjectId, Error> {
+        let mut document = to_document(record)?;
+        // Ids set on a record serialize as hex strings; they are kept as ObjectIds like
+        // generated ones.
+        let id = match document.get("_id") {
+            Some(Bson::ObjectId(id)) => *id,
+            Some(Bson::String(id)) => ObjectId::parse_str(id).unwrap_or_else(|_| ObjectId::new()),
+            _ => ObjectId::new(),
+        };
+        document.insert("_id", id);
+        let mut documents = self.documents.lock().unwrap();
+        self.check_unique(&documents, None, record)?;
+        documents.push(document);
+        Ok(id)
+    }
+
+    /// Matching records, in insertion order.
+    pub fn find(&self, filter: impl Fn(&T) -> bool) -> Result<Vec<T>, Error> {
+        let documents = self.documents.lock().unwrap();
+        let mut records = Vec::new();
+        for document in documents.iter() {
+            let record: T = from_document(document.clone())?;
+            if filter(&record) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    pub fn find_one(&self, filter: impl Fn(&T) -> bool) -> Result<Option<T>, Error> {
+        Ok(self.find(filter)?.into_iter().next())
+    }
+
+    pub fn count(&self, filter: impl Fn(&T) -> bool) -> Result<u64, Error> {
+        Ok(self.find(filter)?.len() as u64)
+    }
+
+    pub fn update_one(
+        &self,
+        filter: impl Fn(&T) -> bool,
+        update: impl FnOnce(&mut T),
+    ) -> Result<UpdateCounts, Error> {
+        let mut update = Some(update);
+        self.update(filter, true, |record| {
+            if let Some(update) = update.take() {
+                update(record);
+            }
+        })
+    }
+
+    pub fn update_many(
+        &self,
+        filter: impl Fn(&T) -> bool,
+        update: impl FnMut(&mut T),
+    ) -> Result<UpdateCounts, Error> {
+        self.update(filter, false, update)
+    }
+
+    pub fn delete_one(&self, filter: impl Fn(&T) -> bool) -> Result<u64, Error> {
+        self.delete(filter, true)
+    }
+
+    pub fn delete_many(&self, filter: impl Fn(&T) -> bool) -> Result<u64, Error> {
+        self.delete(filter, false)
+    }
+
+    fn update(
+        &self,
+        filter: impl Fn(&T) -> bool,
+        single: bool,
+        mut update: impl FnMut(&mut T),
+    ) -> Result<UpdateCounts, Error> {
+        let mut documents = self.documents.lock().unwrap();
+        let mut counts = UpdateCounts::default();
+        for index in 0..documents.len() {
+            let mut record: T = from_document(documents[index].clone())?;
+            if !filter(&record) {
+                continue;
+            }
+            counts.matched += 1;
+            update(&mut record);
+            let mut updated = to_document(&record)?;
+            if let Some(id) = documents[index].get("_id") {
+                updated.insert("_id", id.clone());
+            }
+            if updated != documents[index] {
+                self.check_unique(&documents, Some(index), &record)?;
+                documents[index] = updated;
+                counts.modified += 1;
+            }
+            if single {
+                break;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Fails when `record` takes a key another stored record already has. `replacing` is the
+    /// position of the record being updated, which is not compared with itself.
+    fn check_unique(
+        &self,
+        documents: &[Document],
+        replacing: Option<usize>,
+        record: &T,
+    ) -> Result<(), Error> {
+        for (name, key) in &self.unique {
+            let Some(key_value) = key(record) else {
+                continue;
+            };
+            for (index, document) in documents.iter().enumerate() {
+                if Some(index) == replacing {
+                    continue;
+                }
+                let other: T = from_document(document.clone())?;
+                if key(&other).as_ref() == Some(&key_value) {
+                    return Err(duplicate_key(name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(&self, filter: impl Fn(&T) -> bool, single: bool) -> Result<u64, Error> {
+        let mut documents = self.documents.lock().unwrap();
+        let mut deleted = 0;
+        let mut index = 0;
+        while index < documents.len() {
+            let record: T = from_document(documents[index].clone())?;
+            if filter(&record) {
+                documents.remove(index);
+                deleted += 1;
+                if single {
+                    break;
+                }
+            } else {
+                index += 1;
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+/// Orders values the way MongoDB sorts them: by type first (null, numbers, strings, object
+/// ids, booleans, dates), then by value.
+pub fn compare_bson(a: &Bson, b: &Bson) -> Ordering {
+    fn rank(value: &Bson) -> u8 {
+        match value {
+            Bson::Null | Bson::Undefined => 0,
+            Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) => 1,
+            Bson::String(_) => 2,
+            Bson::ObjectId(_) => 3,
+            Bson::Boolean(_) => 4,
+            Bson::DateTime(_) => 5,
+            _ => 6,
+        }
+    }
+    fn number(value: &Bson) -> f64 {
+        match value {
+            Bson::Int32(value) => *value as f64,
+            Bson::Int64(value) => *value as f64,
+            Bson::Double(value) => *value,
+            _ => 0.0,
+        }
+    }
+
+    match (a, b) {
+        (Bson::String(a), Bson::String(b)) => a.cmp(b),
+        (Bson::ObjectId(a), Bson::ObjectId(b)) => a.cmp(b),
+        (Bson::Boolean(a), Bson::Boolean(b)) => a.cmp(b),
+        (Bson::DateTime(a), Bson::DateTime(b)) => a.cmp(b),
+        _ if rank(a) == 1 && rank(b) == 1 => number(a).total_cmp(&number(b)),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
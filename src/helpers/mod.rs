@@ -1,2 +1,11 @@
 pub mod object_id_helper;
 pub mod api_response;
+pub mod pagination_helper;
+pub mod time_zone_helper;
+pub mod token_helper;
+#[cfg(test)]
+pub mod memory_collection_helper;
+pub mod mongo_error_helper;
+pub mod date_helper;
+pub mod nullable_helper;
+pub mod json_helper;
@@ -0,0 +1,26 @@
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+
+const DUPLICATE_KEY: i32 = 11000;
+
+/// Whether the write was rejected by a unique index.
+pub fn is_duplicate_key(err: &Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY
+        }
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
+
+/// For `map_err`: turns a unique index violation into `duplicate`, and any other error into
+/// the service error wrapping it.
+pub fn duplicate_key_as<E: From<Error>>(duplicate: E) -> impl FnOnce(Error) -> E {
+    move |err| {
+        if is_duplicate_key(&err) {
+            duplicate
+        } else {
+            E::from(err)
+        }
+    }
+}
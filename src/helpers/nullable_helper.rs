@@ -0,0 +1,13 @@
+use serde::{Deserialize, Deserializer};
+
+/// Deserializes an `Option<Option<T>>` update field so that `null` clears the value.
+///
+/// Use together with `#[serde(default)]`: a missing field stays `None`, `null` becomes
+/// `Some(None)` and any other value becomes `Some(Some(value))`.
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
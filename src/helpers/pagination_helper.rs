@@ -0,0 +1,82 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum SortDirection {
+    #[default]
+    #[serde(rename = "asc")]
+    Asc,
+    #[serde(rename = "desc")]
+    Desc,
+}
+
+impl SortDirection {
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            SortDirection::Asc => 1,
+            SortDirection::Desc => -1,
+        }
+    }
+}
+
+/// Position after the last item of a page: the field the list is sorted on, its value and the
+/// `_id` tie-breaker.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub sort: String,
+    pub value: Bson,
+    pub id: ObjectId,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let document = doc! { "s": &self.sort, "v": self.value.clone(), "id": self.id };
+        let mut bytes = Vec::new();
+        document
+            .to_writer(&mut bytes)
+            .expect("Cursor document is always serializable");
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Decodes a cursor for a list sorted on `sort`. Cursors from a list sorted on another
+    /// field would skip or repeat items, so they are rejected like malformed ones.
+    pub fn decode(cursor: &str, sort: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let document = Document::from_reader(bytes.as_slice()).ok()?;
+        if document.get_str("s").ok()? != sort {
+            return None;
+        }
+        Some(Self {
+            sort: sort.to_string(),
+            value: document.get("v")?.clone(),
+            id: document.get_object_id("id").ok()?,
+        })
+    }
+
+    /// Filter matching every document that sorts after this cursor.
+    pub fn after_filter(&self, field: &str, direction: SortDirection) -> Document {
+        let operator = match direction {
+            SortDirection::Asc => "$gt",
+            SortDirection::Desc => "$lt",
+        };
+        doc! {
+            "$or": [
+                { field: { operator: self.value.clone() } },
+                { field: self.value.clone(), "_id": { operator: self.id } },
+            ]
+        }
+    }
+}
+
+/// Escapes a user-supplied string so it is matched literally inside a `$regex`.
+pub fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+
+/// Zone of new accounts that do not choose one.
+pub fn default_time_zone() -> Tz {
+    Tz::UTC
+}
+
+/// Zone every date was read in before users had their own; older accounts keep it.
+pub fn legacy_time_zone() -> Tz {
+    Tz::America__Sao_Paulo
+}
+
+#[derive(Deserialize)]
+pub struct TimeZoneQuery {
+    #[serde(default)]
+    pub local: bool,
+}
+
+/// A date sent by a client: either an absolute instant (with offset) or a wall-clock time
+/// to be read in the user's time zone.
+#[derive(Debug, Clone, Copy)]
+pub enum DateTimeInput {
+    Absolute(DateTime<FixedOffset>),
+    Local(NaiveDateTime),
+}
+
+impl DateTimeInput {
+    pub fn to_utc(self, tz: &Tz) -> DateTime<Utc> {
+        match self {
+            DateTimeInput::Absolute(date) => date.with_timezone(&Utc),
+            DateTimeInput::Local(naive) => local_to_utc(naive, tz),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTimeInput {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        if let Ok(date) = DateTime::parse_from_rfc3339(&value) {
+            return Ok(DateTimeInput::Absolute(date));
+        }
+
+        NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M"))
+            .map(DateTimeInput::Local)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Resolves a wall-clock time in `tz` to UTC. Ambiguous times (DST fall-back) use the
+/// earlier instant; times skipped by a DST jump are moved forward past the gap.
+pub fn local_to_utc(naive: NaiveDateTime, tz: &Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(date) => date.with_timezone(&Utc),
+        LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+        LocalResult::None => {
+            let mut shifted = naive;
+            loop {
+                shifted += Duration::minutes(15);
+                if let Some(date) = tz.from_local_datetime(&shifted).earliest() {
+                    return date.with_timezone(&Utc);
+                }
+            }
+        }
+    }
+}
+
+pub fn to_zone(date: DateTime<Utc>, tz: Option<&Tz>) -> DateTime<FixedOffset> {
+    match tz {
+        Some(tz) => date.with_timezone(tz).fixed_offset(),
+        None => date.fixed_offset(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn naive(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        naive(y, m, d, h, min).and_utc()
+    }
+
+    #[test]
+    fn times_skipped_by_dst_move_past_the_gap() {
+        // 02:30 does not exist in New York on 2024-03-10; clocks jump to 03:00 EDT.
+        assert_eq!(
+            local_to_utc(naive(2024, 3, 10, 2, 30), &Tz::America__New_York),
+            utc(2024, 3, 10, 7, 0)
+        );
+        // Sao Paulo skipped midnight when it still had DST.
+        assert_eq!(
+            local_to_utc(naive(2018, 11, 4, 0, 30), &legacy_time_zone()),
+            utc(2018, 11, 4, 3, 0)
+        );
+    }
+
+    #[test]
+    fn ambiguous_times_use_the_earlier_instant() {
+        // 01:30 happens twice in New York on 2024-11-03, first in EDT (UTC-4).
+        assert_eq!(
+            local_to_utc(naive(2024, 11, 3, 1, 30), &Tz::America__New_York),
+            utc(2024, 11, 3, 5, 30)
+        );
+    }
+
+    #[test]
+    fn offsets_follow_the_date() {
+        let tz = Tz::Europe__Berlin;
+        assert_eq!(
+            local_to_utc(naive(2024, 1, 15, 9, 0), &tz),
+            utc(2024, 1, 15, 8, 0)
+        );
+        assert_eq!(
+            local_to_utc(naive(2024, 7, 15, 9, 0), &tz),
+            utc(2024, 7, 15, 7, 0)
+        );
+        assert_eq!(
+            to_zone(utc(2024, 7, 15, 7, 0), Some(&tz)).to_rfc3339(),
+            "2024-07-15T09:00:00+02:00"
+        );
+    }
+
+    #[test]
+    fn absolute_inputs_ignore_the_zone() {
+        let input: DateTimeInput =
+            serde_json::from_value(serde_json::json!("2024-03-10T02:30:00-05:00")).unwrap();
+        assert_eq!(
+            input.to_utc(&Tz::America__New_York),
+            utc(2024, 3, 10, 7, 30)
+        );
+    }
+}
@@ -1,14 +1,29 @@
 mod config;
 mod helpers;
+mod migrations;
 mod modules;
 
 use axum::{extract::Json, routing::get, Router};
 use dotenv::dotenv;
 use env_logger::Env;
-use log::info;
-use modules::{auth, category, notification, goal, task::{self, repository::TaskRepository}, user};
+use log::{error, info};
+use modules::{
+    admin, archive,
+    audit::{self, repository::MongoAuditRepository},
+    auth, calendar, category, goal,
+    mail::mailer::{self, Mailer},
+    notification::{self, channels::NotificationDispatcher},
+    rate_limit::limiter::{self, RateLimitStore},
+    task, user, workspace,
+};
+use config::{
+    app::AppConfig,
+    repositories::Repositories,
+};
+use migrations::migrator::{self, Migrator};
 use mongodb::Database;
 use std::env;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
@@ -16,7 +31,11 @@ use tower_http::cors::CorsLayer;
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
 struct AppState {
+    config: AppConfig,
     mongodb: Database,
+    mailer: Arc<dyn Mailer>,
+    rate_limits: Arc<dyn RateLimitStore>,
+    repositories: Repositories,
 }
 
 #[tokio::main]
@@ -24,36 +43,109 @@ async fn main() {
     dotenv().ok();
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
-    let app_host: String = env::var("APP_HOST").unwrap_or_else(|_| String::from("127.0.0.1:8080"));
-    let mongodb = config::mongodb::get_database().await;
+    let config = AppConfig::load().expect("Invalid configuration");
+    let mongodb = config::mongodb::get_database(&config.mongodb).await;
+
+    let migrator = Migrator::new(&mongodb, migrator::migrations());
+    // `planit migrate-down <version>` reverts the migrations newer than `version` and exits.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let [command, version] = args.as_slice() {
+        if command == "migrate-down" {
+            let version = version.parse().expect("The target migration must be a number");
+            let reverted = migrator
+                .down_to(version)
+                .await
+                .expect("Failed to revert database migrations");
+            info!("Reverted migrations {:?}", reverted);
+            return;
+        }
+    }
+    let applied = migrator
+        .up()
+        .await
+        .expect("Failed to run database migrations");
+    if !applied.is_empty() {
+        info!("Applied migrations {:?}", applied);
+    }
+
+    // After the migrations, which resolve the duplicates older releases let through.
+    config::mongodb::ensure_indexes(&mongodb)
+        .await
+        .expect("Failed to create MongoDB indexes");
+
+    let mailer = mailer::from_config(&config.smtp, config.environment)
+        .expect("Failed to configure mail delivery");
+
+    let rate_limits = limiter::from_config(config.rate_limit.store, &mongodb);
+
+    let repositories = Repositories::mongodb(&mongodb);
+
+    MongoAuditRepository::new(&mongodb)
+        .ensure_retention(config.audit.retention())
+        .await
+        .expect("Failed to set up audit log retention");
+
+    let host = config.host.clone();
+    let scheduler_config = config.clone();
+    let state = Arc::new(AppState {
+        config,
+        mongodb,
+        mailer,
+        rate_limits,
+        repositories: repositories.clone(),
+    });
+    if let Err(err) = admin::handlers::admin_service(&state)
+        .promote_admins(&state.config.admin.emails)
+        .await
+    {
+        error!("Failed to promote the configured administrators: {}", err);
+    }
+
+    let deletions = user::handlers::account_deletion_service(&state);
+    tokio::spawn(async move { deletions.finish_pending_deletions().await });
 
-    let state = Arc::new(AppState { mongodb });
     let app = Router::new()
         .route(
             "/",
             get(|| async { Json(format!("PlanIt v{}", VERSION.unwrap_or("unknown"))) }),
         )
-        .nest("/", auth::handles())
-        .nest("/", user::handles())
-        .nest("/", category::handles())
-        .nest("/", goal::handles())
-        .nest("/", task::handles())
-        .nest("/", notification::handles())
+        .nest("/", auth::handles(state.clone()))
+        .nest("/", user::handles(state.clone()))
+        .nest("/", category::handles(state.clone()))
+        .nest("/", goal::handles(state.clone()))
+        .nest("/", task::handles(state.clone()))
+        .nest("/", notification::handles(state.clone()))
+        .nest("/", calendar::handles(state.clone()))
+        .nest("/", archive::handles(state.clone()))
+        .nest("/", admin::handles(state.clone()))
+        .nest("/", workspace::handles(state.clone()))
+        .nest("/", audit::handles(state.clone()))
         .layer(CorsLayer::permissive())
         .with_state(state);
 
-    let listener = TcpListener::bind(app_host)
+    let listener = TcpListener::bind(host)
         .await
         .expect("Unable to connect to the server");
 
     info!("Web Server running at {}", listener.local_addr().unwrap());
 
-    tokio::spawn(async {
-        let task_repository = TaskRepository::new(&config::mongodb::get_database().await);
-        notification::scheduler::boot(&task_repository).await;
+    tokio::spawn(async move {
+        let database = config::mongodb::get_database(&scheduler_config.mongodb).await;
+        let dispatcher = NotificationDispatcher::from_config(&scheduler_config.smtp, &database)
+            .expect("Failed to configure notification channels");
+        notification::scheduler::boot(
+            repositories.tasks.as_ref(),
+            repositories.users.as_ref(),
+            &dispatcher,
+        )
+        .await;
     });
 
-    axum::serve(listener, app)
+    // Peer addresses are needed to rate limit by client IP.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
         .await
         .expect("Error serving application");
 }
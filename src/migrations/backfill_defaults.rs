@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Bson, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use super::migration::Migration;
+
+/// Writes the fields added to tasks, goals and categories after their first release, which
+/// older documents only get through `#[serde(default)]` when read.
+pub struct BackfillDefaults;
+
+fn defaults() -> Vec<(&'static str, Vec<(&'static str, Bson)>)> {
+    vec![
+        (
+            "tasks",
+            vec![
+                ("workspace_id", Bson::Null),
+                ("assignee_id", Bson::Null),
+                ("goal_id", Bson::Null),
+                ("recurrence", Bson::Null),
+                ("occurrence_overrides", Bson::Array(Vec::new())),
+                ("subtasks", Bson::Array(Vec::new())),
+                ("derive_status", Bson::Boolean(false)),
+            ],
+        ),
+        (
+            "goals",
+            vec![
+                ("workspace_id", Bson::Null),
+                ("auto_status", Bson::Boolean(false)),
+            ],
+        ),
+        ("categories", vec![("workspace_id", Bson::Null)]),
+    ]
+}
+
+#[async_trait]
+impl Migration for BackfillDefaults {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "backfill_defaults"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        for (collection, fields) in defaults() {
+            let collection = db.collection::<Document>(collection);
+            for (field, value) in fields {
+                collection
+                    .update_many(
+                        doc! { field: { "$exists": false } },
+                        doc! { "$set": { field: value } },
+                    )
+                    .await?;
+            }
+        }
+
+        db.collection::<Document>("tasks")
+            .update_many(
+                doc! {
+                    "notification": { "$type": "object" },
+                    "notification.viewed": { "$exists": false },
+                },
+                doc! { "$set": { "notification.viewed": false } },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Removes the fields that hold their default, which read back the same.
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        for (collection, fields) in defaults() {
+            let collection = db.collection::<Document>(collection);
+            for (field, value) in fields {
+                collection
+                    .update_many(doc! { field: value }, doc! { "$unset": { field: "" } })
+                    .await?;
+            }
+        }
+
+        db.collection::<Document>("tasks")
+            .update_many(
+                doc! { "notification.viewed": false },
+                doc! { "$unset": { "notification.viewed": "" } },
+            )
+            .await?;
+
+        Ok(())
+    }
+}
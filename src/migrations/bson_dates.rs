@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use super::migration::Migration;
+
+/// Converts the dates of tasks and goals from RFC 3339 strings to BSON dates, so range
+/// queries compare instants instead of strings.
+pub struct BsonDates;
+
+/// RFC 3339 in UTC with the milliseconds BSON dates keep, for `down`. The strings written
+/// before this migration only had fractional seconds when there were some; both parse back.
+const STRING_FORMAT: &str = "%Y-%m-%dT%H:%M:%S.%LZ";
+
+const FIELDS: [(&str, &str); 5] = [
+    ("tasks", "start_date"),
+    ("tasks", "end_date"),
+    ("tasks", "notification.scheduled_time"),
+    ("tasks", "recurrence.until"),
+    ("goals", "end_date"),
+];
+
+fn to_string(value: &str) -> Document {
+    doc! { "$dateToString": { "format": STRING_FORMAT, "date": value } }
+}
+
+#[async_trait]
+impl Migration for BsonDates {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "bson_dates"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        for (collection, field) in FIELDS {
+            db.collection::<Document>(collection)
+                .update_many(
+                    doc! { field: { "$type": "string" } },
+                    vec![doc! { "$set": { field: { "$toDate": format!("${}", field) } } }],
+                )
+                .await?;
+        }
+
+        db.collection::<Document>("tasks")
+            .update_many(
+                doc! { "occurrence_overrides.occurrence_start": { "$type": "string" } },
+                vec![doc! { "$set": { "occurrence_overrides": { "$map": {
+                    "input": "$occurrence_overrides",
+                    "as": "override",
+                    "in": { "$mergeObjects": [
+                        "$$override",
+                        { "occurrence_start": { "$toDate": "$$override.occurrence_start" } },
+                    ] },
+                } } } }],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        for (collection, field) in FIELDS {
+            db.collection::<Document>(collection)
+                .update_many(
+                    doc! { field: { "$type": "date" } },
+                    vec![doc! { "$set": { field: to_string(&format!("${}", field)) } }],
+                )
+                .await?;
+        }
+
+        db.collection::<Document>("tasks")
+            .update_many(
+                doc! { "occurrence_overrides.occurrence_start": { "$type": "date" } },
+                vec![doc! { "$set": { "occurrence_overrides": { "$map": {
+                    "input": "$occurrence_overrides",
+                    "as": "override",
+                    "in": { "$mergeObjects": [
+                        "$$override",
+                        { "occurrence_start": { "$cond": [
+                            { "$eq": [{ "$type": "$$override.occurrence_start" }, "date"] },
+                            to_string("$$override.occurrence_start"),
+                            "$$override.occurrence_start",
+                        ] } },
+                    ] },
+                } } } }],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
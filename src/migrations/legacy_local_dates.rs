@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, doc, oid::ObjectId, Bson, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use crate::helpers::date_helper::to_bson_datetime;
+use crate::helpers::time_zone_helper::{legacy_time_zone, local_to_utc};
+
+use super::migration::Migration;
+
+/// Before dates were zone-aware, tasks and goals stored São Paulo wall-clock times labeled
+/// as UTC. This stores the instants they meant. Only documents created before the first
+/// zone-aware release started (when `user_time_zones` was applied) are converted.
+pub struct LegacyLocalDates;
+
+const FIELDS: [(&str, &[&str]); 2] = [
+    (
+        "tasks",
+        &["start_date", "end_date", "notification.scheduled_time"],
+    ),
+    ("goals", &["end_date"]),
+];
+
+const USER_TIME_ZONES_VERSION: i64 = 4;
+
+/// The instant a legacy date meant: its UTC label read as wall-clock time in the legacy zone.
+fn to_utc(date: DateTime<Utc>) -> DateTime<Utc> {
+    local_to_utc(date.naive_utc(), &legacy_time_zone())
+}
+
+/// The legacy label of an instant, for `down`.
+fn to_legacy(date: DateTime<Utc>) -> DateTime<Utc> {
+    date.with_timezone(&legacy_time_zone())
+        .naive_local()
+        .and_utc()
+}
+
+fn get_date(document: &Document, path: &str) -> Option<DateTime<Utc>> {
+    let mut value = document;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        if keys.peek().is_none() {
+            return value
+                .get_datetime(key)
+                .ok()
+                .and_then(|date| DateTime::from_timestamp_millis(date.timestamp_millis()));
+        }
+        value = value.get_document(key).ok()?;
+    }
+    None
+}
+
+/// The `$set` converting the dates at `fields` of `document`; fields that are missing or not
+/// dates are left alone.
+fn changes(
+    document: &Document,
+    fields: &[&str],
+    convert: fn(DateTime<Utc>) -> DateTime<Utc>,
+) -> Document {
+    let mut changes = Document::new();
+    for field in fields {
+        if let Some(date) = get_date(document, field) {
+            changes.insert(*field, to_bson_datetime(convert(date)));
+        }
+    }
+    changes
+}
+
+/// Ids of documents created before the first zone-aware release started are lower than this.
+async fn legacy_id_bound(db: &Database) -> Result<ObjectId, Error> {
+    let started = db
+        .collection::<Document>("_migrations")
+        .find_one(doc! { "_id": USER_TIME_ZONES_VERSION })
+        .await?
+        .and_then(|applied| applied.get_datetime("applied_at").ok().copied())
+        .unwrap_or_else(bson::DateTime::now);
+    let mut bytes = [0; 12];
+    bytes[..4].copy_from_slice(&((started.timestamp_millis() / 1000) as u32).to_be_bytes());
+    Ok(ObjectId::from_bytes(bytes))
+}
+
+async fn convert_all(
+    db: &Database,
+    convert: fn(DateTime<Utc>) -> DateTime<Utc>,
+) -> Result<(), Error> {
+    let bound = legacy_id_bound(db).await?;
+    for (collection, fields) in FIELDS {
+        let collection = db.collection::<Document>(collection);
+        let projection: Document = fields
+            .iter()
+            .map(|field| (field.to_string(), Bson::Int32(1)))
+            .collect();
+        let mut cursor = collection
+            .find(doc! { "_id": { "$lt": bound } })
+            .projection(projection)
+            .await?;
+        while cursor.advance().await? {
+            let document = cursor.deserialize_current()?;
+            let Ok(id) = document.get_object_id("_id") else {
+                continue;
+            };
+            let changes = changes(&document, fields, convert);
+            if changes.is_empty() {
+                continue;
+            }
+            collection
+                .update_one(doc! { "_id": id }, doc! { "$set": changes })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Migration for LegacyLocalDates {
+    fn version(&self) -> u32 {
+        7
+    }
+
+    fn name(&self) -> &'static str {
+        "legacy_local_dates"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        convert_all(db, to_utc).await
+    }
+
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        convert_all(db, to_legacy).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn legacy_task_dates_become_the_instants_they_meant() {
+        // Written by the baseline for a task at 10:00 São Paulo time, with a reminder at 9:00.
+        let document = doc! {
+            "_id": ObjectId::new(),
+            "title": "Report",
+            "start_date": to_bson_datetime(date(2024, 3, 2, 10)),
+            "end_date": to_bson_datetime(date(2024, 3, 2, 11)),
+            "notification": {
+                "time_unit": "HOURS",
+                "time_value": 1,
+                "scheduled_time": to_bson_datetime(date(2024, 3, 2, 9)),
+                "sent": false,
+            },
+        };
+
+        let changes = changes(&document, FIELDS[0].1, to_utc);
+        assert_eq!(
+            changes,
+            doc! {
+                "start_date": to_bson_datetime(date(2024, 3, 2, 13)),
+                "end_date": to_bson_datetime(date(2024, 3, 2, 14)),
+                "notification.scheduled_time": to_bson_datetime(date(2024, 3, 2, 12)),
+            }
+        );
+    }
+
+    #[test]
+    fn daylight_saving_offsets_of_the_time_are_used() {
+        // São Paulo kept daylight saving time, at UTC-2, until 2019.
+        assert_eq!(to_utc(date(2018, 12, 1, 10)), date(2018, 12, 1, 12));
+        assert_eq!(to_legacy(date(2018, 12, 1, 12)), date(2018, 12, 1, 10));
+        assert_eq!(
+            to_legacy(to_utc(date(2024, 3, 2, 10))),
+            date(2024, 3, 2, 10)
+        );
+    }
+
+    #[test]
+    fn missing_dates_are_left_alone() {
+        let document = doc! { "_id": ObjectId::new(), "end_date": Bson::Null };
+        assert!(changes(&document, FIELDS[1].1, to_utc).is_empty());
+    }
+}
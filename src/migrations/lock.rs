@@ -0,0 +1,118 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::{info, warn};
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::error::Error;
+use mongodb::{Collection, Database};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::helpers::mongo_error_helper::is_duplicate_key;
+
+const LOCK_ID: &str = "migrations";
+// A lock left behind by an instance that died while migrating is taken over after this long.
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+// The holder renews the lock well within `STALE_AFTER`, so a long migration keeps it.
+const RENEW_EVERY: Duration = Duration::from_secs(60);
+const RETRY_EVERY: Duration = Duration::from_secs(1);
+
+/// Makes sure a single instance migrates the database at a time.
+#[async_trait]
+pub trait MigrationLock: Send + Sync {
+    /// Waits until no other instance holds the lock, then takes it.
+    async fn acquire(&self) -> Result<(), Error>;
+
+    /// Gives the lock up, unless another instance has taken it over since.
+    async fn release(&self) -> Result<(), Error>;
+}
+
+pub struct MongoMigrationLock {
+    collection: Collection<Document>,
+    owner: ObjectId,
+    renewal: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MongoMigrationLock {
+    pub fn new(db: &Database) -> Self {
+        MongoMigrationLock {
+            collection: db.collection("_migrations_lock"),
+            owner: ObjectId::new(),
+            renewal: Mutex::new(None),
+        }
+    }
+
+    /// Keeps `locked_at` fresh until the lock is released, so other instances do not take
+    /// it over as stale while a migration is still running.
+    fn start_renewal(&self) {
+        let collection = self.collection.clone();
+        let owner = self.owner;
+        let renewal = tokio::spawn(async move {
+            loop {
+                sleep(RENEW_EVERY).await;
+                match collection
+                    .update_one(
+                        doc! { "_id": LOCK_ID, "owner": owner },
+                        doc! { "$set": { "locked_at": DateTime::now() } },
+                    )
+                    .await
+                {
+                    Ok(result) if result.matched_count == 0 => {
+                        warn!("The migration lock was taken over by another instance");
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(err) => warn!("Failed to renew the migration lock: {}", err),
+                }
+            }
+        });
+        *self.renewal.lock().unwrap() = Some(renewal);
+    }
+}
+
+#[async_trait]
+impl MigrationLock for MongoMigrationLock {
+    async fn acquire(&self) -> Result<(), Error> {
+        let mut waiting = false;
+        loop {
+            let now = DateTime::now();
+            let stale =
+                DateTime::from_millis(now.timestamp_millis() - STALE_AFTER.as_millis() as i64);
+            // Only a missing or stale lock matches; the upsert of a held one hits a duplicate key.
+            let result = self
+                .collection
+                .update_one(
+                    doc! { "_id": LOCK_ID, "locked_at": { "$lt": stale } },
+                    doc! { "$set": { "owner": self.owner, "locked_at": now } },
+                )
+                .upsert(true)
+                .await;
+
+            match result {
+                Ok(_) => {
+                    self.start_renewal();
+                    return Ok(());
+                }
+                Err(err) if is_duplicate_key(&err) => {
+                    if !waiting {
+                        info!("Waiting for another instance to finish migrating the database");
+                        waiting = true;
+                    }
+                    sleep(RETRY_EVERY).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn release(&self) -> Result<(), Error> {
+        if let Some(renewal) = self.renewal.lock().unwrap().take() {
+            renewal.abort();
+        }
+        self.collection
+            .delete_one(doc! { "_id": LOCK_ID, "owner": self.owner })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use super::migration::Migration;
+
+/// Failed logins used to be counted per email, in documents keyed by the address; they are
+/// now counted per email and client IP. The counters only matter for a day, so the old ones
+/// are dropped rather than converted.
+pub struct LoginAttemptsByIp;
+
+#[async_trait]
+impl Migration for LoginAttemptsByIp {
+    fn version(&self) -> u32 {
+        6
+    }
+
+    fn name(&self) -> &'static str {
+        "login_attempts_by_ip"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("login_attempts")
+            .delete_many(doc! { "ip": { "$exists": false } })
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("login_attempts")
+            .delete_many(doc! { "ip": { "$exists": true } })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use mongodb::error::Error;
+
+use super::lock::MigrationLock;
+use super::migration::Migration;
+use super::repository::MigrationRepository;
+
+/// Applied versions kept in memory, in the order they were recorded.
+#[derive(Default)]
+pub struct InMemoryMigrationRepository {
+    versions: Mutex<Vec<u32>>,
+}
+
+impl InMemoryMigrationRepository {
+    pub fn with_versions(versions: &[u32]) -> Self {
+        InMemoryMigrationRepository {
+            versions: Mutex::new(versions.to_vec()),
+        }
+    }
+}
+
+#[async_trait]
+impl MigrationRepository for InMemoryMigrationRepository {
+    async fn applied_versions(&self) -> Result<Vec<u32>, Error> {
+        let mut versions = self.versions.lock().unwrap().clone();
+        versions.sort();
+        Ok(versions)
+    }
+
+    async fn record(&self, migration: &dyn Migration) -> Result<(), Error> {
+        self.versions.lock().unwrap().push(migration.version());
+        Ok(())
+    }
+
+    async fn remove(&self, version: u32) -> Result<bool, Error> {
+        let mut versions = self.versions.lock().unwrap();
+        let before = versions.len();
+        versions.retain(|applied| *applied != version);
+        Ok(versions.len() < before)
+    }
+}
+
+/// A lock for a single process; taking it twice is a bug in the migrator.
+#[derive(Default)]
+pub struct InMemoryMigrationLock {
+    held: Mutex<bool>,
+}
+
+impl InMemoryMigrationLock {
+    pub fn is_held(&self) -> bool {
+        *self.held.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl MigrationLock for InMemoryMigrationLock {
+    async fn acquire(&self) -> Result<(), Error> {
+        let mut held = self.held.lock().unwrap();
+        assert!(!*held, "the migration lock is already held");
+        *held = true;
+        Ok(())
+    }
+
+    async fn release(&self) -> Result<(), Error> {
+        *self.held.lock().unwrap() = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use mongodb::{error::Error, Database};
+
+/// A change to stored documents, applied once per database in `version` order.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// Unique and increasing; a released version is never renumbered or reused.
+    fn version(&self) -> u32;
+
+    fn name(&self) -> &'static str;
+
+    async fn up(&self, db: &Database) -> Result<(), Error>;
+
+    /// Undoes `up`, so the previous release can run against the database again.
+    async fn down(&self, db: &Database) -> Result<(), Error>;
+}
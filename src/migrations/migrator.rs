@@ -0,0 +1,305 @@
+use std::sync::Arc;
+
+use log::info;
+use mongodb::Database;
+use thiserror::Error;
+
+use super::backfill_defaults::BackfillDefaults;
+use super::bson_dates::BsonDates;
+use super::legacy_local_dates::LegacyLocalDates;
+use super::lock::{MigrationLock, MongoMigrationLock};
+use super::login_attempts_by_ip::LoginAttemptsByIp;
+use super::migration::Migration;
+use super::refresh_token_dates::RefreshTokenDates;
+use super::repository::{MigrationRepository, MongoMigrationRepository};
+use super::unique_keys::UniqueKeys;
+use super::user_contacts::UserContacts;
+use super::user_time_zones::UserTimeZones;
+
+/// Every migration of the application; new ones are added here.
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(BackfillDefaults),
+        Box::new(BsonDates),
+        Box::new(RefreshTokenDates),
+        Box::new(UserTimeZones),
+        Box::new(UserContacts),
+        Box::new(LoginAttemptsByIp),
+        Box::new(LegacyLocalDates),
+        Box::new(UniqueKeys),
+    ]
+}
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Migration {version} is applied but unknown to this build")]
+    UnknownMigration { version: u32 },
+
+    #[error("Migration {version} ({name}) failed: {source}")]
+    MigrationFailed {
+        version: u32,
+        name: &'static str,
+        source: mongodb::error::Error,
+    },
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct Migrator {
+    db: Database,
+    migrations: Vec<Box<dyn Migration>>,
+    repository: Arc<dyn MigrationRepository>,
+    lock: Arc<dyn MigrationLock>,
+}
+
+impl Migrator {
+    pub fn new(db: &Database, migrations: Vec<Box<dyn Migration>>) -> Self {
+        Migrator::with_store(
+            db,
+            migrations,
+            Arc::new(MongoMigrationRepository::new(db)),
+            Arc::new(MongoMigrationLock::new(db)),
+        )
+    }
+
+    /// Keeps track of applied migrations in `repository` instead of the `_migrations`
+    /// collection.
+    pub fn with_store(
+        db: &Database,
+        mut migrations: Vec<Box<dyn Migration>>,
+        repository: Arc<dyn MigrationRepository>,
+        lock: Arc<dyn MigrationLock>,
+    ) -> Self {
+        migrations.sort_by_key(|migration| migration.version());
+        Migrator {
+            db: db.clone(),
+            migrations,
+            repository,
+            lock,
+        }
+    }
+
+    /// Applies the migrations not applied yet, oldest first, and returns their versions.
+    pub async fn up(&self) -> Result<Vec<u32>, MigrationError> {
+        self.lock.acquire().await?;
+        let result = self.apply_pending().await;
+        let released = self.lock.release().await;
+        let applied = result?;
+        released?;
+        Ok(applied)
+    }
+
+    /// Reverts the applied migrations newer than `version`, newest first, and returns their
+    /// versions.
+    pub async fn down_to(&self, version: u32) -> Result<Vec<u32>, MigrationError> {
+        self.lock.acquire().await?;
+        let result = self.revert_after(version).await;
+        let released = self.lock.release().await;
+        let reverted = result?;
+        released?;
+        Ok(reverted)
+    }
+
+    async fn apply_pending(&self) -> Result<Vec<u32>, MigrationError> {
+        let applied = self.repository.applied_versions().await?;
+        let mut versions = Vec::new();
+
+        for migration in &self.migrations {
+            if applied.contains(&migration.version()) {
+                continue;
+            }
+            info!(
+                "Applying migration {} ({})",
+                migration.version(),
+                migration.name()
+            );
+            migration
+                .up(&self.db)
+                .await
+                .map_err(|source| MigrationError::MigrationFailed {
+                    version: migration.version(),
+                    name: migration.name(),
+                    source,
+                })?;
+            self.repository.record(migration.as_ref()).await?;
+            versions.push(migration.version());
+        }
+
+        Ok(versions)
+    }
+
+    async fn revert_after(&self, target: u32) -> Result<Vec<u32>, MigrationError> {
+        let applied = self.repository.applied_versions().await?;
+        let mut versions = Vec::new();
+
+        for version in applied
+            .into_iter()
+            .rev()
+            .filter(|version| *version > target)
+        {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|migration| migration.version() == version)
+                .ok_or(MigrationError::UnknownMigration { version })?;
+            info!("Reverting migration {} ({})", version, migration.name());
+            migration
+                .down(&self.db)
+                .await
+                .map_err(|source| MigrationError::MigrationFailed {
+                    version,
+                    name: migration.name(),
+                    source,
+                })?;
+            self.repository.remove(version).await?;
+            versions.push(version);
+        }
+
+        Ok(versions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use mongodb::Client;
+
+    use crate::migrations::memory::{InMemoryMigrationLock, InMemoryMigrationRepository};
+
+    use super::*;
+
+    /// Writes what it does to `log` instead of the database.
+    struct Step {
+        version: u32,
+        fails: bool,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Migration for Step {
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn name(&self) -> &'static str {
+            "step"
+        }
+
+        async fn up(&self, _db: &Database) -> Result<(), mongodb::error::Error> {
+            if self.fails {
+                return Err(std::io::Error::other("failed").into());
+            }
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("up {}", self.version));
+            Ok(())
+        }
+
+        async fn down(&self, _db: &Database) -> Result<(), mongodb::error::Error> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("down {}", self.version));
+            Ok(())
+        }
+    }
+
+    struct Fixture {
+        migrator: Migrator,
+        repository: Arc<InMemoryMigrationRepository>,
+        lock: Arc<InMemoryMigrationLock>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    /// A migrator over the migrations `versions` (failing for `failing`) on a database where
+    /// `applied` are applied. The client never connects: the steps do not use it.
+    async fn fixture(versions: &[u32], failing: Option<u32>, applied: &[u32]) -> Fixture {
+        let db = Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap()
+            .database("planit_test");
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let migrations = versions
+            .iter()
+            .map(|&version| {
+                Box::new(Step {
+                    version,
+                    fails: failing == Some(version),
+                    log: log.clone(),
+                }) as Box<dyn Migration>
+            })
+            .collect();
+        let repository = Arc::new(InMemoryMigrationRepository::with_versions(applied));
+        let lock = Arc::new(InMemoryMigrationLock::default());
+        Fixture {
+            migrator: Migrator::with_store(&db, migrations, repository.clone(), lock.clone()),
+            repository,
+            lock,
+            log,
+        }
+    }
+
+    #[tokio::test]
+    async fn up_applies_pending_migrations_oldest_first() {
+        let fixture = fixture(&[3, 1, 2], None, &[1]).await;
+
+        assert_eq!(fixture.migrator.up().await.unwrap(), vec![2, 3]);
+        assert_eq!(*fixture.log.lock().unwrap(), vec!["up 2", "up 3"]);
+        assert_eq!(
+            fixture.repository.applied_versions().await.unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(!fixture.lock.is_held());
+
+        assert!(fixture.migrator.up().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_migrations_stop_the_run_and_are_not_recorded() {
+        let fixture = fixture(&[1, 2, 3], Some(2), &[]).await;
+
+        assert!(matches!(
+            fixture.migrator.up().await,
+            Err(MigrationError::MigrationFailed { version: 2, .. })
+        ));
+        assert_eq!(*fixture.log.lock().unwrap(), vec!["up 1"]);
+        assert_eq!(
+            fixture.repository.applied_versions().await.unwrap(),
+            vec![1]
+        );
+        assert!(!fixture.lock.is_held());
+    }
+
+    #[tokio::test]
+    async fn down_to_reverts_newer_migrations_newest_first() {
+        let fixture = fixture(&[1, 2, 3], None, &[1, 2, 3]).await;
+
+        assert_eq!(fixture.migrator.down_to(1).await.unwrap(), vec![3, 2]);
+        assert_eq!(*fixture.log.lock().unwrap(), vec!["down 3", "down 2"]);
+        assert_eq!(
+            fixture.repository.applied_versions().await.unwrap(),
+            vec![1]
+        );
+        assert!(!fixture.lock.is_held());
+    }
+
+    #[tokio::test]
+    async fn down_to_refuses_versions_unknown_to_this_build() {
+        let fixture = fixture(&[1, 2], None, &[1, 2, 5]).await;
+
+        assert!(matches!(
+            fixture.migrator.down_to(1).await,
+            Err(MigrationError::UnknownMigration { version: 5 })
+        ));
+        assert!(fixture.log.lock().unwrap().is_empty());
+        assert_eq!(
+            fixture.repository.applied_versions().await.unwrap(),
+            vec![1, 2, 5]
+        );
+        assert!(!fixture.lock.is_held());
+    }
+}
@@ -0,0 +1,14 @@
+pub mod backfill_defaults;
+pub mod bson_dates;
+pub mod legacy_local_dates;
+pub mod lock;
+pub mod login_attempts_by_ip;
+#[cfg(test)]
+pub mod memory;
+pub mod migration;
+pub mod migrator;
+pub mod refresh_token_dates;
+pub mod repository;
+pub mod unique_keys;
+pub mod user_contacts;
+pub mod user_time_zones;
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use super::migration::Migration;
+
+/// Stores the expiry of refresh tokens as a BSON date, which the TTL index on
+/// `refresh_tokens.expires_at` needs to remove expired tokens.
+pub struct RefreshTokenDates;
+
+const STRING_FORMAT: &str = "%Y-%m-%dT%H:%M:%S.%LZ";
+
+#[async_trait]
+impl Migration for RefreshTokenDates {
+    fn version(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "refresh_token_dates"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("refresh_tokens")
+            .update_many(
+                doc! { "expires_at": { "$type": "string" } },
+                vec![doc! { "$set": { "expires_at": { "$toDate": "$expires_at" } } }],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("refresh_tokens")
+            .update_many(
+                doc! { "expires_at": { "$type": "date" } },
+                vec![doc! { "$set": { "expires_at": {
+                    "$dateToString": { "format": STRING_FORMAT, "date": "$expires_at" }
+                } } }],
+            )
+            .await?;
+        Ok(())
+    }
+}
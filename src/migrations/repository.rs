@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use mongodb::bson::{self, doc};
+use mongodb::error::Error;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+
+use super::migration::Migration;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    #[serde(rename = "_id")]
+    pub version: i64,
+    pub name: String,
+    pub applied_at: bson::DateTime,
+}
+
+/// The migrations applied to the database.
+#[async_trait]
+pub trait MigrationRepository: Send + Sync {
+    /// Versions applied to the database, oldest first.
+    async fn applied_versions(&self) -> Result<Vec<u32>, Error>;
+
+    async fn record(&self, migration: &dyn Migration) -> Result<(), Error>;
+
+    async fn remove(&self, version: u32) -> Result<bool, Error>;
+}
+
+pub struct MongoMigrationRepository {
+    collection: Collection<AppliedMigration>,
+}
+
+impl MongoMigrationRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("_migrations");
+        MongoMigrationRepository { collection }
+    }
+}
+
+#[async_trait]
+impl MigrationRepository for MongoMigrationRepository {
+    async fn applied_versions(&self) -> Result<Vec<u32>, Error> {
+        let mut cursor = self
+            .collection
+            .find(doc! {})
+            .sort(doc! { "_id": 1 })
+            .await?;
+        let mut versions = Vec::new();
+        while cursor.advance().await? {
+            versions.push(cursor.deserialize_current()?.version as u32);
+        }
+
+        Ok(versions)
+    }
+
+    async fn record(&self, migration: &dyn Migration) -> Result<(), Error> {
+        let applied = AppliedMigration {
+            version: migration.version() as i64,
+            name: migration.name().to_string(),
+            applied_at: bson::DateTime::now(),
+        };
+        self.collection.insert_one(applied).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, version: u32) -> Result<bool, Error> {
+        let result = self
+            .collection
+            .delete_one(doc! { "_id": version as i64 })
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use log::warn;
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use super::migration::Migration;
+
+/// Resolves the duplicates older releases let through, which would keep the unique indexes
+/// of `ensure_indexes` from being built: emails differing only in case, and task or category
+/// titles repeated in a scope. The first record keeps its value; the others get a distinct
+/// one, and each change is logged.
+pub struct UniqueKeys;
+
+/// Updates giving every account but one of each email a distinct address. A live account
+/// is kept over a deleted one, then the oldest.
+fn dedupe_emails(users: &[Document]) -> Vec<(ObjectId, Document)> {
+    let mut groups: HashMap<String, Vec<&Document>> = HashMap::new();
+    for user in users {
+        if let Ok(email) = user.get_str("email") {
+            groups.entry(email.to_lowercase()).or_default().push(user);
+        }
+    }
+
+    let mut updates = Vec::new();
+    for (email, mut group) in groups {
+        if group.len() < 2 {
+            continue;
+        }
+        group.sort_by_key(|user| {
+            (
+                user.get_bool("deleted").unwrap_or(false),
+                user.get_object_id("_id").ok(),
+            )
+        });
+        for user in &group[1..] {
+            let Ok(id) = user.get_object_id("_id") else {
+                continue;
+            };
+            let renamed = match email.split_once('@') {
+                Some((local, domain)) => format!("{}+duplicate-{}@{}", local, id, domain),
+                None => format!("{}+duplicate-{}", email, id),
+            };
+            updates.push((id, doc! { "email": renamed }));
+        }
+    }
+    updates
+}
+
+/// Updates giving every task or category but the first of each title in its scope a
+/// numbered title. Shared records are scoped by workspace, personal ones by user.
+fn dedupe_titles(records: &[Document]) -> Vec<(ObjectId, Document)> {
+    let scope = |record: &Document| match record.get("workspace_id") {
+        Some(Bson::ObjectId(workspace_id)) => format!("workspace {}", workspace_id),
+        _ => format!("user {}", record.get("user_id").unwrap_or(&Bson::Null)),
+    };
+
+    let mut taken: HashSet<(String, String)> = records
+        .iter()
+        .filter_map(|record| Some((scope(record), record.get_str("title").ok()?.to_string())))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut updates = Vec::new();
+    for record in records {
+        let (Ok(id), Ok(title)) = (record.get_object_id("_id"), record.get_str("title")) else {
+            continue;
+        };
+        let scope = scope(record);
+        if seen.insert((scope.clone(), title.to_string())) {
+            continue;
+        }
+        let renamed = (2..)
+            .map(|number| format!("{} ({})", title, number))
+            .find(|candidate| !taken.contains(&(scope.clone(), candidate.clone())))
+            .unwrap();
+        taken.insert((scope.clone(), renamed.clone()));
+        seen.insert((scope, renamed.clone()));
+        updates.push((id, doc! { "title": renamed }));
+    }
+    updates
+}
+
+async fn find_all(
+    db: &Database,
+    collection: &str,
+    fields: &[&str],
+) -> Result<Vec<Document>, Error> {
+    let projection: Document = fields
+        .iter()
+        .map(|field| (field.to_string(), Bson::Int32(1)))
+        .collect();
+    let mut cursor = db
+        .collection::<Document>(collection)
+        .find(doc! {})
+        .projection(projection)
+        .sort(doc! { "_id": 1 })
+        .await?;
+    let mut documents = Vec::new();
+    while cursor.advance().await? {
+        documents.push(cursor.deserialize_current()?);
+    }
+    Ok(documents)
+}
+
+async fn apply(
+    db: &Database,
+    collection: &str,
+    updates: Vec<(ObjectId, Document)>,
+) -> Result<(), Error> {
+    for (id, changes) in updates {
+        warn!(
+            "Duplicate in {} resolved: {} now has {}",
+            collection, id, changes
+        );
+        db.collection::<Document>(collection)
+            .update_one(doc! { "_id": id }, doc! { "$set": changes })
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Migration for UniqueKeys {
+    fn version(&self) -> u32 {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "unique_keys"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        let users = find_all(db, "users", &["email", "deleted"]).await?;
+        apply(db, "users", dedupe_emails(&users)).await?;
+
+        for collection in ["tasks", "categories"] {
+            let records = find_all(db, collection, &["user_id", "workspace_id", "title"]).await?;
+            apply(db, collection, dedupe_titles(&records)).await?;
+        }
+        Ok(())
+    }
+
+    /// The duplicates are not restored; the distinct values read back like any other.
+    async fn down(&self, _db: &Database) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails_differing_in_case_are_renamed_but_one() {
+        let first = ObjectId::new();
+        let second = ObjectId::new();
+        let deleted = ObjectId::new();
+        let other = ObjectId::new();
+        // As left by `user_contacts`, which lowercases emails before the index exists.
+        let users = vec![
+            doc! { "_id": deleted, "email": "ana@example.com", "deleted": true },
+            doc! { "_id": first, "email": "ana@example.com" },
+            doc! { "_id": second, "email": "Ana@Example.com" },
+            doc! { "_id": other, "email": "bia@example.com" },
+        ];
+
+        let mut updates = dedupe_emails(&users);
+        updates.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            updates,
+            vec![
+                (
+                    second,
+                    doc! { "email": format!("ana+duplicate-{}@example.com", second) }
+                ),
+                (
+                    deleted,
+                    doc! { "email": format!("ana+duplicate-{}@example.com", deleted) }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn titles_repeated_in_a_scope_are_numbered() {
+        let user_id = ObjectId::new();
+        let other_user_id = ObjectId::new();
+        let workspace_id = ObjectId::new();
+        let ids: Vec<ObjectId> = (0..6).map(|_| ObjectId::new()).collect();
+        let records = vec![
+            doc! { "_id": ids[0], "user_id": user_id, "workspace_id": null, "title": "Work" },
+            doc! { "_id": ids[1], "user_id": user_id, "title": "Work" },
+            doc! { "_id": ids[2], "user_id": user_id, "workspace_id": null, "title": "Work (2)" },
+            doc! { "_id": ids[3], "user_id": other_user_id, "workspace_id": null, "title": "Work" },
+            doc! { "_id": ids[4], "user_id": user_id, "workspace_id": workspace_id, "title": "Work" },
+            doc! { "_id": ids[5], "user_id": other_user_id, "workspace_id": workspace_id, "title": "Work" },
+        ];
+
+        assert_eq!(
+            dedupe_titles(&records),
+            vec![
+                (ids[1], doc! { "title": "Work (3)" }),
+                (ids[5], doc! { "title": "Work (2)" }),
+            ]
+        );
+    }
+}
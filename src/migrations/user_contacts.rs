@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use log::warn;
+use mongodb::bson::{doc, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use crate::helpers::mongo_error_helper::is_duplicate_key;
+use crate::modules::user::types::{Email, PhoneNumber};
+
+use super::migration::Migration;
+
+/// Stores the email and phone of accounts created before they were normalised in the form
+/// the API now writes: trimmed lowercase emails and E.164 phone numbers. Values that cannot
+/// be normalised are left as they are; they still read back.
+pub struct UserContacts;
+
+#[async_trait]
+impl Migration for UserContacts {
+    fn version(&self) -> u32 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "user_contacts"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        let users = db.collection::<Document>("users");
+        let mut cursor = users
+            .find(doc! {})
+            .projection(doc! { "email": 1, "phone": 1 })
+            .await?;
+
+        while cursor.advance().await? {
+            let user = cursor.deserialize_current()?;
+            let Ok(id) = user.get_object_id("_id") else {
+                continue;
+            };
+
+            let mut changes = Document::new();
+            if let Ok(email) = user.get_str("email") {
+                let normalized = Email::normalize(email);
+                if normalized != email {
+                    changes.insert("email", normalized);
+                }
+            }
+            if let Ok(phone) = user.get_str("phone") {
+                match PhoneNumber::parse(phone) {
+                    Ok(normalized) if normalized.as_str() != phone => {
+                        changes.insert("phone", normalized);
+                    }
+                    Ok(_) => {}
+                    Err(_) => warn!(
+                        "User {} has a phone number that is not valid: kept as is",
+                        id
+                    ),
+                }
+            }
+            if changes.is_empty() {
+                continue;
+            }
+
+            // Once `email_unique` exists, only surrounding whitespace can make two addresses
+            // collide, and the account whose address is already clean keeps it. Before, the
+            // duplicates are written and `unique_keys` resolves them.
+            match users
+                .update_one(doc! { "_id": id }, doc! { "$set": changes })
+                .await
+            {
+                Ok(_) => {}
+                Err(err) if is_duplicate_key(&err) => {
+                    warn!(
+                        "User {} has an email used by another account: kept as is",
+                        id
+                    )
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// The original spelling is not kept; normalised values read back the same.
+    async fn down(&self, _db: &Database) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, Document};
+use mongodb::error::Error;
+use mongodb::Database;
+
+use crate::helpers::time_zone_helper::legacy_time_zone;
+
+use super::migration::Migration;
+
+/// Gives accounts created before users had a time zone the zone the application assumed
+/// for them. New accounts default to UTC.
+pub struct UserTimeZones;
+
+#[async_trait]
+impl Migration for UserTimeZones {
+    fn version(&self) -> u32 {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "user_time_zones"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("users")
+            .update_many(
+                doc! { "time_zone": { "$exists": false } },
+                doc! { "$set": { "time_zone": legacy_time_zone().name() } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the legacy zone, which reads back the same.
+    async fn down(&self, db: &Database) -> Result<(), Error> {
+        db.collection::<Document>("users")
+            .update_many(
+                doc! { "time_zone": legacy_time_zone().name() },
+                doc! { "$unset": { "time_zone": "" } },
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::modules::user::{
+    models::{Language, Role, User, UserCounts},
+    types::{Email, PhoneNumber},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct UserListQuery {
+    #[validate(length(min = 1, max = 100))]
+    pub q: Option<String>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AdminUserResponse {
+    pub _id: String,
+    pub name: String,
+    pub email: Email,
+    pub phone: PhoneNumber,
+    pub time_zone: Tz,
+    pub language: Language,
+    pub role: Role,
+    pub verified: bool,
+    pub disabled: bool,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        AdminUserResponse {
+            _id: user.id.unwrap().to_string(),
+            name: user.name,
+            email: user.email,
+            phone: user.phone,
+            time_zone: user.time_zone,
+            language: user.language,
+            role: user.role,
+            verified: user.verified,
+            disabled: user.disabled,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateRoleRequest {
+    pub role: Role,
+}
+
+#[derive(Serialize)]
+pub struct ForceLogoutResponse {
+    pub revoked_sessions: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StatusCounts {
+    pub total: u64,
+    pub by_status: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize)]
+pub struct UsageStats {
+    pub users: UserCounts,
+    pub categories: u64,
+    pub goals: StatusCounts,
+    pub tasks: StatusCounts,
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Path, Query, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Router,
+};
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::{
+        audit::{
+            extractors::ClientInfo,
+            handlers::audit_service,
+            models::{AuditAction, AuditEvent, AuditOutcome},
+        },
+        auth::{self, extractors::Admin},
+    },
+    AppState,
+};
+
+use super::{
+    dto::{AdminUserResponse, ForceLogoutResponse, UpdateRoleRequest, UserListQuery},
+    service::{AdminService, AdminServiceError},
+};
+
+pub fn admin_service(state: &AppState) -> AdminService {
+    AdminService::new(
+        state.repositories.users.clone(),
+        state.repositories.refresh_tokens.clone(),
+        state.repositories.categories.clone(),
+        state.repositories.goals.clone(),
+        state.repositories.tasks.clone(),
+    )
+}
+
+fn admin_error_response(err: AdminServiceError) -> Response {
+    match err {
+        AdminServiceError::UserNotFound => {
+            ApiResponse::not_found(err.to_string().as_str()).into_response()
+        }
+        AdminServiceError::OwnAccount => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        AdminServiceError::InvalidCursor => {
+            ApiResponse::bad_request(err.to_string().as_str(), None::<()>).into_response()
+        }
+        err => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn list_users(
+    State(state): State<Arc<AppState>>,
+    Admin(_admin): Admin,
+    Query(query): Query<UserListQuery>,
+) -> impl IntoResponse {
+    if let Err(errors) = query.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match admin_service(&state).list_users(&query).await {
+        Ok((users, next_cursor)) => {
+            let users: Vec<AdminUserResponse> = users.into_iter().map(Into::into).collect();
+            ApiResponse::page("Users retrieved successfully", users, next_cursor).into_response()
+        }
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn get_user(
+    Path(user_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Admin(_admin): Admin,
+) -> impl IntoResponse {
+    match admin_service(&state).get_user(&user_id).await {
+        Ok(user) => ApiResponse::ok(
+            "User retrieved successfully",
+            Some(AdminUserResponse::from(user)),
+        )
+        .into_response(),
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn update_role(
+    Path(user_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Admin(admin): Admin,
+    client: ClientInfo,
+    Json(payload): Json<UpdateRoleRequest>,
+) -> impl IntoResponse {
+    match admin_service(&state)
+        .set_role(&admin.id, &user_id, payload.role)
+        .await
+    {
+        Ok(()) => {
+            let event = AuditEvent::new(AuditAction::RoleChanged, AuditOutcome::Success, &client)
+                .actor(admin.id)
+                .target(user_id)
+                .detail(format!("{:?}", payload.role));
+            audit_service(&state).record(event).await;
+            ApiResponse::ok("Role updated successfully", None::<()>).into_response()
+        }
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn disable_user(
+    Path(user_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Admin(admin): Admin,
+    client: ClientInfo,
+) -> impl IntoResponse {
+    match admin_service(&state)
+        .set_disabled(&admin.id, &user_id, true)
+        .await
+    {
+        Ok(()) => {
+            let event =
+                AuditEvent::new(AuditAction::AccountDisabled, AuditOutcome::Success, &client)
+                    .actor(admin.id)
+                    .target(user_id);
+            audit_service(&state).record(event).await;
+            ApiResponse::ok("User disabled successfully", None::<()>).into_response()
+        }
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn enable_user(
+    Path(user_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Admin(admin): Admin,
+    client: ClientInfo,
+) -> impl IntoResponse {
+    match admin_service(&state)
+        .set_disabled(&admin.id, &user_id, false)
+        .await
+    {
+        Ok(()) => {
+            let event =
+                AuditEvent::new(AuditAction::AccountEnabled, AuditOutcome::Success, &client)
+                    .actor(admin.id)
+                    .target(user_id);
+            audit_service(&state).record(event).await;
+            ApiResponse::ok("User enabled successfully", None::<()>).into_response()
+        }
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn force_logout(
+    Path(user_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Admin(admin): Admin,
+    client: ClientInfo,
+) -> impl IntoResponse {
+    match admin_service(&state).force_logout(&user_id).await {
+        Ok(revoked_sessions) => {
+            let event =
+                AuditEvent::new(AuditAction::SessionsRevoked, AuditOutcome::Success, &client)
+                    .actor(admin.id)
+                    .target(user_id);
+            audit_service(&state).record(event).await;
+            ApiResponse::ok(
+                "User logged out successfully",
+                Some(ForceLogoutResponse { revoked_sessions }),
+            )
+            .into_response()
+        }
+        Err(err) => admin_error_response(err),
+    }
+}
+
+async fn usage_stats(
+    State(state): State<Arc<AppState>>,
+    Admin(_admin): Admin,
+) -> impl IntoResponse {
+    match admin_service(&state).usage_stats().await {
+        Ok(stats) => ApiResponse::ok("Usage retrieved successfully", Some(stats)).into_response(),
+        Err(err) => admin_error_response(err),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let admin: Router<Arc<AppState>> = Router::new()
+        .route("/users", get(list_users))
+        .route("/users/:user_id", get(get_user))
+        .route("/users/:user_id/role", put(update_role))
+        .route("/users/:user_id/disable", post(disable_user))
+        .route("/users/:user_id/enable", post(enable_user))
+        .route("/users/:user_id/logout", post(force_logout))
+        .route("/stats", get(usage_stats))
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ));
+    Router::new().nest("/v1/admin", admin)
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use mongodb::bson::{oid::ObjectId, Bson};
+use thiserror::Error;
+
+use crate::{
+    helpers::pagination_helper::Cursor,
+    modules::{
+        auth::repository::RefreshTokenRepository,
+        category::repository::CategoryRepository,
+        goal::repository::GoalRepository,
+        task::repository::TaskRepository,
+        user::{
+            models::{Role, User},
+            repository::UserRepository,
+            types::Email,
+        },
+    },
+};
+
+use super::dto::{StatusCounts, UsageStats, UserListQuery};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+
+#[derive(Error, Debug)]
+pub enum AdminServiceError {
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Administrators cannot change the role or status of their own account")]
+    OwnAccount,
+
+    #[error("Invalid cursor")]
+    InvalidCursor,
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct AdminService {
+    user_repository: Arc<dyn UserRepository>,
+    token_repository: Arc<dyn RefreshTokenRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+    goal_repository: Arc<dyn GoalRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+}
+
+impl AdminService {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        token_repository: Arc<dyn RefreshTokenRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+        goal_repository: Arc<dyn GoalRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+    ) -> Self {
+        AdminService {
+            user_repository,
+            token_repository,
+            category_repository,
+            goal_repository,
+            task_repository,
+        }
+    }
+
+    /// Returns one page of users plus the cursor for the next page, if any.
+    pub async fn list_users(
+        &self,
+        query: &UserListQuery,
+    ) -> Result<(Vec<User>, Option<String>), AdminServiceError> {
+        let after = match &query.cursor {
+            Some(cursor) => Some(
+                Cursor::decode(cursor, "_id")
+                    .ok_or(AdminServiceError::InvalidCursor)?
+                    .id,
+            ),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+        let mut users = self
+            .user_repository
+            .find_users(query.q.as_deref(), after.as_ref(), limit)
+            .await?;
+        let next_cursor = if users.len() as i64 > limit {
+            users.truncate(limit as usize);
+            users.last().map(|last| {
+                Cursor {
+                    sort: "_id".to_string(),
+                    value: Bson::Null,
+                    id: last.id.unwrap(),
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok((users, next_cursor))
+    }
+
+    pub async fn get_user(&self, user_id: &ObjectId) -> Result<User, AdminServiceError> {
+        self.user_repository
+            .find_user_by_id(user_id)
+            .await?
+            .ok_or(AdminServiceError::UserNotFound)
+    }
+
+    pub async fn set_role(
+        &self,
+        admin_id: &ObjectId,
+        user_id: &ObjectId,
+        role: Role,
+    ) -> Result<(), AdminServiceError> {
+        if admin_id == user_id {
+            return Err(AdminServiceError::OwnAccount);
+        }
+        if !self.user_repository.set_role(user_id, role).await? {
+            return Err(AdminServiceError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    /// Disabling also ends every session, so the user is logged out right away.
+    pub async fn set_disabled(
+        &self,
+        admin_id: &ObjectId,
+        user_id: &ObjectId,
+        disabled: bool,
+    ) -> Result<(), AdminServiceError> {
+        if admin_id == user_id {
+            return Err(AdminServiceError::OwnAccount);
+        }
+        if !self.user_repository.set_disabled(user_id, disabled).await? {
+            return Err(AdminServiceError::UserNotFound);
+        }
+        if disabled {
+            self.token_repository
+                .revoke_user_families(user_id, None)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Revokes every session of the user and returns how many were active.
+    pub async fn force_logout(&self, user_id: &ObjectId) -> Result<u64, AdminServiceError> {
+        self.get_user(user_id).await?;
+        let revoked = self
+            .token_repository
+            .revoke_user_families(user_id, None)
+            .await?;
+        Ok(revoked)
+    }
+
+    /// Makes the verified accounts using `emails` administrators. Unverified accounts are
+    /// skipped, so nobody can claim the role by signing up with an address they do not own.
+    pub async fn promote_admins(&self, emails: &[Email]) -> Result<(), AdminServiceError> {
+        for email in emails {
+            match self.user_repository.find_user_by_email(email).await? {
+                None => warn!("No account uses the administrator email {}", email),
+                Some(user) if !user.verified => {
+                    warn!("Not promoting {}: the email is not verified", email)
+                }
+                Some(user) if user.role == Role::Admin => {}
+                Some(user) => {
+                    self.user_repository
+                        .set_role(&user.id.unwrap(), Role::Admin)
+                        .await?;
+                    info!("Made {} an administrator", email);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn usage_stats(&self) -> Result<UsageStats, AdminServiceError> {
+        let mut goals = StatusCounts::default();
+        for (status, count) in self.goal_repository.count_all_goals_by_status().await? {
+            goals.total += count;
+            goals.by_status.insert(status, count);
+        }
+
+        let mut tasks = StatusCounts::default();
+        for (status, count) in self.task_repository.count_all_tasks_by_status().await? {
+            tasks.total += count;
+            tasks.by_status.insert(status, count);
+        }
+
+        Ok(UsageStats {
+            users: self.user_repository.count_users().await?,
+            categories: self.category_repository.count_categories().await?,
+            goals,
+            tasks,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::modules::{
+    category::models::Color,
+    goal::models::{Priority, Status as GoalStatus},
+    notification::models::TimeUnit,
+    task::models::{RecurrenceRule, Status as TaskStatus},
+};
+
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Portable copy of a user's data. Ids are only meaningful inside the archive: they link
+/// tasks to categories and goals, and are replaced by new ids on import.
+#[derive(Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub categories: Vec<ArchiveCategory>,
+    pub goals: Vec<ArchiveGoal>,
+    pub tasks: Vec<ArchiveTask>,
+    #[serde(default)]
+    pub notifications: Vec<ArchiveInboxMessage>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveCategory {
+    pub id: String,
+    pub title: String,
+    pub color: Color,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveGoal {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub category_id: Option<String>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub status: GoalStatus,
+    #[serde(default)]
+    pub auto_status: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveTask {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub status: TaskStatus,
+    pub category_id: String,
+    pub goal_id: Option<String>,
+    pub notification: Option<ArchiveNotification>,
+    pub recurrence: Option<RecurrenceRule>,
+    #[serde(default)]
+    pub occurrence_overrides: Vec<ArchiveOccurrenceOverride>,
+    #[serde(default)]
+    pub subtasks: Vec<ArchiveSubtask>,
+    #[serde(default)]
+    pub derive_status: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveNotification {
+    pub time_unit: TimeUnit,
+    pub time_value: u16,
+    pub scheduled_time: DateTime<Utc>,
+    pub sent: bool,
+    #[serde(default)]
+    pub viewed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveOccurrenceOverride {
+    pub occurrence_start: DateTime<Utc>,
+    pub status: Option<TaskStatus>,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveSubtask {
+    pub title: String,
+    pub done: bool,
+    pub position: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveInboxMessage {
+    pub task_id: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub read: bool,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum ImportMode {
+    /// Keeps existing data; records whose title already exists are matched, not duplicated.
+    #[default]
+    #[serde(rename = "merge")]
+    Merge,
+    /// Deletes the user's categories, goals, tasks and inbox before importing. They are
+    /// restored if the import fails.
+    #[serde(rename = "replace")]
+    Replace,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportCounts {
+    pub created: usize,
+    pub skipped: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportSummary {
+    pub categories: ImportCounts,
+    pub goals: ImportCounts,
+    pub tasks: ImportCounts,
+    pub notifications: ImportCounts,
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{DefaultBodyLimit, Query, State},
+    http::header,
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::{
+        auth::{self, dto::AuthState},
+        goal::handlers::goal_service,
+        notification::repository::InboxRepository,
+        workspace::models::Scope,
+    },
+    AppState,
+};
+
+use super::{
+    dto::{Archive, ImportQuery},
+    service::{ArchiveService, ArchiveServiceError},
+};
+
+const MAX_ARCHIVE_BYTES: usize = 10 * 1024 * 1024;
+
+fn archive_service(state: &AppState) -> ArchiveService {
+    ArchiveService::new(
+        state.repositories.categories.clone(),
+        state.repositories.goals.clone(),
+        state.repositories.tasks.clone(),
+        state.repositories.task_comments.clone(),
+        state.repositories.task_activity.clone(),
+        InboxRepository::new(&state.mongodb),
+    )
+}
+
+async fn export_data(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match archive_service(&state).export_user_data(&user.id).await {
+        // The archive is returned as is so it can be sent back to /v1/me/import unchanged.
+        Ok(archive) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"planit-export.json\"",
+            )],
+            Json(archive),
+        )
+            .into_response(),
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn import_data(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Query(query): Query<ImportQuery>,
+    Json(archive): Json<Archive>,
+) -> impl IntoResponse {
+    match archive_service(&state)
+        .import_user_data(&user.id, archive, query.mode)
+        .await
+    {
+        Ok(summary) => {
+            goal_service(&state)
+                .sync_progress(&Scope::User(user.id))
+                .await;
+            ApiResponse::ok("Data imported successfully", Some(summary)).into_response()
+        }
+        Err(
+            err @ (ArchiveServiceError::UnsupportedVersion(_)
+            | ArchiveServiceError::InvalidArchive(_)),
+        ) => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/me/export", get(export_data))
+        .route(
+            "/v1/me/import",
+            post(import_data).layer(DefaultBodyLimit::max(MAX_ARCHIVE_BYTES)),
+        )
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
+}
@@ -0,0 +1,5 @@
+pub mod dto;
+pub mod handlers;
+pub mod service;
+
+pub use handlers::handles;
@@ -0,0 +1,625 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::Utc;
+use log::error;
+use mongodb::bson::oid::ObjectId;
+use thiserror::Error;
+use validator::Validate;
+
+use crate::modules::{
+    category::{models::Category, repository::CategoryRepository},
+    goal::{models::Goal, repository::GoalRepository},
+    notification::{
+        models::{InboxMessage, Notification},
+        repository::InboxRepository,
+    },
+    task::{
+        models::{OccurrenceOverride, Subtask, Task},
+        repository::{TaskActivityRepository, TaskCommentRepository, TaskRepository},
+    },
+    workspace::models::Scope,
+};
+
+use super::dto::{
+    Archive, ArchiveCategory, ArchiveGoal, ArchiveInboxMessage, ArchiveNotification,
+    ArchiveOccurrenceOverride, ArchiveSubtask, ArchiveTask, ImportMode, ImportSummary,
+    ARCHIVE_VERSION,
+};
+
+// Same limits as the task, subtask, category and goal requests.
+const MAX_TITLE_LENGTH: usize = 30;
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+const MAX_SUBTASK_TITLE_LENGTH: usize = 100;
+
+#[derive(Error, Debug)]
+pub enum ArchiveServiceError {
+    #[error("Unsupported archive version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct ArchiveService {
+    category_repository: Arc<dyn CategoryRepository>,
+    goal_repository: Arc<dyn GoalRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+    comment_repository: Arc<dyn TaskCommentRepository>,
+    activity_repository: Arc<dyn TaskActivityRepository>,
+    inbox_repository: InboxRepository,
+}
+
+impl ArchiveService {
+    pub fn new(
+        category_repository: Arc<dyn CategoryRepository>,
+        goal_repository: Arc<dyn GoalRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+        comment_repository: Arc<dyn TaskCommentRepository>,
+        activity_repository: Arc<dyn TaskActivityRepository>,
+        inbox_repository: InboxRepository,
+    ) -> Self {
+        ArchiveService {
+            category_repository,
+            goal_repository,
+            task_repository,
+            comment_repository,
+            activity_repository,
+            inbox_repository,
+        }
+    }
+
+    pub async fn export_user_data(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<Archive, ArchiveServiceError> {
+        let scope = Scope::User(*user_id);
+        let categories = self
+            .category_repository
+            .get_all_user_categories(&scope)
+            .await?
+            .into_iter()
+            .map(|category| ArchiveCategory {
+                id: category.id.unwrap().to_hex(),
+                title: category.title,
+                color: category.color,
+            })
+            .collect();
+
+        let goals = self
+            .goal_repository
+            .get_all_user_goals(&scope)
+            .await?
+            .into_iter()
+            .map(|goal| ArchiveGoal {
+                id: goal.id.unwrap().to_hex(),
+                title: goal.title,
+                description: goal.description,
+                category_id: goal.category_id.map(|id| id.to_hex()),
+                end_date: goal.end_date,
+                priority: goal.priority,
+                status: goal.status,
+                auto_status: goal.auto_status,
+            })
+            .collect();
+
+        let tasks = self
+            .task_repository
+            .get_all_user_tasks(&scope)
+            .await?
+            .into_iter()
+            .map(export_task)
+            .collect();
+
+        let notifications = self
+            .inbox_repository
+            .get_all_user_messages(user_id)
+            .await?
+            .into_iter()
+            .map(|message| ArchiveInboxMessage {
+                task_id: message.task_id.to_hex(),
+                title: message.title,
+                body: message.body,
+                created_at: message.created_at,
+                read: message.read,
+            })
+            .collect();
+
+        Ok(Archive {
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            categories,
+            goals,
+            tasks,
+            notifications,
+        })
+    }
+
+    /// Restores an archive for the user. Archive ids are remapped to new `ObjectId`s, and in
+    /// merge mode to the ids of existing records with the same title, which are left as is.
+    pub async fn import_user_data(
+        &self,
+        user_id: &ObjectId,
+        archive: Archive,
+        mode: ImportMode,
+    ) -> Result<ImportSummary, ArchiveServiceError> {
+        if archive.version != ARCHIVE_VERSION {
+            return Err(ArchiveServiceError::UnsupportedVersion(archive.version));
+        }
+        validate_archive(&archive)?;
+
+        if mode == ImportMode::Merge {
+            return self.import_records(user_id, archive, true).await;
+        }
+
+        // Without transactions, a copy of the current data is what lets a replace that fails
+        // half-way put things back. Restored records get new ids, like imported ones.
+        let previous = self.export_user_data(user_id).await?;
+        self.delete_user_data(user_id).await?;
+        match self.import_records(user_id, archive, false).await {
+            Ok(summary) => Ok(summary),
+            Err(err) => {
+                let restored = match self.delete_user_data(user_id).await {
+                    Ok(()) => self.import_records(user_id, previous, false).await.map(|_| ()),
+                    Err(err) => Err(err),
+                };
+                if let Err(restore_err) = restored {
+                    error!(
+                        "Failed to restore the data of user {} after a failed import: {}",
+                        user_id, restore_err
+                    );
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Comments and history are not archived, so they go with their tasks.
+    async fn delete_user_data(&self, user_id: &ObjectId) -> Result<(), ArchiveServiceError> {
+        let scope = Scope::User(*user_id);
+        self.inbox_repository
+            .delete_all_user_messages(user_id)
+            .await?;
+        let task_ids: Vec<ObjectId> = self
+            .task_repository
+            .get_all_user_tasks(&scope)
+            .await?
+            .into_iter()
+            .filter_map(|task| task.id)
+            .collect();
+        self.comment_repository
+            .delete_tasks_comments(&task_ids)
+            .await?;
+        self.activity_repository
+            .delete_tasks_activity(&task_ids)
+            .await?;
+        self.task_repository.delete_all_user_tasks(&scope).await?;
+        self.goal_repository.delete_all_user_goals(&scope).await?;
+        self.category_repository
+            .delete_all_user_categories(&scope)
+            .await?;
+        Ok(())
+    }
+
+    /// Creates the archive records, or in `merge` mode matches them to existing records
+    /// with the same title.
+    async fn import_records(
+        &self,
+        user_id: &ObjectId,
+        archive: Archive,
+        merge: bool,
+    ) -> Result<ImportSummary, ArchiveServiceError> {
+        let scope = Scope::User(*user_id);
+        let mut summary = ImportSummary::default();
+
+        let mut category_ids: HashMap<String, ObjectId> = HashMap::new();
+        for category in archive.categories {
+            let existing = if merge {
+                self.category_repository
+                    .get_category_by_title(&scope, &category.title)
+                    .await?
+                    .and_then(|existing| existing.id)
+            } else {
+                None
+            };
+            let id = match existing {
+                Some(id) => {
+                    summary.categories.skipped += 1;
+                    id
+                }
+                None => {
+                    summary.categories.created += 1;
+                    self.category_repository
+                        .create_category(Category {
+                            id: None,
+                            user_id: *user_id,
+                            workspace_id: None,
+                            title: category.title,
+                            color: category.color,
+                        })
+                        .await?
+                }
+            };
+            category_ids.insert(category.id, id);
+        }
+
+        let mut goal_ids: HashMap<String, ObjectId> = HashMap::new();
+        for goal in archive.goals {
+            let existing = if merge {
+                self.goal_repository
+                    .get_goal_by_title(&scope, &goal.title)
+                    .await?
+                    .and_then(|existing| existing.id)
+            } else {
+                None
+            };
+            let id = match existing {
+                Some(id) => {
+                    summary.goals.skipped += 1;
+                    id
+                }
+                None => {
+                    summary.goals.created += 1;
+                    self.goal_repository
+                        .create_goal(Goal {
+                            id: None,
+                            title: goal.title,
+                            description: goal.description,
+                            category_id: goal
+                                .category_id
+                                .and_then(|id| category_ids.get(&id).copied()),
+                            end_date: goal.end_date,
+                            priority: goal.priority,
+                            status: goal.status,
+                            user_id: *user_id,
+                            workspace_id: None,
+                            auto_status: goal.auto_status,
+                        })
+                        .await?
+                }
+            };
+            goal_ids.insert(goal.id, id);
+        }
+
+        let mut task_ids: HashMap<String, ObjectId> = HashMap::new();
+        let mut created_tasks: HashSet<String> = HashSet::new();
+        for task in archive.tasks {
+            let existing = if merge {
+                self.task_repository
+                    .get_task_by_title(&scope, &task.title)
+                    .await?
+                    .and_then(|existing| existing.id)
+            } else {
+                None
+            };
+            let id = match existing {
+                Some(id) => {
+                    summary.tasks.skipped += 1;
+                    id
+                }
+                None => {
+                    summary.tasks.created += 1;
+                    created_tasks.insert(task.id.clone());
+                    let category_id = category_ids[&task.category_id];
+                    let goal_id = task.goal_id.as_ref().map(|id| goal_ids[id]);
+                    self.task_repository
+                        .create_task(import_task(&task, *user_id, category_id, goal_id))
+                        .await?
+                }
+            };
+            task_ids.insert(task.id, id);
+        }
+
+        // Messages of merged tasks are assumed to be there already.
+        for message in archive.notifications {
+            if !created_tasks.contains(&message.task_id) {
+                summary.notifications.skipped += 1;
+                continue;
+            }
+            summary.notifications.created += 1;
+            self.inbox_repository
+                .create_message(InboxMessage {
+                    id: None,
+                    user_id: *user_id,
+                    task_id: task_ids[&message.task_id],
+                    title: message.title,
+                    body: message.body,
+                    created_at: message.created_at,
+                    read: message.read,
+                })
+                .await?;
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Checks the archive can be imported as a whole before anything is written: titles and
+/// descriptions within the limits of the API, no title used twice, and every id referenced
+/// by a goal, task or message defined in the archive.
+fn validate_archive(archive: &Archive) -> Result<(), ArchiveServiceError> {
+    fn unique_ids<'a>(
+        kind: &str,
+        ids: impl Iterator<Item = &'a String>,
+    ) -> Result<HashSet<&'a str>, ArchiveServiceError> {
+        let mut seen = HashSet::new();
+        for id in ids {
+            if !seen.insert(id.as_str()) {
+                return Err(ArchiveServiceError::InvalidArchive(format!(
+                    "duplicate {} id {}",
+                    kind, id
+                )));
+            }
+        }
+        Ok(seen)
+    }
+    fn unique_titles<'a>(
+        kind: &str,
+        titles: impl Iterator<Item = &'a String>,
+    ) -> Result<(), ArchiveServiceError> {
+        let mut seen = HashSet::new();
+        for title in titles {
+            if !seen.insert(title.as_str()) {
+                return Err(ArchiveServiceError::InvalidArchive(format!(
+                    "duplicate {} title \"{}\"",
+                    kind, title
+                )));
+            }
+        }
+        Ok(())
+    }
+    fn check_length(field: &str, text: &str, max: usize) -> Result<(), ArchiveServiceError> {
+        let length = text.chars().count();
+        if length == 0 || length > max {
+            return Err(ArchiveServiceError::InvalidArchive(format!(
+                "{} \"{}\" must be 1 to {} characters",
+                field, text, max
+            )));
+        }
+        Ok(())
+    }
+    let missing = |kind: &str, id: &str| {
+        ArchiveServiceError::InvalidArchive(format!("unknown {} id {}", kind, id))
+    };
+
+    let categories = unique_ids("category", archive.categories.iter().map(|c| &c.id))?;
+    let goals = unique_ids("goal", archive.goals.iter().map(|g| &g.id))?;
+    let tasks = unique_ids("task", archive.tasks.iter().map(|t| &t.id))?;
+
+    for category in &archive.categories {
+        check_length("category title", &category.title, MAX_TITLE_LENGTH)?;
+    }
+    for goal in &archive.goals {
+        check_length("goal title", &goal.title, MAX_TITLE_LENGTH)?;
+        check_length("goal description", &goal.description, MAX_DESCRIPTION_LENGTH)?;
+    }
+    for task in &archive.tasks {
+        check_length("task title", &task.title, MAX_TITLE_LENGTH)?;
+        check_length("task description", &task.description, MAX_DESCRIPTION_LENGTH)?;
+        if task.end_date < task.start_date {
+            return Err(ArchiveServiceError::InvalidArchive(format!(
+                "task \"{}\" ends before it starts",
+                task.title
+            )));
+        }
+        for subtask in &task.subtasks {
+            check_length("subtask title", &subtask.title, MAX_SUBTASK_TITLE_LENGTH)?;
+        }
+        if let Some(rule) = &task.recurrence {
+            rule.validate().map_err(|errors| {
+                ArchiveServiceError::InvalidArchive(format!(
+                    "recurrence of task \"{}\": {}",
+                    task.title, errors
+                ))
+            })?;
+        }
+    }
+    unique_titles("category", archive.categories.iter().map(|c| &c.title))?;
+    unique_titles("goal", archive.goals.iter().map(|g| &g.title))?;
+    unique_titles("task", archive.tasks.iter().map(|t| &t.title))?;
+
+    for goal in &archive.goals {
+        if let Some(category_id) = &goal.category_id {
+            if !categories.contains(category_id.as_str()) {
+                return Err(missing("category", category_id));
+            }
+        }
+    }
+    for task in &archive.tasks {
+        if !categories.contains(task.category_id.as_str()) {
+            return Err(missing("category", &task.category_id));
+        }
+        if let Some(goal_id) = &task.goal_id {
+            if !goals.contains(goal_id.as_str()) {
+                return Err(missing("goal", goal_id));
+            }
+        }
+    }
+    for message in &archive.notifications {
+        if !tasks.contains(message.task_id.as_str()) {
+            return Err(missing("task", &message.task_id));
+        }
+    }
+
+    Ok(())
+}
+
+fn export_task(task: Task) -> ArchiveTask {
+    ArchiveTask {
+        id: task.id.unwrap().to_hex(),
+        title: task.title,
+        description: task.description,
+        start_date: task.start_date,
+        end_date: task.end_date,
+        status: task.status,
+        category_id: task.category_id.to_hex(),
+        goal_id: task.goal_id.map(|id| id.to_hex()),
+        notification: task.notification.map(|notification| ArchiveNotification {
+            time_unit: notification.time_unit,
+            time_value: notification.time_value,
+            scheduled_time: notification.scheduled_time,
+            sent: notification.sent,
+            viewed: notification.viewed,
+        }),
+        recurrence: task.recurrence,
+        occurrence_overrides: task
+            .occurrence_overrides
+            .into_iter()
+            .map(|occurrence| ArchiveOccurrenceOverride {
+                occurrence_start: occurrence.occurrence_start,
+                status: occurrence.status,
+                cancelled: occurrence.cancelled,
+            })
+            .collect(),
+        subtasks: task
+            .subtasks
+            .into_iter()
+            .map(|subtask| ArchiveSubtask {
+                title: subtask.title,
+                done: subtask.done,
+                position: subtask.position,
+            })
+            .collect(),
+        derive_status: task.derive_status,
+    }
+}
+
+fn import_task(
+    task: &ArchiveTask,
+    user_id: ObjectId,
+    category_id: ObjectId,
+    goal_id: Option<ObjectId>,
+) -> Task {
+    Task {
+        id: None,
+        title: task.title.clone(),
+        description: task.description.clone(),
+        start_date: task.start_date,
+        end_date: task.end_date,
+        status: task.status.clone(),
+        user_id,
+        workspace_id: None,
+        assignee_id: None,
+        category_id,
+        goal_id,
+        notification: task.notification.as_ref().map(|notification| Notification {
+            id: ObjectId::new(),
+            time_unit: notification.time_unit.clone(),
+            time_value: notification.time_value,
+            scheduled_time: notification.scheduled_time,
+            sent: notification.sent,
+            viewed: notification.viewed,
+            attempts: 0,
+            missed: false,
+        }),
+        recurrence: task.recurrence.clone(),
+        occurrence_overrides: task
+            .occurrence_overrides
+            .iter()
+            .map(|occurrence| OccurrenceOverride {
+                occurrence_start: occurrence.occurrence_start,
+                status: occurrence.status.clone(),
+                cancelled: occurrence.cancelled,
+            })
+            .collect(),
+        subtasks: task
+            .subtasks
+            .iter()
+            .map(|subtask| Subtask {
+                id: ObjectId::new(),
+                title: subtask.title.clone(),
+                done: subtask.done,
+                position: subtask.position,
+            })
+            .collect(),
+        derive_status: task.derive_status,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn archive(tasks: serde_json::Value) -> Archive {
+        serde_json::from_value(json!({
+            "version": ARCHIVE_VERSION,
+            "exported_at": "2024-03-01T10:00:00Z",
+            "categories": [{ "id": "c1", "title": "Work", "color": "GREEN" }],
+            "goals": [],
+            "tasks": tasks,
+        }))
+        .unwrap()
+    }
+
+    fn task(id: &str, title: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "title": title,
+            "description": "Description",
+            "start_date": "2024-03-01T10:00:00Z",
+            "end_date": "2024-03-01T11:00:00Z",
+            "status": "EXECUTADA",
+            "category_id": "c1",
+            "goal_id": null,
+            "notification": null,
+            "recurrence": null,
+        })
+    }
+
+    fn rejection(archive: &Archive) -> String {
+        match validate_archive(archive) {
+            Err(ArchiveServiceError::InvalidArchive(reason)) => reason,
+            other => panic!("expected an invalid archive, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn archives_are_validated_as_a_whole() {
+        assert!(validate_archive(&archive(json!([task("t1", "Report")]))).is_ok());
+
+        assert_eq!(
+            rejection(&archive(json!([
+                task("t1", "Report"),
+                task("t2", "Report")
+            ]))),
+            "duplicate task title \"Report\""
+        );
+        assert_eq!(
+            rejection(&archive(json!([task("t1", "")]))),
+            "task title \"\" must be 1 to 30 characters"
+        );
+        let mut backwards = task("t1", "Report");
+        backwards["end_date"] = json!("2024-03-01T09:00:00Z");
+        assert_eq!(
+            rejection(&archive(json!([backwards]))),
+            "task \"Report\" ends before it starts"
+        );
+        let mut long_goal = archive(json!([task("t1", "Report")]));
+        long_goal.goals = serde_json::from_value(json!([{
+            "id": "g1",
+            "title": "Run",
+            "description": "x".repeat(101),
+            "category_id": null,
+            "end_date": null,
+            "priority": "HIGH",
+            "status": "NOT_REACHED",
+        }]))
+        .unwrap();
+        assert!(rejection(&long_goal).starts_with("goal description"));
+        let mut long_subtask = task("t1", "Report");
+        long_subtask["subtasks"] =
+            json!([{ "title": "x".repeat(101), "done": false, "position": 0 }]);
+        assert!(rejection(&archive(json!([long_subtask]))).starts_with("subtask title"));
+        let mut dangling = task("t1", "Report");
+        dangling["category_id"] = json!("c2");
+        assert_eq!(
+            rejection(&archive(json!([dangling]))),
+            "unknown category id c2"
+        );
+    }
+}
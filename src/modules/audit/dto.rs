@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::models::{AuditAction, AuditEvent, AuditOutcome};
+
+#[derive(Deserialize, Validate)]
+pub struct AuditQuery {
+    pub action: Option<AuditAction>,
+    pub outcome: Option<AuditOutcome>,
+    pub actor_id: Option<ObjectId>,
+    pub target_id: Option<ObjectId>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SecurityEventsQuery {
+    pub action: Option<AuditAction>,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuditEventResponse {
+    pub _id: String,
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEvent> for AuditEventResponse {
+    fn from(event: AuditEvent) -> Self {
+        AuditEventResponse {
+            _id: event.id.unwrap().to_string(),
+            action: event.action,
+            outcome: event.outcome,
+            actor_id: event.actor_id.map(|id| id.to_string()),
+            target_id: event.target_id.map(|id| id.to_string()),
+            ip: event.ip,
+            user_agent: event.user_agent,
+            detail: event.detail,
+            created_at: DateTime::from_timestamp_millis(event.created_at.timestamp_millis())
+                .unwrap_or_default(),
+        }
+    }
+}
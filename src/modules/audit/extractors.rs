@@ -0,0 +1,36 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{async_trait, extract::FromRequestParts, http::header, http::request::Parts};
+
+use crate::{modules::rate_limit::middlewares::client_ip, AppState};
+
+/// Where a request came from, as recorded in the audit log.
+pub struct ClientInfo {
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(256).collect());
+
+        Ok(ClientInfo {
+            ip: client_ip(
+                &parts.headers,
+                &parts.extensions,
+                state.config.rate_limit.trust_proxy_headers,
+            ),
+            user_agent,
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Router,
+};
+use validator::Validate;
+
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::auth::{self, dto::AuthState, extractors::Admin},
+    AppState,
+};
+
+use super::{
+    dto::{AuditEventResponse, AuditQuery, SecurityEventsQuery},
+    service::{AuditService, AuditServiceError},
+};
+
+pub fn audit_service(state: &AppState) -> AuditService {
+    AuditService::new(
+        state.repositories.audit_events.clone(),
+        state.repositories.users.clone(),
+    )
+}
+
+fn audit_error_response(err: AuditServiceError) -> Response {
+    match err {
+        AuditServiceError::InvalidCursor => {
+            ApiResponse::bad_request(err.to_string().as_str(), None::<()>).into_response()
+        }
+        err => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn list_audit_events(
+    State(state): State<Arc<AppState>>,
+    Admin(_admin): Admin,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    if let Err(errors) = query.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match audit_service(&state).list_events(&query, None).await {
+        Ok((events, next_cursor)) => {
+            let events: Vec<AuditEventResponse> = events.into_iter().map(Into::into).collect();
+            ApiResponse::page("Audit events retrieved successfully", events, next_cursor)
+                .into_response()
+        }
+        Err(err) => audit_error_response(err),
+    }
+}
+
+async fn list_security_events(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Query(query): Query<SecurityEventsQuery>,
+) -> impl IntoResponse {
+    if let Err(errors) = query.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    let query = AuditQuery {
+        action: query.action,
+        outcome: None,
+        actor_id: None,
+        target_id: None,
+        from: None,
+        to: None,
+        limit: query.limit,
+        cursor: query.cursor,
+    };
+    match audit_service(&state)
+        .list_events(&query, Some(user.id))
+        .await
+    {
+        Ok((events, next_cursor)) => {
+            let events: Vec<AuditEventResponse> = events.into_iter().map(Into::into).collect();
+            ApiResponse::page(
+                "Security events retrieved successfully",
+                events,
+                next_cursor,
+            )
+            .into_response()
+        }
+        Err(err) => audit_error_response(err),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let v1: Router<Arc<AppState>> = Router::new()
+        .route("/admin/audit-events", get(list_audit_events))
+        .route("/me/security-events", get(list_security_events))
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ));
+    Router::new().nest("/v1", v1)
+}
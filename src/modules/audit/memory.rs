@@ -0,0 +1,52 @@
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+
+use super::models::{AuditEvent, AuditFilter};
+use super::repository::AuditRepository;
+
+#[derive(Default)]
+pub struct InMemoryAuditRepository {
+    events: MemoryCollection<AuditEvent>,
+}
+
+fn matches(event: &AuditEvent, filter: &AuditFilter) -> bool {
+    filter.action.is_none_or(|action| event.action == action)
+        && filter
+            .outcome
+            .is_none_or(|outcome| event.outcome == outcome)
+        && filter
+            .actor_id
+            .is_none_or(|actor_id| event.actor_id == Some(actor_id))
+        && filter
+            .target_id
+            .is_none_or(|target_id| event.target_id == Some(target_id))
+        && filter.from.is_none_or(|from| event.created_at >= from)
+        && filter.to.is_none_or(|to| event.created_at <= to)
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn record(&self, event: AuditEvent) -> Result<ObjectId, Error> {
+        self.events.insert(&event)
+    }
+
+    async fn find_events(
+        &self,
+        filter: &AuditFilter,
+        before: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, Error> {
+        let mut events = self.events.find(|event| {
+            matches(event, filter) && before.is_none_or(|before| event.id.as_ref() < Some(before))
+        })?;
+        events.sort_by_key(|event| Reverse(event.id));
+        events.truncate((limit + 1).max(0) as usize);
+
+        Ok(events)
+    }
+}
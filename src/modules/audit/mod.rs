@@ -0,0 +1,10 @@
+pub mod dto;
+pub mod extractors;
+pub mod handlers;
+#[cfg(test)]
+pub mod memory;
+pub mod models;
+pub mod repository;
+pub mod service;
+
+pub use handlers::handles;
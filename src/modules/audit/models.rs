@@ -0,0 +1,111 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+
+use super::extractors::ClientInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AuditAction {
+    #[serde(rename = "LOGIN")]
+    Login,
+    #[serde(rename = "LOGOUT")]
+    Logout,
+    #[serde(rename = "REFRESH_TOKEN_REUSED")]
+    RefreshTokenReused,
+    #[serde(rename = "SIGNUP")]
+    Signup,
+    #[serde(rename = "PASSWORD_CHANGED")]
+    PasswordChanged,
+    #[serde(rename = "PASSWORD_RESET_REQUESTED")]
+    PasswordResetRequested,
+    #[serde(rename = "PASSWORD_RESET")]
+    PasswordReset,
+    #[serde(rename = "EMAIL_CHANGED")]
+    EmailChanged,
+    #[serde(rename = "ACCOUNT_DELETED")]
+    AccountDeleted,
+    #[serde(rename = "ROLE_CHANGED")]
+    RoleChanged,
+    #[serde(rename = "ACCOUNT_DISABLED")]
+    AccountDisabled,
+    #[serde(rename = "ACCOUNT_ENABLED")]
+    AccountEnabled,
+    #[serde(rename = "SESSIONS_REVOKED")]
+    SessionsRevoked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    #[serde(rename = "SUCCESS")]
+    Success,
+    #[serde(rename = "FAILURE")]
+    Failure,
+}
+
+/// A security-relevant event. `created_at` is a BSON date so the retention TTL index can
+/// expire it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEvent {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    pub actor_id: Option<ObjectId>,  // Who acted, when known
+    pub target_id: Option<ObjectId>, // Account the event concerns
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, outcome: AuditOutcome, client: &ClientInfo) -> Self {
+        AuditEvent {
+            id: None,
+            action,
+            outcome,
+            actor_id: None,
+            target_id: None,
+            ip: client.ip.clone(),
+            user_agent: client.user_agent.clone(),
+            detail: None,
+            created_at: DateTime::now(),
+        }
+    }
+
+    pub fn actor(mut self, actor_id: ObjectId) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn target(mut self, target_id: ObjectId) -> Self {
+        self.target_id = Some(target_id);
+        self
+    }
+
+    /// Shorthand for events users trigger on their own account.
+    pub fn by_owner(self, user_id: ObjectId) -> Self {
+        self.actor(user_id).target(user_id)
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub action: Option<AuditAction>,
+    pub outcome: Option<AuditOutcome>,
+    pub actor_id: Option<ObjectId>,
+    pub target_id: Option<ObjectId>,
+    pub from: Option<DateTime>,
+    pub to: Option<DateTime>,
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::bson::{doc, oid::ObjectId, to_bson, Document};
+use mongodb::error::{Error, ErrorKind};
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, Database, IndexModel};
+
+use super::models::{AuditEvent, AuditFilter};
+
+const COLLECTION: &str = "audit_events";
+const TTL_INDEX: &str = "created_at_ttl";
+const INDEX_OPTIONS_CONFLICT: i32 = 85;
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn record(&self, event: AuditEvent) -> Result<ObjectId, Error>;
+
+    /// Returns up to `limit` events matching `filter`, newest first and older than `before`
+    /// when given; one extra event is fetched to tell if there is a next page.
+    async fn find_events(
+        &self,
+        filter: &AuditFilter,
+        before: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, Error>;
+}
+
+pub struct MongoAuditRepository {
+    db: Database,
+    collection: Collection<AuditEvent>,
+}
+
+impl MongoAuditRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection(COLLECTION);
+        MongoAuditRepository {
+            db: db.clone(),
+            collection,
+        }
+    }
+
+    /// Creates the TTL index that expires events after `retention`, or updates its expiry
+    /// when the index exists with another one.
+    pub async fn ensure_retention(&self, retention: Duration) -> Result<(), Error> {
+        let index = IndexModel::builder()
+            .keys(doc! { "created_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name(TTL_INDEX.to_string())
+                    .expire_after(retention)
+                    .build(),
+            )
+            .build();
+
+        match self.collection.create_index(index).await {
+            Ok(_) => Ok(()),
+            Err(err) if is_options_conflict(&err) => {
+                self.db
+                    .run_command(doc! {
+                        "collMod": COLLECTION,
+                        "index": {
+                            "name": TTL_INDEX,
+                            "expireAfterSeconds": retention.as_secs() as i64,
+                        },
+                    })
+                    .await?;
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for MongoAuditRepository {
+    async fn record(&self, event: AuditEvent) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(event).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn find_events(
+        &self,
+        filter: &AuditFilter,
+        before: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, Error> {
+        let mut query = Document::new();
+        if let Some(action) = &filter.action {
+            query.insert("action", to_bson(action)?);
+        }
+        if let Some(outcome) = &filter.outcome {
+            query.insert("outcome", to_bson(outcome)?);
+        }
+        if let Some(actor_id) = &filter.actor_id {
+            query.insert("actor_id", actor_id);
+        }
+        if let Some(target_id) = &filter.target_id {
+            query.insert("target_id", target_id);
+        }
+        if filter.from.is_some() || filter.to.is_some() {
+            let mut range = Document::new();
+            if let Some(from) = filter.from {
+                range.insert("$gte", from);
+            }
+            if let Some(to) = filter.to {
+                range.insert("$lte", to);
+            }
+            query.insert("created_at", range);
+        }
+        if let Some(before) = before {
+            query.insert("_id", doc! { "$lt": before });
+        }
+
+        let mut cursor = self
+            .collection
+            .find(query)
+            .sort(doc! { "_id": -1 })
+            .limit(limit + 1)
+            .await?;
+        let mut events = Vec::new();
+        while cursor.advance().await? {
+            events.push(cursor.deserialize_current()?);
+        }
+
+        Ok(events)
+    }
+}
+
+fn is_options_conflict(err: &Error) -> bool {
+    matches!(&*err.kind, ErrorKind::Command(command) if command.code == INDEX_OPTIONS_CONFLICT)
+}
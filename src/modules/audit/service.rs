@@ -0,0 +1,322 @@
+use std::sync::Arc;
+
+use log::error;
+use mongodb::bson::{oid::ObjectId, Bson};
+use thiserror::Error;
+
+use crate::helpers::{date_helper::to_bson_datetime, pagination_helper::Cursor};
+use crate::modules::user::{repository::UserRepository, types::Email};
+
+use super::dto::AuditQuery;
+use super::models::{AuditEvent, AuditFilter};
+use super::repository::AuditRepository;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+
+#[derive(Error, Debug)]
+pub enum AuditServiceError {
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct AuditService {
+    repository: Arc<dyn AuditRepository>,
+    user_repository: Arc<dyn UserRepository>,
+}
+
+impl AuditService {
+    pub fn new(
+        repository: Arc<dyn AuditRepository>,
+        user_repository: Arc<dyn UserRepository>,
+    ) -> Self {
+        AuditService {
+            repository,
+            user_repository,
+        }
+    }
+
+    /// Stores the event. Failures are logged, never returned: the audit log must not break
+    /// the request it describes.
+    pub async fn record(&self, event: AuditEvent) {
+        let action = event.action;
+        if let Err(err) = self.repository.record(event).await {
+            error!("Failed to record audit event {:?}: {}", action, err);
+        }
+    }
+
+    /// Records an event about the account registered with `email`, if there is one.
+    pub async fn record_for_email(&self, event: AuditEvent, email: &Email) {
+        let event = match self.user_repository.find_user_by_email(email).await {
+            Ok(Some(user)) => match user.id {
+                Some(user_id) => event.target(user_id),
+                None => event,
+            },
+            Ok(None) => {
+                let detail = format!(
+                    "{} (no account for {})",
+                    event.detail.as_deref().unwrap_or("Unknown account"),
+                    email
+                );
+                event.detail(detail)
+            }
+            Err(err) => {
+                error!("Failed to look up the account of an audit event: {}", err);
+                event
+            }
+        };
+        self.record(event).await;
+    }
+
+    /// Returns one page of events plus the cursor for the next page, if any. `target_id`
+    /// overrides the query, to restrict users to their own history.
+    pub async fn list_events(
+        &self,
+        query: &AuditQuery,
+        target_id: Option<ObjectId>,
+    ) -> Result<(Vec<AuditEvent>, Option<String>), AuditServiceError> {
+        let before = match &query.cursor {
+            Some(cursor) => Some(
+                Cursor::decode(cursor, "_id")
+                    .ok_or(AuditServiceError::InvalidCursor)?
+                    .id,
+            ),
+            None => None,
+        };
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        let filter = AuditFilter {
+            action: query.action,
+            outcome: query.outcome,
+            actor_id: query.actor_id,
+            target_id: target_id.or(query.target_id),
+            from: query.from.map(to_bson_datetime),
+            to: query.to.map(to_bson_datetime),
+        };
+
+        let mut events = self
+            .repository
+            .find_events(&filter, before.as_ref(), limit)
+            .await?;
+        let next_cursor = if events.len() as i64 > limit {
+            events.truncate(limit as usize);
+            events.last().map(|last| {
+                Cursor {
+                    sort: "_id".to_string(),
+                    value: Bson::Null,
+                    id: last.id.unwrap(),
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok((events, next_cursor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::{app::AuthConfig, repositories::Repositories};
+    use crate::helpers::token_helper::hash_token;
+    use crate::modules::audit::{
+        extractors::ClientInfo,
+        models::{AuditAction, AuditOutcome},
+    };
+    use crate::modules::auth::service::{AuthService, AuthServiceError};
+    use crate::modules::mail::outbox::InMemoryOutbox;
+    use crate::modules::rate_limit::{
+        limiter::{RateLimiter, LOGIN_PER_ACCOUNT},
+        memory::InMemoryStore,
+    };
+    use crate::modules::user::{service::UserService, types::Password};
+
+    use super::*;
+
+    const PASSWORD: &str = "Str0ng!Passw0rd";
+
+    fn client() -> ClientInfo {
+        ClientInfo {
+            ip: "10.0.0.1".to_string(),
+            user_agent: Some("tests".to_string()),
+        }
+    }
+
+    fn services(
+        repositories: &Repositories,
+        outbox: Arc<InMemoryOutbox>,
+    ) -> (AuthService, AuditService) {
+        let auth = AuthService::new(
+            UserService::new(repositories.users.clone()),
+            repositories.refresh_tokens.clone(),
+            repositories.one_time_tokens.clone(),
+            repositories.login_attempts.clone(),
+            RateLimiter::new(
+                Arc::new(InMemoryStore::default()),
+                LOGIN_PER_ACCOUNT,
+                "login-account",
+            ),
+            outbox,
+            &AuthConfig::default(),
+        );
+        let audit = AuditService::new(
+            repositories.audit_events.clone(),
+            repositories.users.clone(),
+        );
+        (auth, audit)
+    }
+
+    async fn create_user(repositories: &Repositories, email: &str) -> Email {
+        UserService::new(repositories.users.clone())
+            .create_user(
+                serde_json::from_value(json!({
+                    "name": "Ana",
+                    "email": email,
+                    "password": PASSWORD,
+                    "phone": "+5511999999999",
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        Email::parse(email).unwrap()
+    }
+
+    async fn events(
+        audit: &AuditService,
+        query: serde_json::Value,
+        target_id: Option<ObjectId>,
+    ) -> Vec<AuditEvent> {
+        let query = serde_json::from_value(query).unwrap();
+        audit.list_events(&query, target_id).await.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn refresh_token_reuse_is_recorded_against_the_account() {
+        let repositories = Repositories::in_memory();
+        let (auth, audit) = services(&repositories, Arc::new(InMemoryOutbox::default()));
+        let email = create_user(&repositories, "ana@example.com").await;
+        let session = auth
+            .login(&email, &Password::parse(PASSWORD).unwrap(), "10.0.0.1")
+            .await
+            .unwrap();
+        auth.refresh(&session.refresh_token).await.unwrap();
+
+        let Err(err @ AuthServiceError::RefreshTokenReused(user_id)) =
+            auth.refresh(&session.refresh_token).await
+        else {
+            panic!("expected a reused token");
+        };
+        let event = AuditEvent::new(
+            AuditAction::RefreshTokenReused,
+            AuditOutcome::Failure,
+            &client(),
+        )
+        .detail(err.to_string());
+        audit.record(event.target(user_id)).await;
+
+        let recorded = events(&audit, json!({ "action": "REFRESH_TOKEN_REUSED" }), None).await;
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].target_id, Some(session.id));
+        assert_eq!(recorded[0].actor_id, None);
+        assert_eq!(recorded[0].outcome, AuditOutcome::Failure);
+        assert_eq!(recorded[0].ip, "10.0.0.1");
+        assert_eq!(
+            recorded[0].detail.as_deref(),
+            Some(err.to_string().as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn logouts_are_recorded_in_the_history_of_their_user() {
+        let repositories = Repositories::in_memory();
+        let (auth, audit) = services(&repositories, Arc::new(InMemoryOutbox::default()));
+        let email = create_user(&repositories, "ana@example.com").await;
+        let other = create_user(&repositories, "bia@example.com").await;
+        let password = Password::parse(PASSWORD).unwrap();
+        let session = auth.login(&email, &password, "10.0.0.1").await.unwrap();
+        let other_session = auth.login(&other, &password, "10.0.0.2").await.unwrap();
+        let family_id = repositories
+            .refresh_tokens
+            .find_by_hash(&hash_token(&session.refresh_token))
+            .await
+            .unwrap()
+            .unwrap()
+            .family_id;
+
+        auth.logout(&family_id).await.unwrap();
+        let event = AuditEvent::new(AuditAction::Logout, AuditOutcome::Success, &client());
+        audit.record(event.by_owner(session.id)).await;
+
+        let recorded = events(&audit, json!({}), Some(session.id)).await;
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].action, AuditAction::Logout);
+        assert_eq!(recorded[0].actor_id, Some(session.id));
+        assert_eq!(recorded[0].target_id, Some(session.id));
+        // Users only see their own history.
+        assert!(events(&audit, json!({}), Some(other_session.id))
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn password_resets_are_recorded_with_their_outcome() {
+        let repositories = Repositories::in_memory();
+        let outbox = Arc::new(InMemoryOutbox::default());
+        let (auth, audit) = services(&repositories, outbox.clone());
+        let email = create_user(&repositories, "ana@example.com").await;
+        let unknown = Email::parse("nobody@example.com").unwrap();
+        let new_password = Password::parse("N3w!Passw0rd").unwrap();
+
+        for email in [&email, &unknown] {
+            auth.request_password_reset(email).await.unwrap();
+            let event = AuditEvent::new(
+                AuditAction::PasswordResetRequested,
+                AuditOutcome::Success,
+                &client(),
+            );
+            audit.record_for_email(event, email).await;
+        }
+        let mail = outbox.messages().pop().expect("a mail was sent");
+        let code = mail.body.split("\n\n").nth(1).unwrap().to_string();
+        let user_id = auth.reset_password(&code, &new_password).await.unwrap();
+        let event = AuditEvent::new(AuditAction::PasswordReset, AuditOutcome::Success, &client());
+        audit.record(event.target(user_id)).await;
+        let Err(err @ AuthServiceError::InvalidOneTimeToken) =
+            auth.reset_password(&code, &new_password).await
+        else {
+            panic!("expected a used code to be rejected");
+        };
+        let event = AuditEvent::new(AuditAction::PasswordReset, AuditOutcome::Failure, &client())
+            .detail(err.to_string());
+        audit.record(event).await;
+
+        // Newest first.
+        let requested = events(
+            &audit,
+            json!({ "action": "PASSWORD_RESET_REQUESTED" }),
+            None,
+        )
+        .await;
+        assert_eq!(requested.len(), 2);
+        assert_eq!(requested[0].target_id, None);
+        assert_eq!(
+            requested[0].detail.as_deref(),
+            Some("Unknown account (no account for nobody@example.com)")
+        );
+        assert_eq!(requested[1].target_id, Some(user_id));
+
+        let resets = events(&audit, json!({ "action": "PASSWORD_RESET" }), None).await;
+        assert_eq!(resets.len(), 2);
+        assert_eq!(resets[0].outcome, AuditOutcome::Failure);
+        assert_eq!(resets[0].target_id, None);
+        assert_eq!(resets[1].outcome, AuditOutcome::Success);
+        assert_eq!(resets[1].target_id, Some(user_id));
+        let failures = events(&audit, json!({ "outcome": "FAILURE" }), None).await;
+        assert_eq!(failures.len(), 1);
+    }
+}
@@ -1,4 +1,8 @@
 use crate::helpers::object_id_helper::{deserialize_object_id, serialize_object_id};
+use crate::modules::user::{
+    models::Role,
+    types::{Email, Password},
+};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -11,16 +15,27 @@ pub struct AuthState {
         deserialize_with = "deserialize_object_id"
     )]
     pub id: ObjectId,
-    pub email: String,
+    pub email: Email,
+    #[serde(
+        serialize_with = "serialize_object_id",
+        deserialize_with = "deserialize_object_id"
+    )]
+    pub sid: ObjectId,
+    #[serde(default)]
+    pub role: Role, // As of when the token was issued
     pub exp: usize,
 }
 
-#[derive(Deserialize, Validate)]
+impl AuthState {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.role == role
+    }
+}
+
+#[derive(Deserialize)]
 pub struct UserLoginRequest {
-    #[validate(email)]
-    pub email: String,
-    #[validate(length(min = 8))]
-    pub password: String,
+    pub email: Email,
+    pub password: Password,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -31,6 +46,37 @@ pub struct UserLoginResponse {
         deserialize_with = "deserialize_object_id"
     )]
     pub id: ObjectId,
-    pub email: String,
+    pub email: Email,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct EmailRequest {
+    pub email: Email,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct OneTimeTokenRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1))]
     pub token: String,
+    pub new_password: Password,
 }
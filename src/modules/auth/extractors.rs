@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use log::error;
+
+use super::dto::AuthState;
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::user::{models::Role, repository::UserRepository},
+    AppState,
+};
+
+/// The authenticated caller, only extracted for administrators. Must run behind
+/// `middlewares::authorize`. The role in the token is checked against the stored user, so
+/// a demoted or disabled administrator loses access before their token expires.
+pub struct Admin(pub AuthState);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for Admin {
+    type Rejection = ApiResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let user = parts
+            .extensions
+            .get::<AuthState>()
+            .cloned()
+            .ok_or_else(|| ApiResponse::unauthorized("Please add the token to the header"))?;
+
+        Admin::check(user, state.repositories.users.as_ref()).await
+    }
+}
+
+impl Admin {
+    async fn check(user: AuthState, users: &dyn UserRepository) -> Result<Self, ApiResponse> {
+        if !user.has_role(Role::Admin) {
+            return Err(ApiResponse::forbidden("Administrator access required"));
+        }
+
+        match users.find_user_by_id(&user.id).await {
+            Ok(Some(stored)) if stored.role == Role::Admin && !stored.disabled => Ok(Admin(user)),
+            Ok(_) => Err(ApiResponse::forbidden("Administrator access required")),
+            Err(err) => {
+                error!("Error checking administrator {}: {}", user.id, err);
+                Err(ApiResponse::server_error(None, None::<()>))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, response::IntoResponse};
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::user::{service::UserService, types::Email};
+
+    use super::*;
+
+    async fn create_user(repositories: &Repositories, email: &str, role: Role) -> AuthState {
+        UserService::new(repositories.users.clone())
+            .create_user(
+                serde_json::from_value(json!({
+                    "name": "Ana",
+                    "email": email,
+                    "password": "Str0ng!Passw0rd",
+                    "phone": "+5511999999999",
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let email = Email::parse(email).unwrap();
+        let user = repositories
+            .users
+            .find_user_by_email(&email)
+            .await
+            .unwrap()
+            .unwrap();
+        let id = user.id.unwrap();
+        repositories.users.set_role(&id, role).await.unwrap();
+        AuthState {
+            id,
+            email,
+            sid: ObjectId::new(),
+            role,
+            exp: usize::MAX,
+        }
+    }
+
+    async fn status(repositories: &Repositories, user: AuthState) -> StatusCode {
+        match Admin::check(user, repositories.users.as_ref()).await {
+            Ok(_) => StatusCode::OK,
+            Err(response) => response.into_response().status(),
+        }
+    }
+
+    #[tokio::test]
+    async fn only_enabled_administrators_pass() {
+        let repositories = Repositories::in_memory();
+        let admin = create_user(&repositories, "admin@example.com", Role::Admin).await;
+        let user = create_user(&repositories, "ana@example.com", Role::User).await;
+
+        assert_eq!(status(&repositories, admin.clone()).await, StatusCode::OK);
+        assert_eq!(
+            status(&repositories, user.clone()).await,
+            StatusCode::FORBIDDEN
+        );
+        // A token issued before a demotion, or forged with the admin role, is not enough.
+        let claimed = AuthState {
+            role: Role::Admin,
+            ..user
+        };
+        assert_eq!(status(&repositories, claimed).await, StatusCode::FORBIDDEN);
+
+        repositories
+            .users
+            .set_disabled(&admin.id, true)
+            .await
+            .unwrap();
+        assert_eq!(status(&repositories, admin).await, StatusCode::FORBIDDEN);
+    }
+}
@@ -1,32 +1,277 @@
 use std::sync::Arc;
 
-use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+use axum::{
+    extract::State, middleware, response::IntoResponse, routing::post, Extension, Router,
+};
+use validator::Validate;
 
 use crate::{
-    helpers::api_response::ApiResponse,
-    modules::user::{repository::UserRepository, service::UserService},
+    helpers::{api_response::ApiResponse, json_helper::JsonBody},
+    modules::{
+        audit::{
+            extractors::ClientInfo,
+            handlers::audit_service,
+            models::{AuditAction, AuditEvent, AuditOutcome},
+        },
+        rate_limit::{
+            limiter::{RateLimiter, ACCOUNT_RECOVERY_PER_IP, LOGIN_PER_ACCOUNT, LOGIN_PER_IP},
+            middlewares::limit_by_ip,
+        },
+        user::service::UserService,
+    },
     AppState,
 };
 
-use super::{dto::UserLoginRequest, service::AuthService};
+use super::{
+    dto::{
+        AuthState, EmailRequest, OneTimeTokenRequest, RefreshTokenRequest, ResetPasswordRequest,
+        UserLoginRequest,
+    },
+    middlewares,
+    service::{AuthService, AuthServiceError},
+};
+
+pub fn auth_service(state: &AppState) -> AuthService {
+    AuthService::new(
+        UserService::new(state.repositories.users.clone()),
+        state.repositories.refresh_tokens.clone(),
+        state.repositories.one_time_tokens.clone(),
+        state.repositories.login_attempts.clone(),
+        RateLimiter::new(state.rate_limits.clone(), LOGIN_PER_ACCOUNT, "login-account"),
+        state.mailer.clone(),
+        &state.config.auth,
+    )
+}
 
 async fn login(
     State(state): State<Arc<AppState>>,
-    Json(mut payload): Json<UserLoginRequest>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<UserLoginRequest>,
 ) -> impl IntoResponse {
-    let user_service = UserService::new(UserRepository::new(&state.mongodb));
-    let auth_service = AuthService::new(user_service);
+    let auth_service = auth_service(&state);
+
+    let result = auth_service
+        .login(&payload.email, &payload.password, &client.ip)
+        .await;
+    match &result {
+        Ok(res) => {
+            let event = AuditEvent::new(AuditAction::Login, AuditOutcome::Success, &client);
+            audit_service(&state).record(event.by_owner(res.id)).await;
+        }
+        Err(
+            err @ (AuthServiceError::Unauthorized
+            | AuthServiceError::EmailNotVerified
+            | AuthServiceError::AccountDisabled
+            | AuthServiceError::TooManyAttempts(_)),
+        ) => {
+            let event = AuditEvent::new(AuditAction::Login, AuditOutcome::Failure, &client)
+                .detail(err.to_string());
+            audit_service(&state)
+                .record_for_email(event, &payload.email)
+                .await;
+        }
+        Err(_) => {}
+    }
 
-    payload.email = payload.email.trim().to_string();
-    match auth_service.login(&payload.email, &payload.password).await {
+    match result {
         Ok(res) => ApiResponse::ok("Login successful", Some(res)).into_response(),
+        Err(err @ AuthServiceError::Unauthorized) => {
+            ApiResponse::unauthorized(err.to_string().as_str()).into_response()
+        }
+        Err(err @ (AuthServiceError::EmailNotVerified | AuthServiceError::AccountDisabled)) => {
+            ApiResponse::forbidden(err.to_string().as_str()).into_response()
+        }
+        Err(err @ AuthServiceError::TooManyAttempts(retry_after)) => {
+            ApiResponse::too_many_requests(err.to_string().as_str(), retry_after).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn refresh_token(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<RefreshTokenRequest>,
+) -> impl IntoResponse {
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match auth_service(&state).refresh(&payload.refresh_token).await {
+        Ok(res) => ApiResponse::ok("Token refreshed successfully", Some(res)).into_response(),
+        Err(err @ AuthServiceError::InvalidRefreshToken) => {
+            ApiResponse::unauthorized(err.to_string().as_str()).into_response()
+        }
+        Err(err @ AuthServiceError::RefreshTokenReused(user_id)) => {
+            let event = AuditEvent::new(
+                AuditAction::RefreshTokenReused,
+                AuditOutcome::Failure,
+                &client,
+            )
+            .detail(err.to_string());
+            audit_service(&state).record(event.target(user_id)).await;
+            ApiResponse::unauthorized(err.to_string().as_str()).into_response()
+        }
+        Err(err @ AuthServiceError::AccountDisabled) => {
+            ApiResponse::forbidden(err.to_string().as_str()).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn logout(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match auth_service(&state).logout(&user.sid).await {
+        Ok(_) => {
+            let event = AuditEvent::new(AuditAction::Logout, AuditOutcome::Success, &client);
+            audit_service(&state).record(event.by_owner(user.id)).await;
+            ApiResponse::ok("Logout successful", None::<()>).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn resend_verification(
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<EmailRequest>,
+) -> impl IntoResponse {
+    match auth_service(&state)
+        .resend_email_verification(&payload.email)
+        .await
+    {
+        Ok(()) => ApiResponse::ok(
+            "If the account exists and is not verified, a new email was sent",
+            None::<()>,
+        )
+        .into_response(),
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
-    let v1: Router<Arc<AppState>> = Router::new().route("/login", post(login));
+async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<OneTimeTokenRequest>,
+) -> impl IntoResponse {
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match auth_service(&state).verify_email(&payload.token).await {
+        Ok(()) => ApiResponse::ok("Email verified successfully", None::<()>).into_response(),
+        Err(err @ AuthServiceError::InvalidOneTimeToken) => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn forgot_password(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<EmailRequest>,
+) -> impl IntoResponse {
+    match auth_service(&state)
+        .request_password_reset(&payload.email)
+        .await
+    {
+        Ok(()) => {
+            let event = AuditEvent::new(
+                AuditAction::PasswordResetRequested,
+                AuditOutcome::Success,
+                &client,
+            );
+            audit_service(&state)
+                .record_for_email(event, &payload.email)
+                .await;
+            ApiResponse::ok(
+                "If the account exists, a password reset email was sent",
+                None::<()>,
+            )
+            .into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<ResetPasswordRequest>,
+) -> impl IntoResponse {
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match auth_service(&state)
+        .reset_password(&payload.token, &payload.new_password)
+        .await
+    {
+        Ok(user_id) => {
+            let event =
+                AuditEvent::new(AuditAction::PasswordReset, AuditOutcome::Success, &client);
+            audit_service(&state).record(event.target(user_id)).await;
+            ApiResponse::ok("Password reset successfully", None::<()>).into_response()
+        }
+        Err(err @ AuthServiceError::InvalidOneTimeToken) => {
+            let event = AuditEvent::new(AuditAction::PasswordReset, AuditOutcome::Failure, &client)
+                .detail(err.to_string());
+            audit_service(&state).record(event).await;
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let login_limit = middleware::from_fn_with_state(
+        RateLimiter::new(state.rate_limits.clone(), LOGIN_PER_IP, "login-ip")
+            .trusting_proxy_headers(state.config.rate_limit.trust_proxy_headers),
+        limit_by_ip,
+    );
+    let recovery_limit = middleware::from_fn_with_state(
+        RateLimiter::new(
+            state.rate_limits.clone(),
+            ACCOUNT_RECOVERY_PER_IP,
+            "recovery-ip",
+        )
+        .trusting_proxy_headers(state.config.rate_limit.trust_proxy_headers),
+        limit_by_ip,
+    );
+
+    let v1: Router<Arc<AppState>> = Router::new()
+        .route("/logout", post(logout))
+        .route_layer(middleware::from_fn_with_state(
+            state,
+            middlewares::authorize,
+        ))
+        .route("/login", post(login).layer(login_limit))
+        .route("/token/refresh", post(refresh_token))
+        .route("/verify-email", post(verify_email))
+        .route(
+            "/verify-email/resend",
+            post(resend_verification).layer(recovery_limit.clone()),
+        )
+        .route(
+            "/password/forgot",
+            post(forgot_password).layer(recovery_limit.clone()),
+        )
+        .route("/password/reset", post(reset_password).layer(recovery_limit));
     Router::new().nest("/v1", v1)
 }
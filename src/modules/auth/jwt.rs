@@ -2,7 +2,6 @@ use super::dto::AuthState;
 use jsonwebtoken::{
     decode, encode, errors::Result as JwtResult, DecodingKey, EncodingKey, Header, Validation,
 };
-use std::env;
 
 pub struct JwtConfig {
     pub secret: String,
@@ -10,10 +9,9 @@ pub struct JwtConfig {
 }
 
 impl JwtConfig {
-    pub fn new() -> Self {
+    pub fn new(secret: &str) -> Self {
         Self {
-            secret: env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "fd183e2e-4d6c-47cd-89c8-619e8c0e9694 ​​".to_string()),
+            secret: secret.to_string(),
             algorithm: jsonwebtoken::Algorithm::HS256,
         }
     }
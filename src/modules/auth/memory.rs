@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, Bson};
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+use crate::modules::user::types::Email;
+
+use super::models::{LoginAttempt, OneTimeToken, RefreshToken, TokenPurpose};
+use super::repository::{LoginAttemptRepository, OneTimeTokenRepository, RefreshTokenRepository};
+
+pub struct InMemoryRefreshTokenRepository {
+    tokens: MemoryCollection<RefreshToken>,
+}
+
+impl Default for InMemoryRefreshTokenRepository {
+    /// Hashes are unique, like `token_hash_unique`.
+    fn default() -> Self {
+        InMemoryRefreshTokenRepository {
+            tokens: MemoryCollection::default()
+                .unique("token_hash_unique", |token: &RefreshToken| {
+                    Some(Bson::String(token.token_hash.clone()))
+                }),
+        }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for InMemoryRefreshTokenRepository {
+    async fn create_token(&self, new_token: RefreshToken) -> Result<ObjectId, Error> {
+        self.tokens.insert(&new_token)
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>, Error> {
+        self.tokens.find_one(|token| token.token_hash == token_hash)
+    }
+
+    async fn mark_as_used(&self, token_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.tokens.update_one(
+            |token| token.id.as_ref() == Some(token_id) && !token.used,
+            |token| token.used = true,
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn revoke_family(&self, family_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.tokens.update_many(
+            |token| token.family_id == *family_id && !token.revoked,
+            |token| token.revoked = true,
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn revoke_user_families(
+        &self,
+        user_id: &ObjectId,
+        except_family: Option<&ObjectId>,
+    ) -> Result<u64, Error> {
+        let result = self.tokens.update_many(
+            |token| {
+                token.user_id == *user_id
+                    && !token.revoked
+                    && Some(&token.family_id) != except_family
+            },
+            |token| token.revoked = true,
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        self.tokens.delete_many(|token| token.user_id == *user_id)
+    }
+
+    async fn is_family_active(&self, family_id: &ObjectId) -> Result<bool, Error> {
+        let token = self
+            .tokens
+            .find_one(|token| token.family_id == *family_id && !token.revoked)?;
+
+        Ok(token.is_some())
+    }
+}
+
+pub struct InMemoryOneTimeTokenRepository {
+    tokens: MemoryCollection<OneTimeToken>,
+}
+
+impl Default for InMemoryOneTimeTokenRepository {
+    /// Hashes are unique, like `token_hash_unique`.
+    fn default() -> Self {
+        InMemoryOneTimeTokenRepository {
+            tokens: MemoryCollection::default()
+                .unique("token_hash_unique", |token: &OneTimeToken| {
+                    Some(Bson::String(token.token_hash.clone()))
+                }),
+        }
+    }
+}
+
+#[async_trait]
+impl OneTimeTokenRepository for InMemoryOneTimeTokenRepository {
+    async fn create_token(&self, new_token: OneTimeToken) -> Result<ObjectId, Error> {
+        self.tokens.insert(&new_token)
+    }
+
+    async fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<OneTimeToken>, Error> {
+        let matches =
+            |token: &OneTimeToken| token.token_hash == token_hash && token.purpose == purpose;
+        let Some(token) = self
+            .tokens
+            .find_one(|token| matches(token) && !token.used)?
+        else {
+            return Ok(None);
+        };
+        // Only the request that flips `used` gets the token, like `find_one_and_update`.
+        let result = self.tokens.update_one(
+            |stored| stored.id == token.id && !stored.used,
+            |stored| stored.used = true,
+        )?;
+
+        Ok((result.modified > 0).then_some(token))
+    }
+
+    async fn invalidate_user_tokens(
+        &self,
+        user_id: &ObjectId,
+        purpose: TokenPurpose,
+    ) -> Result<u64, Error> {
+        let result = self.tokens.update_many(
+            |token| token.user_id == *user_id && token.purpose == purpose && !token.used,
+            |token| token.used = true,
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        self.tokens.delete_many(|token| token.user_id == *user_id)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryLoginAttemptRepository {
+    attempts: MemoryCollection<LoginAttempt>,
+}
+
+fn same_client(attempt: &LoginAttempt, email: &Email, ip: &str) -> bool {
+    attempt.email.as_str() == email.as_str() && attempt.ip == ip
+}
+
+#[async_trait]
+impl LoginAttemptRepository for InMemoryLoginAttemptRepository {
+    async fn find(&self, email: &Email, ip: &str) -> Result<Option<LoginAttempt>, Error> {
+        self.attempts
+            .find_one(|attempt| same_client(attempt, email, ip))
+    }
+
+    async fn record_failure(
+        &self,
+        email: &Email,
+        ip: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error> {
+        let result = self.attempts.update_one(
+            |attempt| same_client(attempt, email, ip),
+            |attempt| {
+                attempt.failures += 1;
+                attempt.last_failure_at = now;
+            },
+        )?;
+        if result.matched == 0 {
+            self.attempts.insert(&LoginAttempt {
+                email: email.clone(),
+                ip: ip.to_string(),
+                failures: 1,
+                last_failure_at: now,
+                locked_until: None,
+            })?;
+        }
+
+        self.find(email, ip).await
+    }
+
+    async fn lock_until(&self, email: &Email, ip: &str, until: DateTime<Utc>) -> Result<(), Error> {
+        self.attempts.update_one(
+            |attempt| same_client(attempt, email, ip),
+            |attempt| attempt.locked_until = Some(until),
+        )?;
+        Ok(())
+    }
+
+    async fn clear(&self, email: &Email, ip: &str) -> Result<(), Error> {
+        self.attempts
+            .delete_one(|attempt| same_client(attempt, email, ip))?;
+        Ok(())
+    }
+
+    async fn clear_email(&self, email: &Email) -> Result<(), Error> {
+        self.attempts
+            .delete_many(|attempt| attempt.email.as_str() == email.as_str())?;
+        Ok(())
+    }
+}
@@ -1,14 +1,21 @@
+use std::sync::Arc;
+
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{Request, State},
     http::{self, Response},
     middleware::Next,
 };
+use log::error;
 
 use super::jwt::JwtConfig;
-use crate::helpers::api_response::ApiResponse;
+use crate::{helpers::api_response::ApiResponse, AppState};
 
-pub async fn authorize(mut req: Request, next: Next) -> Result<Response<Body>, ApiResponse> {
+pub async fn authorize(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response<Body>, ApiResponse> {
     let auth_header = req.headers_mut().get(http::header::AUTHORIZATION);
 
     let auth_header = match auth_header {
@@ -25,15 +32,27 @@ pub async fn authorize(mut req: Request, next: Next) -> Result<Response<Body>, A
     let mut header = auth_header.split_whitespace();
     let (_, token) = (header.next(), header.next());
 
-    let jwt = JwtConfig::new();
+    let jwt = JwtConfig::new(&state.config.auth.jwt_secret);
     let token_data = match jwt.decode_token(token.unwrap()) {
         Ok(data) => data,
         Err(err) => {
-            eprintln!("Error decoding token: {:?}", err);
+            error!("Error decoding token: {:?}", err);
             return Err(ApiResponse::unauthorized("Invalid token"));
         }
     };
 
+    match state.repositories.refresh_tokens.clone()
+        .is_family_active(&token_data.sid)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return Err(ApiResponse::unauthorized("Session has been revoked")),
+        Err(err) => {
+            error!("Error checking session: {:?}", err);
+            return Err(ApiResponse::server_error(None, None::<()>));
+        }
+    }
+
     req.extensions_mut().insert(token_data);
     Ok(next.run(req).await)
 }
@@ -1,7 +1,13 @@
 pub mod dto;
+pub mod extractors;
 pub mod handlers;
 pub mod jwt;
+#[cfg(test)]
+pub mod memory;
 pub mod middlewares;
+pub mod models;
+pub mod password;
+pub mod repository;
 pub mod service;
 
 pub use handlers::handles;
@@ -0,0 +1,79 @@
+use crate::helpers::date_helper::{
+    deserialize_bson_datetime, deserialize_option_bson_datetime, serialize_bson_datetime,
+    serialize_option_bson_datetime,
+};
+use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+use crate::modules::user::types::Email;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshToken {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub family_id: ObjectId, // One family per login session, shared by every rotated token
+    pub token_hash: String,
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
+    pub expires_at: DateTime<Utc>, // BSON date, for the TTL index
+    pub created_at: DateTime<Utc>,
+    pub used: bool,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TokenPurpose {
+    #[serde(rename = "EMAIL_VERIFICATION")]
+    EmailVerification,
+    #[serde(rename = "PASSWORD_RESET")]
+    PasswordReset,
+}
+
+/// A single-use token sent by email. Only its SHA-256 hash is stored.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OneTimeToken {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub purpose: TokenPurpose,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+/// Failed logins for an email address from one client IP, whether or not an account uses
+/// the address, so a lockout does not reveal which addresses are registered. Failures from
+/// one client do not lock the owner out elsewhere.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginAttempt {
+    #[serde(deserialize_with = "Email::deserialize_stored")]
+    pub email: Email,
+    pub ip: String,
+    pub failures: u32,
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
+    pub last_failure_at: DateTime<Utc>, // A BSON date, for the TTL index
+    #[serde(
+        default,
+        serialize_with = "serialize_option_bson_datetime",
+        deserialize_with = "deserialize_option_bson_datetime"
+    )]
+    pub locked_until: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,139 @@
+use std::sync::LazyLock;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use log::error;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+use tokio::task::{self, JoinError};
+
+use crate::helpers::token_helper::generate_token;
+use crate::modules::user::types::{HashedPassword, Password};
+
+const PHC_PREFIX: &str = "$argon2";
+
+// Checked when no account matches, so unknown emails take as long to reject as wrong
+// passwords. Its password is never known to anyone.
+static DUMMY_HASH: LazyLock<HashedPassword> = LazyLock::new(|| {
+    let password = Password::parse(&generate_token()).expect("tokens are valid passwords");
+    hash_blocking(&password).expect("hashing a generated password")
+});
+
+#[derive(Error, Debug)]
+pub enum PasswordError {
+    #[error("Failed to hash password: {0}")]
+    Hash(argon2::password_hash::Error),
+
+    #[error("Password hashing task failed: {0}")]
+    Task(#[from] JoinError),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PasswordVerification {
+    Valid,
+    /// The stored value is a legacy plaintext password that matched and must be rehashed.
+    ValidLegacy,
+    Invalid,
+}
+
+/// Hashes a password with Argon2id, returning it in PHC string format. Runs on the blocking
+/// thread pool, since a hash takes long enough to stall the other requests of a worker.
+pub async fn hash_password(password: &Password) -> Result<HashedPassword, PasswordError> {
+    let password = password.clone();
+    task::spawn_blocking(move || hash_blocking(&password)).await?
+}
+
+/// Verifies a password against the stored value, accepting legacy plaintext records. Runs
+/// on the blocking thread pool like `hash_password`.
+pub async fn verify_password(password: &Password, stored: &HashedPassword) -> PasswordVerification {
+    let (password, stored) = (password.clone(), stored.clone());
+    match task::spawn_blocking(move || verify_blocking(&password, &stored)).await {
+        Ok(verification) => verification,
+        Err(err) => {
+            error!("Password verification task failed: {}", err);
+            PasswordVerification::Invalid
+        }
+    }
+}
+
+/// Spends the time of a verification without any account to check, always failing.
+pub async fn verify_dummy(password: &Password) -> PasswordVerification {
+    let password = password.clone();
+    let _ = task::spawn_blocking(move || verify_blocking(&password, &DUMMY_HASH)).await;
+    PasswordVerification::Invalid
+}
+
+fn hash_blocking(password: &Password) -> Result<HashedPassword, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.expose().as_bytes(), &salt)
+        .map(|hash| HashedPassword::new(hash.to_string()))
+        .map_err(PasswordError::Hash)
+}
+
+fn verify_blocking(password: &Password, stored: &HashedPassword) -> PasswordVerification {
+    let (password, stored) = (password.expose(), stored.as_str());
+    if !stored.starts_with(PHC_PREFIX) {
+        return if bool::from(password.as_bytes().ct_eq(stored.as_bytes())) {
+            PasswordVerification::ValidLegacy
+        } else {
+            PasswordVerification::Invalid
+        };
+    }
+
+    match PasswordHash::new(stored) {
+        Ok(hash)
+            if Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok() =>
+        {
+            PasswordVerification::Valid
+        }
+        _ => PasswordVerification::Invalid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hashed_passwords_verify_only_with_the_same_password() {
+        let password = Password::parse("Str0ng!Passw0rd").unwrap();
+        let hash = hash_password(&password).await.unwrap();
+
+        assert!(hash.as_str().starts_with(PHC_PREFIX));
+        assert_ne!(
+            hash_password(&password).await.unwrap().as_str(),
+            hash.as_str()
+        );
+        assert_eq!(
+            verify_password(&password, &hash).await,
+            PasswordVerification::Valid
+        );
+        let wrong = Password::parse("Wr0ng!Passw0rd").unwrap();
+        assert_eq!(
+            verify_password(&wrong, &hash).await,
+            PasswordVerification::Invalid
+        );
+        assert_eq!(verify_dummy(&password).await, PasswordVerification::Invalid);
+    }
+
+    #[tokio::test]
+    async fn legacy_plaintext_passwords_verify_as_legacy() {
+        let password = Password::parse("Str0ng!Passw0rd").unwrap();
+        let stored = HashedPassword::new("Str0ng!Passw0rd".to_string());
+
+        assert_eq!(
+            verify_password(&password, &stored).await,
+            PasswordVerification::ValidLegacy
+        );
+        let wrong = Password::parse("Wr0ng!Passw0rd").unwrap();
+        assert_eq!(
+            verify_password(&wrong, &stored).await,
+            PasswordVerification::Invalid
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::Error;
+use mongodb::{bson::doc, Collection, Database};
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::to_bson;
+use mongodb::options::ReturnDocument;
+
+use super::models::{LoginAttempt, OneTimeToken, RefreshToken, TokenPurpose};
+use crate::helpers::date_helper::to_bson_datetime;
+use crate::modules::user::types::Email;
+
+#[async_trait]
+pub trait RefreshTokenRepository: Send + Sync {
+    async fn create_token(&self, new_token: RefreshToken) -> Result<ObjectId, Error>;
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>, Error>;
+
+    /// Atomically flags a token as used; returns false if it had already been consumed.
+    async fn mark_as_used(&self, token_id: &ObjectId) -> Result<bool, Error>;
+
+    async fn revoke_family(&self, family_id: &ObjectId) -> Result<u64, Error>;
+
+    /// Revokes every session of the user, optionally keeping the current one.
+    async fn revoke_user_families(
+        &self,
+        user_id: &ObjectId,
+        except_family: Option<&ObjectId>,
+    ) -> Result<u64, Error>;
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error>;
+
+    async fn is_family_active(&self, family_id: &ObjectId) -> Result<bool, Error>;
+}
+
+#[async_trait]
+pub trait OneTimeTokenRepository: Send + Sync {
+    async fn create_token(&self, new_token: OneTimeToken) -> Result<ObjectId, Error>;
+
+    /// Atomically marks an unused token as used and returns it, so a token can only be
+    /// consumed once even under concurrent requests. Expiry is left to the caller.
+    async fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<OneTimeToken>, Error>;
+
+    /// Invalidates the user's outstanding tokens, so only the latest one sent works.
+    async fn invalidate_user_tokens(
+        &self,
+        user_id: &ObjectId,
+        purpose: TokenPurpose,
+    ) -> Result<u64, Error>;
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error>;
+}
+
+#[async_trait]
+pub trait LoginAttemptRepository: Send + Sync {
+    async fn find(&self, email: &Email, ip: &str) -> Result<Option<LoginAttempt>, Error>;
+
+    /// Atomically counts one more failure and returns the updated record.
+    async fn record_failure(
+        &self,
+        email: &Email,
+        ip: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error>;
+
+    async fn lock_until(
+        &self,
+        email: &Email,
+        ip: &str,
+        until: DateTime<Utc>,
+    ) -> Result<(), Error>;
+
+    async fn clear(&self, email: &Email, ip: &str) -> Result<(), Error>;
+
+    /// Clears the failures of `email` from every IP.
+    async fn clear_email(&self, email: &Email) -> Result<(), Error>;
+}
+
+pub struct MongoRefreshTokenRepository {
+    collection: Collection<RefreshToken>,
+}
+
+impl MongoRefreshTokenRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("refresh_tokens");
+        MongoRefreshTokenRepository { collection }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for MongoRefreshTokenRepository {
+    async fn create_token(&self, new_token: RefreshToken) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(new_token).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>, Error> {
+        self.collection
+            .find_one(doc! { "token_hash": token_hash })
+            .await
+    }
+
+    async fn mark_as_used(&self, token_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "_id": token_id, "used": false };
+        let update = doc! { "$set": { "used": true } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn revoke_family(&self, family_id: &ObjectId) -> Result<u64, Error> {
+        let filter = doc! { "family_id": family_id, "revoked": false };
+        let update = doc! { "$set": { "revoked": true } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn revoke_user_families(
+        &self,
+        user_id: &ObjectId,
+        except_family: Option<&ObjectId>,
+    ) -> Result<u64, Error> {
+        let mut filter = doc! { "user_id": user_id, "revoked": false };
+        if let Some(family_id) = except_family {
+            filter.insert("family_id", doc! { "$ne": family_id });
+        }
+        let update = doc! { "$set": { "revoked": true } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .delete_many(doc! { "user_id": user_id })
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn is_family_active(&self, family_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "family_id": family_id, "revoked": false };
+        let token = self.collection.find_one(filter).await?;
+
+        Ok(token.is_some())
+    }
+}
+
+pub struct MongoOneTimeTokenRepository {
+    collection: Collection<OneTimeToken>,
+}
+
+impl MongoOneTimeTokenRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("one_time_tokens");
+        MongoOneTimeTokenRepository { collection }
+    }
+}
+
+#[async_trait]
+impl OneTimeTokenRepository for MongoOneTimeTokenRepository {
+    async fn create_token(&self, new_token: OneTimeToken) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(new_token).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+    ) -> Result<Option<OneTimeToken>, Error> {
+        let filter = doc! {
+            "token_hash": token_hash,
+            "purpose": to_bson(&purpose)?,
+            "used": false,
+        };
+        let update = doc! { "$set": { "used": true } };
+        self.collection.find_one_and_update(filter, update).await
+    }
+
+    async fn invalidate_user_tokens(
+        &self,
+        user_id: &ObjectId,
+        purpose: TokenPurpose,
+    ) -> Result<u64, Error> {
+        let filter = doc! { "user_id": user_id, "purpose": to_bson(&purpose)?, "used": false };
+        let update = doc! { "$set": { "used": true } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_user_tokens(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .delete_many(doc! { "user_id": user_id })
+            .await?;
+
+        Ok(result.deleted_count)
+    }
+}
+
+pub struct MongoLoginAttemptRepository {
+    collection: Collection<LoginAttempt>,
+}
+
+impl MongoLoginAttemptRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("login_attempts");
+        MongoLoginAttemptRepository { collection }
+    }
+}
+
+#[async_trait]
+impl LoginAttemptRepository for MongoLoginAttemptRepository {
+    async fn find(&self, email: &Email, ip: &str) -> Result<Option<LoginAttempt>, Error> {
+        self.collection
+            .find_one(doc! { "email": email.as_str(), "ip": ip })
+            .await
+    }
+
+    async fn record_failure(
+        &self,
+        email: &Email,
+        ip: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<LoginAttempt>, Error> {
+        let update = doc! {
+            "$inc": { "failures": 1 },
+            "$set": { "last_failure_at": to_bson_datetime(now) },
+        };
+        self.collection
+            .find_one_and_update(doc! { "email": email.as_str(), "ip": ip }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+    }
+
+    async fn lock_until(
+        &self,
+        email: &Email,
+        ip: &str,
+        until: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let update = doc! { "$set": { "locked_until": to_bson_datetime(until) } };
+        self.collection
+            .update_one(doc! { "email": email.as_str(), "ip": ip }, update)
+            .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, email: &Email, ip: &str) -> Result<(), Error> {
+        self.collection
+            .delete_one(doc! { "email": email.as_str(), "ip": ip })
+            .await?;
+        Ok(())
+    }
+
+    async fn clear_email(&self, email: &Email) -> Result<(), Error> {
+        self.collection
+            .delete_many(doc! { "email": email.as_str() })
+            .await?;
+        Ok(())
+    }
+}
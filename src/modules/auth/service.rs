@@ -1,65 +1,714 @@
+use std::sync::Arc;
 use thiserror::Error;
 
 use super::{
-    dto::{AuthState, UserLoginResponse},
+    dto::{AuthState, TokenResponse, UserLoginResponse},
     jwt::JwtConfig,
+    models::{OneTimeToken, RefreshToken, TokenPurpose},
+    password::{self, PasswordVerification},
+    repository::{LoginAttemptRepository, OneTimeTokenRepository, RefreshTokenRepository},
 };
+use crate::config::app::AuthConfig;
+use crate::helpers::token_helper::{generate_token, hash_token};
+use crate::modules::mail::mailer::{Mail, MailError, Mailer};
+use crate::modules::rate_limit::limiter::{RateDecision, RateLimiter};
+use crate::modules::user::models::User;
 use crate::modules::user::service::{UserService, UserServiceError};
+use crate::modules::user::types::{Email, Password};
 use chrono::{Duration, Utc};
+use log::{info, warn};
+use mongodb::bson::oid::ObjectId;
+
+const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+const EMAIL_VERIFICATION_TTL_HOURS: i64 = 48;
+const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+const LOCKOUT_THRESHOLD: u32 = 5;
+const LOCKOUT_BASE_MINUTES: i64 = 1;
+const LOCKOUT_MAX_MINUTES: i64 = 60;
+// Failures older than this no longer count towards a lockout.
+pub const FAILED_LOGIN_WINDOW_HOURS: i64 = 24;
 
 #[derive(Error, Debug)]
 pub enum AuthServiceError {
     #[error("Invalid email or password")]
     Unauthorized,
 
+    #[error("Invalid or expired refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Too many login attempts, please try again later")]
+    TooManyAttempts(std::time::Duration),
+
+    #[error("Refresh token reuse detected, session revoked")]
+    RefreshTokenReused(ObjectId), // Owner of the revoked session
+
+    #[error("This account has been disabled")]
+    AccountDisabled,
+
+    #[error("Email address has not been verified")]
+    EmailNotVerified,
+
+    #[error("Invalid or expired token")]
+    InvalidOneTimeToken,
+
+    #[error("Failed to send email: {0}")]
+    MailError(#[from] MailError),
+
     #[error("User error: {0}")]
     UserService(#[from] UserServiceError),
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+
+    #[error("Token error: {0}")]
+    TokenError(#[from] jsonwebtoken::errors::Error),
 }
 
 pub struct AuthService {
     jwt_config: JwtConfig,
     user_service: UserService,
+    token_repository: Arc<dyn RefreshTokenRepository>,
+    one_time_token_repository: Arc<dyn OneTimeTokenRepository>,
+    login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+    account_limiter: RateLimiter,
+    mailer: Arc<dyn Mailer>,
+    require_verified_email: bool,
 }
 
 impl AuthService {
-    pub fn new(user_service: UserService) -> Self {
+    pub fn new(
+        user_service: UserService,
+        token_repository: Arc<dyn RefreshTokenRepository>,
+        one_time_token_repository: Arc<dyn OneTimeTokenRepository>,
+        login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+        account_limiter: RateLimiter,
+        mailer: Arc<dyn Mailer>,
+        config: &AuthConfig,
+    ) -> Self {
         Self {
-            jwt_config: JwtConfig::new(),
+            jwt_config: JwtConfig::new(&config.jwt_secret),
             user_service,
+            token_repository,
+            one_time_token_repository,
+            login_attempt_repository,
+            account_limiter,
+            mailer,
+            require_verified_email: config.require_email_verification,
         }
     }
 
+    /// Attempts are limited and failures counted per email and client IP, so a client
+    /// guessing passwords locks itself out without locking out the owner.
     pub async fn login(
         &self,
-        email: &str,
-        password: &str,
+        email: &Email,
+        password: &Password,
+        client_ip: &str,
     ) -> Result<UserLoginResponse, AuthServiceError> {
+        let account_key = format!("{}|{}", email, client_ip);
+        if let RateDecision::Limited(retry_after) = self.account_limiter.check(&account_key).await {
+            return Err(AuthServiceError::TooManyAttempts(retry_after));
+        }
+        self.check_lockout(email, client_ip).await?;
+
+        let user = match self.verify_credentials(email, password).await {
+            Ok(user) => user,
+            Err(AuthServiceError::Unauthorized) => {
+                self.record_failed_login(email, client_ip).await?;
+                return Err(AuthServiceError::Unauthorized);
+            }
+            Err(err) => return Err(err),
+        };
+        self.login_attempt_repository.clear(email, client_ip).await?;
+
+        if user.disabled {
+            return Err(AuthServiceError::AccountDisabled);
+        }
+        if self.require_verified_email && !user.verified {
+            return Err(AuthServiceError::EmailNotVerified);
+        }
+
+        let tokens = self.issue_tokens(&user, ObjectId::new()).await?;
+        Ok(UserLoginResponse {
+            id: user.id.unwrap(),
+            email: user.email,
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
+        })
+    }
+
+    async fn verify_credentials(
+        &self,
+        email: &Email,
+        password: &Password,
+    ) -> Result<User, AuthServiceError> {
+        let Some(user) = self.user_service.find_user_by_email(email).await? else {
+            password::verify_dummy(password).await;
+            return Err(AuthServiceError::Unauthorized);
+        };
+
+        match password::verify_password(password, &user.password).await {
+            PasswordVerification::Valid => {}
+            PasswordVerification::ValidLegacy => {
+                match self
+                    .user_service
+                    .update_password(&user.id.unwrap(), password)
+                    .await
+                {
+                    Ok(_) => info!("Rehashed legacy password for user {}", user.id.unwrap()),
+                    Err(err) => warn!(
+                        "Failed to rehash legacy password for user {}: {}",
+                        user.id.unwrap(),
+                        err
+                    ),
+                }
+            }
+            PasswordVerification::Invalid => return Err(AuthServiceError::Unauthorized),
+        }
+        Ok(user)
+    }
+
+    /// Rejects the login while the address is locked for the client, even with the right
+    /// password.
+    async fn check_lockout(&self, email: &Email, client_ip: &str) -> Result<(), AuthServiceError> {
+        let Some(attempt) = self.login_attempt_repository.find(email, client_ip).await? else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        if let Some(locked_until) = attempt.locked_until.filter(|until| *until > now) {
+            let retry_after = (locked_until - now).to_std().unwrap_or_default();
+            return Err(AuthServiceError::TooManyAttempts(retry_after));
+        }
+        if now - attempt.last_failure_at > Duration::hours(FAILED_LOGIN_WINDOW_HOURS) {
+            self.login_attempt_repository.clear(email, client_ip).await?;
+        }
+        Ok(())
+    }
+
+    /// Locks the address once it reaches `LOCKOUT_THRESHOLD` failures, doubling the lock
+    /// with every further failure up to `LOCKOUT_MAX_MINUTES`.
+    async fn record_failed_login(
+        &self,
+        email: &Email,
+        client_ip: &str,
+    ) -> Result<(), AuthServiceError> {
+        let now = Utc::now();
+        let Some(attempt) = self
+            .login_attempt_repository
+            .record_failure(email, client_ip, now)
+            .await?
+        else {
+            return Ok(());
+        };
+        if attempt.failures < LOCKOUT_THRESHOLD {
+            return Ok(());
+        }
+
+        let doublings = (attempt.failures - LOCKOUT_THRESHOLD).min(8);
+        let lock = Duration::minutes(LOCKOUT_BASE_MINUTES * (1 << doublings))
+            .min(Duration::minutes(LOCKOUT_MAX_MINUTES));
+        warn!(
+            "Locking logins for {} from {} for {} minutes after {} failures",
+            email,
+            client_ip,
+            lock.num_minutes(),
+            attempt.failures
+        );
+        self.login_attempt_repository
+            .lock_until(email, client_ip, now + lock)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenResponse, AuthServiceError> {
+        let stored = self
+            .token_repository
+            .find_by_hash(&hash_token(refresh_token))
+            .await?
+            .ok_or(AuthServiceError::InvalidRefreshToken)?;
+
+        if stored.revoked {
+            return Err(AuthServiceError::InvalidRefreshToken);
+        }
+
+        if stored.used
+            || !self
+                .token_repository
+                .mark_as_used(&stored.id.unwrap())
+                .await?
+        {
+            warn!(
+                "Refresh token reuse detected for user {}, revoking family {}",
+                stored.user_id, stored.family_id
+            );
+            self.token_repository
+                .revoke_family(&stored.family_id)
+                .await?;
+            return Err(AuthServiceError::RefreshTokenReused(stored.user_id));
+        }
+
+        if stored.expires_at <= Utc::now() {
+            return Err(AuthServiceError::InvalidRefreshToken);
+        }
+
         let user = self
             .user_service
-            .find_user_by_email(email)
+            .find_user_by_id(&stored.user_id)
+            .await?
+            .ok_or(AuthServiceError::InvalidRefreshToken)?;
+        if user.disabled {
+            return Err(AuthServiceError::AccountDisabled);
+        }
+
+        self.issue_tokens(&user, stored.family_id).await
+    }
+
+    pub async fn logout(&self, session_id: &ObjectId) -> Result<(), AuthServiceError> {
+        self.token_repository.revoke_family(session_id).await?;
+        Ok(())
+    }
+
+    pub async fn send_email_verification(
+        &self,
+        user_id: &ObjectId,
+        name: &str,
+        email: &Email,
+    ) -> Result<(), AuthServiceError> {
+        let token = self
+            .issue_one_time_token(
+                user_id,
+                TokenPurpose::EmailVerification,
+                Duration::hours(EMAIL_VERIFICATION_TTL_HOURS),
+            )
+            .await?;
+
+        self.mailer
+            .send(Mail {
+                to_name: Some(name.to_string()),
+                to: email.clone(),
+                subject: "Confirm your PlanIt email address".to_string(),
+                body: format!(
+                    "Use this code to confirm your email address:\n\n{}\n\nIt expires in {} hours.",
+                    token, EMAIL_VERIFICATION_TTL_HOURS
+                ),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Sends a new verification email if the account exists and is not verified yet. Succeeds
+    /// either way, so the response does not reveal which addresses have an account.
+    pub async fn resend_email_verification(&self, email: &Email) -> Result<(), AuthServiceError> {
+        match self.user_service.find_user_by_email(email).await? {
+            Some(user) if !user.verified => {
+                self.send_email_verification(&user.id.unwrap(), &user.name, &user.email)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn verify_email(&self, token: &str) -> Result<(), AuthServiceError> {
+        let stored = self
+            .consume_one_time_token(token, TokenPurpose::EmailVerification)
+            .await?;
+        self.user_service.mark_verified(&stored.user_id).await?;
+        Ok(())
+    }
+
+    /// Emails a password reset code if the account exists. Succeeds either way, so the
+    /// response does not reveal which addresses have an account.
+    pub async fn request_password_reset(&self, email: &Email) -> Result<(), AuthServiceError> {
+        let Some(user) = self.user_service.find_user_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let token = self
+            .issue_one_time_token(
+                &user.id.unwrap(),
+                TokenPurpose::PasswordReset,
+                Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+            )
+            .await?;
+
+        self.mailer
+            .send(Mail {
+                to_name: Some(user.name),
+                to: user.email,
+                subject: "Reset your PlanIt password".to_string(),
+                body: format!(
+                    "Use this code to choose a new password:\n\n{}\n\nIt expires in {} minutes. If you did not ask for a reset, ignore this message.",
+                    token, PASSWORD_RESET_TTL_MINUTES
+                ),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Sets a new password and signs the user out everywhere. Receiving the code also proves
+    /// ownership of the address, so the account becomes verified. Returns the user's id.
+    pub async fn reset_password(
+        &self,
+        token: &str,
+        new_password: &Password,
+    ) -> Result<ObjectId, AuthServiceError> {
+        let stored = self
+            .consume_one_time_token(token, TokenPurpose::PasswordReset)
+            .await?;
+
+        self.user_service
+            .update_password(&stored.user_id, new_password)
+            .await?;
+        self.user_service.mark_verified(&stored.user_id).await?;
+        self.token_repository
+            .revoke_user_families(&stored.user_id, None)
+            .await?;
+        Ok(stored.user_id)
+    }
+
+    /// Creates a token for `purpose`, invalidating the ones sent before.
+    async fn issue_one_time_token(
+        &self,
+        user_id: &ObjectId,
+        purpose: TokenPurpose,
+        ttl: Duration,
+    ) -> Result<String, AuthServiceError> {
+        self.one_time_token_repository
+            .invalidate_user_tokens(user_id, purpose)
+            .await?;
+
+        let token = generate_token();
+        let now = Utc::now();
+        self.one_time_token_repository
+            .create_token(OneTimeToken {
+                id: None,
+                user_id: *user_id,
+                purpose,
+                token_hash: hash_token(&token),
+                expires_at: now + ttl,
+                created_at: now,
+                used: false,
+            })
+            .await?;
+        Ok(token)
+    }
+
+    async fn consume_one_time_token(
+        &self,
+        token: &str,
+        purpose: TokenPurpose,
+    ) -> Result<OneTimeToken, AuthServiceError> {
+        self.one_time_token_repository
+            .consume(&hash_token(token), purpose)
+            .await?
+            .filter(|stored| stored.expires_at > Utc::now())
+            .ok_or(AuthServiceError::InvalidOneTimeToken)
+    }
+
+    async fn issue_tokens(
+        &self,
+        user: &User,
+        family_id: ObjectId,
+    ) -> Result<TokenResponse, AuthServiceError> {
+        let user_id = &user.id.unwrap();
+        let now = Utc::now();
+        let exp: usize = (now + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES)).timestamp() as usize;
+        let token = self.jwt_config.encode_token(AuthState {
+            id: *user_id,
+            email: user.email.clone(),
+            sid: family_id,
+            role: user.role,
+            exp,
+        })?;
+
+        let refresh_token = generate_token();
+        self.token_repository
+            .create_token(RefreshToken {
+                id: None,
+                user_id: *user_id,
+                family_id,
+                token_hash: hash_token(&refresh_token),
+                expires_at: now + Duration::days(REFRESH_TOKEN_TTL_DAYS),
+                created_at: now,
+                used: false,
+                revoked: false,
+            })
+            .await?;
+
+        Ok(TokenResponse {
+            token,
+            refresh_token,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::mail::outbox::InMemoryOutbox;
+    use crate::modules::rate_limit::{limiter::LOGIN_PER_ACCOUNT, memory::InMemoryStore};
+    use crate::modules::user::types::HashedPassword;
+
+    use super::*;
+
+    const PASSWORD: &str = "Str0ng!Passw0rd";
+
+    fn auth_service(repositories: &Repositories) -> AuthService {
+        auth_service_with_outbox(repositories, Arc::new(InMemoryOutbox::default()))
+    }
+
+    fn auth_service_with_outbox(
+        repositories: &Repositories,
+        outbox: Arc<InMemoryOutbox>,
+    ) -> AuthService {
+        AuthService::new(
+            UserService::new(repositories.users.clone()),
+            repositories.refresh_tokens.clone(),
+            repositories.one_time_tokens.clone(),
+            repositories.login_attempts.clone(),
+            RateLimiter::new(
+                Arc::new(InMemoryStore::default()),
+                LOGIN_PER_ACCOUNT,
+                "login-account",
+            ),
+            outbox,
+            &AuthConfig::default(),
+        )
+    }
+
+    async fn create_user(repositories: &Repositories, email: &str) -> Email {
+        UserService::new(repositories.users.clone())
+            .create_user(
+                serde_json::from_value(json!({
+                    "name": "Ana",
+                    "email": email,
+                    "password": PASSWORD,
+                    "phone": "+5511999999999",
+                }))
+                .unwrap(),
+            )
             .await
-            .map_err(AuthServiceError::from)?;
-        match user {
-            Some(user) => {
-                if user.password != password {
-                    return Err(AuthServiceError::Unauthorized);
-                }
+            .unwrap();
+        Email::parse(email).unwrap()
+    }
+
+    /// The code in the last mail sent, which is on a line of its own.
+    fn sent_code(outbox: &InMemoryOutbox) -> String {
+        let mail = outbox.messages().pop().expect("a mail was sent");
+        mail.body.split("\n\n").nth(1).unwrap().to_string()
+    }
 
-                let now = Utc::now();
-                let expire: chrono::TimeDelta = Duration::hours(24);
-                let exp: usize = (now + expire).timestamp() as usize;
-                let token = self.jwt_config.encode_token(AuthState {
-                    id: user.id.unwrap(),
-                    email: user.email.clone(),
-                    exp,
-                });
-                Ok(UserLoginResponse {
-                    id: user.id.unwrap(),
-                    email: user.email,
-                    token: token.unwrap(),
-                })
+    #[tokio::test]
+    async fn failed_logins_lock_out_only_the_guessing_client() {
+        let repositories = Repositories::in_memory();
+        let service = auth_service(&repositories);
+        let email = create_user(&repositories, "ana@example.com").await;
+        let password = Password::parse(PASSWORD).unwrap();
+        let wrong = Password::parse("Wr0ng!Passw0rd").unwrap();
+
+        for _ in 0..LOCKOUT_THRESHOLD {
+            assert!(matches!(
+                service.login(&email, &wrong, "10.0.0.1").await,
+                Err(AuthServiceError::Unauthorized)
+            ));
+        }
+        // Past the account limit too, which is counted per client as well.
+        for _ in 0..LOGIN_PER_ACCOUNT.capacity {
+            assert!(matches!(
+                service.login(&email, &wrong, "10.0.0.1").await,
+                Err(AuthServiceError::TooManyAttempts(_))
+            ));
+        }
+        match service.login(&email, &password, "10.0.0.1").await {
+            Err(AuthServiceError::TooManyAttempts(retry_after)) => {
+                assert!(!retry_after.is_zero())
             }
-            None => Err(AuthServiceError::Unauthorized),
+            other => panic!("expected a lockout, got {:?}", other.map(|res| res.id)),
         }
+
+        assert!(service.login(&email, &password, "10.0.0.2").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn legacy_passwords_are_rehashed_on_login() {
+        let repositories = Repositories::in_memory();
+        let service = auth_service(&repositories);
+        let email = create_user(&repositories, "ana@example.com").await;
+        let password = Password::parse(PASSWORD).unwrap();
+        let user = repositories
+            .users
+            .find_user_by_email(&email)
+            .await
+            .unwrap()
+            .unwrap();
+        repositories
+            .users
+            .update_password(
+                &user.id.unwrap(),
+                &HashedPassword::new(PASSWORD.to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert!(service.login(&email, &password, "10.0.0.1").await.is_ok());
+        let user = repositories
+            .users
+            .find_user_by_email(&email)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(user.password.as_str().starts_with("$argon2"));
+        assert!(service.login(&email, &password, "10.0.0.1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unknown_emails_are_rejected_like_wrong_passwords() {
+        let repositories = Repositories::in_memory();
+        let service = auth_service(&repositories);
+        let email = Email::parse("nobody@example.com").unwrap();
+
+        assert!(matches!(
+            service
+                .login(&email, &Password::parse(PASSWORD).unwrap(), "10.0.0.1")
+                .await,
+            Err(AuthServiceError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn password_reset_codes_work_once_and_end_every_session() {
+        let repositories = Repositories::in_memory();
+        let outbox = Arc::new(InMemoryOutbox::default());
+        let service = auth_service_with_outbox(&repositories, outbox.clone());
+        let email = create_user(&repositories, "ana@example.com").await;
+        let session = service
+            .login(&email, &Password::parse(PASSWORD).unwrap(), "10.0.0.1")
+            .await
+            .unwrap();
+
+        service.request_password_reset(&email).await.unwrap();
+        assert_eq!(outbox.messages().len(), 1);
+        assert_eq!(outbox.messages()[0].to, email);
+        let code = sent_code(&outbox);
+
+        let new_password = Password::parse("N3w!Passw0rd").unwrap();
+        service.reset_password(&code, &new_password).await.unwrap();
+        assert!(matches!(
+            service.reset_password(&code, &new_password).await,
+            Err(AuthServiceError::InvalidOneTimeToken)
+        ));
+        assert!(matches!(
+            service.refresh(&session.refresh_token).await,
+            Err(AuthServiceError::InvalidRefreshToken)
+        ));
+        assert!(service
+            .login(&email, &new_password, "10.0.0.1")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn superseded_and_expired_codes_are_rejected() {
+        let repositories = Repositories::in_memory();
+        let outbox = Arc::new(InMemoryOutbox::default());
+        let service = auth_service_with_outbox(&repositories, outbox.clone());
+        let email = create_user(&repositories, "ana@example.com").await;
+        let new_password = Password::parse("N3w!Passw0rd").unwrap();
+
+        service.request_password_reset(&email).await.unwrap();
+        let first = sent_code(&outbox);
+        service.request_password_reset(&email).await.unwrap();
+        assert!(matches!(
+            service.reset_password(&first, &new_password).await,
+            Err(AuthServiceError::InvalidOneTimeToken)
+        ));
+
+        let user = repositories
+            .users
+            .find_user_by_email(&email)
+            .await
+            .unwrap()
+            .unwrap();
+        let expired = generate_token();
+        repositories
+            .one_time_tokens
+            .create_token(OneTimeToken {
+                id: None,
+                user_id: user.id.unwrap(),
+                purpose: TokenPurpose::PasswordReset,
+                token_hash: hash_token(&expired),
+                expires_at: Utc::now() - Duration::minutes(1),
+                created_at: Utc::now() - Duration::minutes(PASSWORD_RESET_TTL_MINUTES + 1),
+                used: false,
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.reset_password(&expired, &new_password).await,
+            Err(AuthServiceError::InvalidOneTimeToken)
+        ));
+
+        service
+            .request_password_reset(&Email::parse("nobody@example.com").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(outbox.messages().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refresh_tokens_rotate_and_reuse_ends_the_session() {
+        let repositories = Repositories::in_memory();
+        let service = auth_service(&repositories);
+        let email = create_user(&repositories, "ana@example.com").await;
+        let password = Password::parse(PASSWORD).unwrap();
+        let session = service.login(&email, &password, "10.0.0.1").await.unwrap();
+        let other_session = service.login(&email, &password, "10.0.0.2").await.unwrap();
+
+        let rotated = service.refresh(&session.refresh_token).await.unwrap();
+        assert_ne!(rotated.refresh_token, session.refresh_token);
+
+        // Replaying the first token looks like a stolen one: its whole family is revoked.
+        assert!(matches!(
+            service.refresh(&session.refresh_token).await,
+            Err(AuthServiceError::RefreshTokenReused(user_id)) if user_id == session.id
+        ));
+        assert!(matches!(
+            service.refresh(&rotated.refresh_token).await,
+            Err(AuthServiceError::InvalidRefreshToken)
+        ));
+        assert!(service.refresh(&other_session.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn logout_ends_only_its_session() {
+        let repositories = Repositories::in_memory();
+        let service = auth_service(&repositories);
+        let email = create_user(&repositories, "ana@example.com").await;
+        let password = Password::parse(PASSWORD).unwrap();
+        let session = service.login(&email, &password, "10.0.0.1").await.unwrap();
+        let other_session = service.login(&email, &password, "10.0.0.2").await.unwrap();
+        let family_id = repositories
+            .refresh_tokens
+            .find_by_hash(&hash_token(&session.refresh_token))
+            .await
+            .unwrap()
+            .unwrap()
+            .family_id;
+
+        service.logout(&family_id).await.unwrap();
+        assert!(!repositories
+            .refresh_tokens
+            .is_family_active(&family_id)
+            .await
+            .unwrap());
+        assert!(matches!(
+            service.refresh(&session.refresh_token).await,
+            Err(AuthServiceError::InvalidRefreshToken)
+        ));
+        assert!(service.refresh(&other_session.refresh_token).await.is_ok());
     }
 }
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    pub token: String,
+}
+
+#[derive(Serialize)]
+pub struct FeedTokenResponse {
+    pub token: String,
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub enum ImportOutcome {
+    #[serde(rename = "CREATED")]
+    Created,
+    #[serde(rename = "SKIPPED")]
+    Skipped,
+    #[serde(rename = "FAILED")]
+    Failed,
+}
+
+#[derive(Serialize)]
+pub struct ImportEntry {
+    pub uid: Option<String>,
+    pub title: Option<String>,
+    pub outcome: ImportOutcome,
+    pub task_id: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct ImportReport {
+    pub created: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub entries: Vec<ImportEntry>,
+}
+
+impl ImportReport {
+    pub fn push(&mut self, entry: ImportEntry) {
+        match entry.outcome {
+            ImportOutcome::Created => self.created += 1,
+            ImportOutcome::Skipped => self.skipped += 1,
+            ImportOutcome::Failed => self.failed += 1,
+        }
+        self.entries.push(entry);
+    }
+}
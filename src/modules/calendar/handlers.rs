@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Query, State},
+    http::header,
+    middleware,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Router,
+};
+
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::{
+        auth::{self, dto::AuthState},
+        category::handlers::category_service,
+        task::handlers::task_service,
+        user::extractors::UserTimeZone,
+    },
+    AppState,
+};
+
+use super::{
+    dto::{FeedQuery, FeedTokenResponse},
+    import::CalendarImportService,
+    service::{CalendarService, CalendarServiceError},
+};
+
+const FEED_PATH: &str = "/v1/calendar/feed.ics";
+const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+fn calendar_service(state: &AppState) -> CalendarService {
+    CalendarService::new(
+        state.repositories.users.clone(),
+        state.repositories.tasks.clone(),
+        state.repositories.categories.clone(),
+    )
+}
+
+async fn get_feed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FeedQuery>,
+) -> impl IntoResponse {
+    match calendar_service(&state).render_feed(&query.token).await {
+        Ok(calendar) => (
+            [
+                (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "inline; filename=\"planit.ics\"",
+                ),
+            ],
+            calendar,
+        )
+            .into_response(),
+        Err(CalendarServiceError::InvalidFeedToken) => {
+            ApiResponse::unauthorized(CalendarServiceError::InvalidFeedToken.to_string().as_str())
+                .into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn create_feed_token(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match calendar_service(&state).rotate_feed_token(&user.id).await {
+        Ok(token) => {
+            let response = FeedTokenResponse {
+                url: format!("{}?token={}", FEED_PATH, token),
+                token,
+            };
+            ApiResponse::created("Calendar feed token created successfully", Some(response))
+                .into_response()
+        }
+        Err(CalendarServiceError::UserNotFound) => {
+            ApiResponse::not_found(CalendarServiceError::UserNotFound.to_string().as_str())
+                .into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn revoke_feed_token(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match calendar_service(&state).revoke_feed_token(&user.id).await {
+        Ok(()) => {
+            ApiResponse::ok("Calendar feed token revoked successfully", None::<()>).into_response()
+        }
+        Err(CalendarServiceError::UserNotFound) => {
+            ApiResponse::not_found(CalendarServiceError::UserNotFound.to_string().as_str())
+                .into_response()
+        }
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn import_calendar(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut content = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => match field.text().await {
+                Ok(text) => {
+                    content = Some(text);
+                    break;
+                }
+                Err(err) => {
+                    return ApiResponse::bad_request(err.body_text().as_str(), None::<()>)
+                        .into_response()
+                }
+            },
+            Ok(Some(_)) => continue,
+            Ok(None) => break,
+            Err(err) => {
+                return ApiResponse::bad_request(err.body_text().as_str(), None::<()>)
+                    .into_response()
+            }
+        }
+    }
+    let Some(content) = content else {
+        return ApiResponse::bad_request("Missing \"file\" field", None::<()>).into_response();
+    };
+
+    let service = CalendarImportService::new(task_service(&state), category_service(&state));
+    let report = service
+        .import_calendar(&user.id, &time_zone, &content)
+        .await;
+
+    ApiResponse::ok("Calendar imported", Some(report)).into_response()
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let v1: Router<Arc<AppState>> = Router::new()
+        .route(
+            "/calendar/feed-token",
+            post(create_feed_token).delete(revoke_feed_token),
+        )
+        .route(
+            "/calendar/import",
+            post(import_calendar).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
+        // Calendar clients cannot send a JWT, so the feed is authenticated by its token.
+        .route("/calendar/feed.ics", get(get_feed));
+    Router::new().nest("/v1", v1)
+}
@@ -0,0 +1,258 @@
+use chrono::{DateTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::modules::{
+    category::models::Category,
+    notification::models::{Notification, TimeUnit},
+    task::models::{Frequency, RecurrenceRule, Task},
+};
+
+const PRODUCT_ID: &str = "-//PlanIt//PlanIt Tasks//EN";
+const UID_DOMAIN: &str = "planit";
+// RFC 5545 section 3.1: lines longer than 75 octets must be folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Builds a VCALENDAR with one VEVENT per task.
+///
+/// Event times are written in the user's time zone, so clients expand recurring tasks the
+/// same way we do and keep their wall-clock time across DST changes. The zone is referenced
+/// by its IANA name rather than described in a VTIMEZONE, as RFC 7809 allows.
+pub struct CalendarWriter {
+    output: String,
+    stamp: DateTime<Utc>,
+    time_zone: Tz,
+}
+
+impl CalendarWriter {
+    pub fn new(name: &str, time_zone: Tz) -> Self {
+        let mut writer = Self {
+            output: String::new(),
+            stamp: Utc::now(),
+            time_zone,
+        };
+        writer.line("BEGIN:VCALENDAR");
+        writer.line("VERSION:2.0");
+        writer.line(&format!("PRODID:{}", PRODUCT_ID));
+        writer.line("CALSCALE:GREGORIAN");
+        writer.line("METHOD:PUBLISH");
+        writer.line(&format!("X-WR-CALNAME:{}", escape_text(name)));
+        writer
+    }
+
+    pub fn add_task(&mut self, task: &Task, category: Option<&Category>) {
+        let Some(task_id) = task.id else {
+            return;
+        };
+
+        self.line("BEGIN:VEVENT");
+        self.line(&format!("UID:{}@{}", task_id, UID_DOMAIN));
+        self.line(&format!("DTSTAMP:{}", format_date(self.stamp)));
+        self.line(&self.date_property("DTSTART", task.start_date));
+        self.line(&self.date_property("DTEND", task.end_date));
+        self.line(&format!("SUMMARY:{}", escape_text(&task.title)));
+        if !task.description.is_empty() {
+            self.line(&format!("DESCRIPTION:{}", escape_text(&task.description)));
+        }
+        if let Some(category) = category {
+            self.line(&format!("CATEGORIES:{}", escape_text(&category.title)));
+            // RFC 7986 COLOR takes a CSS3 color name, which all category colors are.
+            self.line(&format!(
+                "COLOR:{}",
+                category.color.as_str().to_ascii_lowercase()
+            ));
+        }
+        if let Some(rule) = &task.recurrence {
+            self.line(&format!("RRULE:{}", format_rule(rule)));
+            for cancelled in task
+                .occurrence_overrides
+                .iter()
+                .filter(|occurrence| occurrence.cancelled)
+            {
+                self.line(&self.date_property("EXDATE", cancelled.occurrence_start));
+            }
+        }
+        if let Some(notification) = &task.notification {
+            self.add_alarm(task, notification);
+        }
+        self.line("END:VEVENT");
+    }
+
+    pub fn finish(mut self) -> String {
+        self.line("END:VCALENDAR");
+        self.output
+    }
+
+    fn add_alarm(&mut self, task: &Task, notification: &Notification) {
+        let unit = match notification.time_unit {
+            TimeUnit::Minute => "M",
+            TimeUnit::Hour => "H",
+        };
+
+        self.line("BEGIN:VALARM");
+        self.line("ACTION:DISPLAY");
+        self.line(&format!("TRIGGER:-PT{}{}", notification.time_value, unit));
+        self.line(&format!("DESCRIPTION:{}", escape_text(&task.title)));
+        self.line("END:VALARM");
+    }
+
+    /// A DATE-TIME property, in UTC for UTC users and as a `TZID` local time otherwise.
+    fn date_property(&self, name: &str, date: DateTime<Utc>) -> String {
+        if self.time_zone == Tz::UTC {
+            return format!("{}:{}", name, format_date(date));
+        }
+
+        format!(
+            "{};TZID={}:{}",
+            name,
+            self.time_zone.name(),
+            date.with_timezone(&self.time_zone).format("%Y%m%dT%H%M%S")
+        )
+    }
+
+    fn line(&mut self, content: &str) {
+        let mut octets = 0;
+        for c in content.chars() {
+            if octets + c.len_utf8() > MAX_LINE_OCTETS {
+                self.output.push_str("\r\n ");
+                // The leading space of a continuation line counts towards its length.
+                octets = 1;
+            }
+            self.output.push(c);
+            octets += c.len_utf8();
+        }
+        self.output.push_str("\r\n");
+    }
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_rule(rule: &RecurrenceRule) -> String {
+    let frequency = match rule.frequency {
+        Frequency::Daily => "DAILY",
+        Frequency::Weekly => "WEEKLY",
+        Frequency::Monthly => "MONTHLY",
+    };
+
+    let mut parts = vec![format!("FREQ={}", frequency)];
+    if rule.interval > 1 {
+        parts.push(format!("INTERVAL={}", rule.interval));
+    }
+    if rule.frequency == Frequency::Weekly && !rule.by_weekday.is_empty() {
+        let days: Vec<&str> = rule.by_weekday.iter().map(weekday_code).collect();
+        parts.push(format!("BYDAY={}", days.join(",")));
+    }
+    // RFC 5545 forbids COUNT and UNTIL in the same rule; COUNT wins when both are set.
+    // UNTIL stays in UTC even when DTSTART has a TZID, as the RFC requires.
+    if let Some(count) = rule.count {
+        parts.push(format!("COUNT={}", count));
+    } else if let Some(until) = rule.until {
+        parts.push(format!("UNTIL={}", format_date(until)));
+    }
+    parts.join(";")
+}
+
+fn weekday_code(weekday: &Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use mongodb::bson::oid::ObjectId;
+
+    use crate::modules::task::models::{OccurrenceOverride, Status};
+
+    use super::*;
+
+    fn daily_task() -> Task {
+        Task {
+            id: Some(ObjectId::new()),
+            title: "Standup".to_string(),
+            description: String::new(),
+            // 09:00 in Sao Paulo
+            start_date: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            end_date: Utc.with_ymd_and_hms(2024, 3, 1, 12, 15, 0).unwrap(),
+            status: Status::Adiada,
+            user_id: ObjectId::new(),
+            workspace_id: None,
+            assignee_id: None,
+            category_id: ObjectId::new(),
+            goal_id: None,
+            notification: None,
+            recurrence: Some(RecurrenceRule {
+                frequency: Frequency::Daily,
+                interval: 1,
+                by_weekday: Vec::new(),
+                count: None,
+                until: Some(Utc.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap()),
+            }),
+            occurrence_overrides: vec![OccurrenceOverride {
+                occurrence_start: Utc.with_ymd_and_hms(2024, 3, 3, 12, 0, 0).unwrap(),
+                status: None,
+                cancelled: true,
+            }],
+            subtasks: Vec::new(),
+            derive_status: false,
+        }
+    }
+
+    fn render(time_zone: Tz) -> Vec<String> {
+        let mut writer = CalendarWriter::new("PlanIt", time_zone);
+        writer.add_task(&daily_task(), None);
+        writer
+            .finish()
+            .split("\r\n")
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn events_are_written_in_the_user_time_zone() {
+        let lines = render(Tz::America__Sao_Paulo);
+
+        for expected in [
+            "DTSTART;TZID=America/Sao_Paulo:20240301T090000",
+            "DTEND;TZID=America/Sao_Paulo:20240301T091500",
+            "EXDATE;TZID=America/Sao_Paulo:20240303T090000",
+            "RRULE:FREQ=DAILY;UNTIL=20240310T120000Z",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn utc_users_get_utc_times() {
+        let lines = render(Tz::UTC);
+
+        assert!(lines.iter().any(|line| line == "DTSTART:20240301T120000Z"));
+        assert!(lines.iter().any(|line| line == "EXDATE:20240303T120000Z"));
+    }
+}
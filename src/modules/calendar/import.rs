@@ -0,0 +1,594 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::helpers::time_zone_helper::{local_to_utc, DateTimeInput};
+use crate::modules::{
+    category::{
+        models::Color,
+        service::{CategoryService, CategoryServiceError},
+    },
+    notification::models::TimeUnit,
+    task::{
+        dto::{CreateTaskRequest, UpdateOccurrenceRequest},
+        models::{Frequency, RecurrenceRule, Status},
+        service::{TaskService, TaskServiceError},
+    },
+};
+
+use super::{
+    dto::{ImportEntry, ImportOutcome, ImportReport},
+    parser::{self, IcsEvent, Property},
+};
+
+// Events without CATEGORIES are filed here.
+const DEFAULT_CATEGORY: &str = "Imported";
+// Same limits as CreateTaskRequest and CreateCategoryRequest.
+const MAX_TITLE_LENGTH: usize = 30;
+const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+pub struct CalendarImportService {
+    task_service: TaskService,
+    category_service: CategoryService,
+}
+
+impl CalendarImportService {
+    pub fn new(task_service: TaskService, category_service: CategoryService) -> Self {
+        CalendarImportService {
+            task_service,
+            category_service,
+        }
+    }
+
+    /// Creates a task for every VEVENT in `content`. Events are independent: one failing
+    /// does not stop the others, and each gets an entry in the report.
+    pub async fn import_calendar(
+        &self,
+        user_id: &ObjectId,
+        time_zone: &Tz,
+        content: &str,
+    ) -> ImportReport {
+        let mut report = ImportReport::default();
+        let mut categories: HashMap<String, ObjectId> = HashMap::new();
+
+        for event in parser::parse_events(content) {
+            let uid = event.text("UID");
+            let title = event
+                .text("SUMMARY")
+                .map(|summary| truncate(summary.trim(), MAX_TITLE_LENGTH));
+
+            let (outcome, task_id, reason) = match self
+                .import_event(user_id, time_zone, &event, &mut categories)
+                .await
+            {
+                Ok((task_id, note)) => (ImportOutcome::Created, Some(task_id.to_string()), note),
+                Err(ImportError::Skipped(reason)) => (ImportOutcome::Skipped, None, Some(reason)),
+                Err(ImportError::Failed(reason)) => (ImportOutcome::Failed, None, Some(reason)),
+            };
+
+            report.push(ImportEntry {
+                uid,
+                title,
+                outcome,
+                task_id,
+                reason,
+            });
+        }
+
+        report
+    }
+
+    async fn import_event(
+        &self,
+        user_id: &ObjectId,
+        time_zone: &Tz,
+        event: &IcsEvent,
+        categories: &mut HashMap<String, ObjectId>,
+    ) -> Result<(ObjectId, Option<String>), ImportError> {
+        if event
+            .text("STATUS")
+            .is_some_and(|status| status.eq_ignore_ascii_case("CANCELLED"))
+        {
+            return Err(ImportError::Skipped("Event is cancelled".to_string()));
+        }
+
+        let title = event
+            .text("SUMMARY")
+            .map(|summary| truncate(summary.trim(), MAX_TITLE_LENGTH))
+            .filter(|summary| !summary.is_empty())
+            .ok_or_else(|| ImportError::Failed("Event has no SUMMARY".to_string()))?;
+        let description = event
+            .text("DESCRIPTION")
+            .map(|description| truncate(description.trim(), MAX_DESCRIPTION_LENGTH))
+            .filter(|description| !description.is_empty())
+            .unwrap_or_else(|| title.clone());
+
+        let start = event
+            .property("DTSTART")
+            .and_then(|property| parse_date(property, time_zone))
+            .ok_or_else(|| ImportError::Failed("Missing or invalid DTSTART".to_string()))?;
+        let start_date = start.to_utc(time_zone);
+        let end_date = match (event.property("DTEND"), event.property("DURATION")) {
+            (Some(property), _) => parse_date(property, time_zone)
+                .ok_or_else(|| ImportError::Failed("Invalid DTEND".to_string()))?
+                .to_utc(time_zone),
+            (None, Some(property)) => {
+                start_date
+                    + parse_duration(&property.value)
+                        .ok_or_else(|| ImportError::Failed("Invalid DURATION".to_string()))?
+            }
+            // RFC 5545 section 3.6.1: an all-day event without an end lasts one day.
+            (None, None) => match start {
+                EventDate::Date(_) => start_date + Duration::days(1),
+                EventDate::DateTime(_) => start_date,
+            },
+        };
+        if end_date < start_date {
+            return Err(ImportError::Failed("Event ends before it starts".to_string()));
+        }
+
+        let recurrence = event
+            .property("RRULE")
+            .map(|property| parse_rule(&property.value, time_zone))
+            .transpose()
+            .map_err(ImportError::Failed)?;
+        let excluded = excluded_dates(event, time_zone)
+            .map_err(|value| ImportError::Failed(format!("Invalid EXDATE: {}", value)))?;
+        let notification = event
+            .alarms
+            .iter()
+            .find_map(|alarm| parse_alarm(alarm, start_date));
+
+        let category_id = self
+            .resolve_category(user_id, event, categories)
+            .await
+            .map_err(|err| ImportError::Failed(err.to_string()))?;
+
+        // Events that are already over are imported as done, the rest as pending.
+        let status = if end_date < Utc::now() && recurrence.is_none() {
+            Status::Executada
+        } else {
+            Status::Pendente
+        };
+
+        let request = CreateTaskRequest {
+            title,
+            description,
+            start_date: DateTimeInput::Absolute(start_date.fixed_offset()),
+            end_date: DateTimeInput::Absolute(end_date.fixed_offset()),
+            status,
+            category_id,
+            goal_id: None,
+            workspace_id: None,
+            assignee_id: None,
+            notification_time_unit: notification.as_ref().map(|(unit, _)| unit.clone()),
+            notification_time_value: notification.map(|(_, value)| value),
+            recurrence,
+            derive_status: false,
+        };
+        if let Err(errors) = request.validate() {
+            return Err(ImportError::Failed(errors.to_string()));
+        }
+
+        let is_recurring = request.recurrence.is_some();
+        let task_id = match self
+            .task_service
+            .create_task_for_user(user_id, time_zone, request)
+            .await
+        {
+            Ok(task_id) => task_id,
+            Err(err @ TaskServiceError::TaskAlreadyExists) => {
+                return Err(ImportError::Skipped(err.to_string()))
+            }
+            Err(err) => return Err(ImportError::Failed(err.to_string())),
+        };
+
+        let note = if is_recurring {
+            self.cancel_excluded_dates(user_id, time_zone, &task_id, &excluded)
+                .await
+        } else {
+            None
+        };
+
+        Ok((task_id, note))
+    }
+
+    async fn resolve_category(
+        &self,
+        user_id: &ObjectId,
+        event: &IcsEvent,
+        categories: &mut HashMap<String, ObjectId>,
+    ) -> Result<ObjectId, CategoryServiceError> {
+        let title = event
+            .list("CATEGORIES")
+            .into_iter()
+            .next()
+            .map(|title| truncate(&title, MAX_TITLE_LENGTH))
+            .unwrap_or_else(|| DEFAULT_CATEGORY.to_string());
+        if let Some(category_id) = categories.get(&title) {
+            return Ok(*category_id);
+        }
+
+        let color = event
+            .text("COLOR")
+            .and_then(|color| parse_color(&color))
+            .unwrap_or(Color::Orange);
+        let category_id = self
+            .category_service
+            .get_or_create_category_for_user(user_id, title.clone(), color)
+            .await?;
+        categories.insert(title, category_id);
+        Ok(category_id)
+    }
+
+    /// EXDATEs become cancelled occurrences. Dates the rule never produces are ignored;
+    /// any other failure is returned as a note for the report, as the task already exists.
+    async fn cancel_excluded_dates(
+        &self,
+        user_id: &ObjectId,
+        time_zone: &Tz,
+        task_id: &ObjectId,
+        excluded: &[EventDate],
+    ) -> Option<String> {
+        let mut failed = Vec::new();
+        for date in excluded {
+            let occurrence_start = date.to_utc(time_zone);
+            match self
+                .task_service
+                .update_user_task_occurrence(
+                    user_id,
+                    time_zone,
+                    task_id,
+                    UpdateOccurrenceRequest {
+                        occurrence_start,
+                        status: None,
+                        cancelled: true,
+                    },
+                )
+                .await
+            {
+                Ok(_) | Err(TaskServiceError::OccurrenceNotFound) => {}
+                Err(err) => failed.push(format!("{}: {}", occurrence_start.to_rfc3339(), err)),
+            }
+        }
+
+        (!failed.is_empty())
+            .then(|| format!("Some EXDATEs were not applied ({})", failed.join("; ")))
+    }
+}
+
+/// Reads every EXDATE of the event, or returns the first value that is not a valid date.
+fn excluded_dates(event: &IcsEvent, time_zone: &Tz) -> Result<Vec<EventDate>, String> {
+    let mut excluded = Vec::new();
+    for property in event
+        .properties
+        .iter()
+        .filter(|property| property.name == "EXDATE")
+    {
+        for value in property.value.split(',') {
+            let single = Property {
+                value: value.to_string(),
+                ..property.clone()
+            };
+            excluded.push(parse_date(&single, time_zone).ok_or_else(|| value.to_string())?);
+        }
+    }
+    Ok(excluded)
+}
+
+enum ImportError {
+    Skipped(String),
+    Failed(String),
+}
+
+enum EventDate {
+    DateTime(DateTime<Utc>),
+    Date(NaiveDate),
+}
+
+impl EventDate {
+    /// All-day dates start at midnight in the user's time zone.
+    fn to_utc(&self, time_zone: &Tz) -> DateTime<Utc> {
+        match self {
+            EventDate::DateTime(date) => *date,
+            EventDate::Date(date) => local_to_utc(date.and_hms_opt(0, 0, 0).unwrap(), time_zone),
+        }
+    }
+}
+
+/// Reads a DATE or DATE-TIME value. UTC (`Z`) times are absolute, `TZID` times are read in
+/// that zone, and floating times are read in the user's time zone.
+fn parse_date(property: &Property, time_zone: &Tz) -> Option<EventDate> {
+    let value = property.value.trim();
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(EventDate::Date);
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(EventDate::DateTime(naive.and_utc()));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = property
+        .param("TZID")
+        .and_then(|tzid| tzid.trim_start_matches('/').parse::<Tz>().ok())
+        .unwrap_or(*time_zone);
+    Some(EventDate::DateTime(local_to_utc(naive, &zone)))
+}
+
+/// Parses an RFC 5545 duration such as `PT1H30M`, `-P1D` or `P2W`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.as_bytes().first()? {
+        b'-' => (true, &value[1..]),
+        b'+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            'T' => in_time = true,
+            c if c.is_ascii_digit() => number.push(c),
+            unit => {
+                let amount: i64 = number.parse().ok()?;
+                number.clear();
+                duration += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(amount),
+                    ('D', false) => Duration::days(amount),
+                    ('H', true) => Duration::hours(amount),
+                    ('M', true) => Duration::minutes(amount),
+                    ('S', true) => Duration::seconds(amount),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(if negative { -duration } else { duration })
+}
+
+/// Maps a VALARM to the task notification. Only reminders before the start are supported.
+fn parse_alarm(alarm: &[Property], start_date: DateTime<Utc>) -> Option<(TimeUnit, u16)> {
+    let trigger = alarm.iter().find(|property| property.name == "TRIGGER")?;
+    let before = if trigger.param("VALUE") == Some("DATE-TIME") {
+        let value = trigger.value.trim().strip_suffix('Z')?;
+        let at = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()?
+            .and_utc();
+        start_date - at
+    } else {
+        if trigger.param("RELATED") == Some("END") {
+            return None;
+        }
+        -parse_duration(&trigger.value)?
+    };
+
+    let minutes = before.num_minutes();
+    if minutes < 0 {
+        return None;
+    }
+    if minutes > 0 && minutes % 60 == 0 {
+        Some((TimeUnit::Hour, u16::try_from(minutes / 60).ok()?))
+    } else {
+        Some((TimeUnit::Minute, u16::try_from(minutes).ok()?))
+    }
+}
+
+fn parse_rule(value: &str, time_zone: &Tz) -> Result<RecurrenceRule, String> {
+    let unsupported = || format!("Unsupported recurrence rule: {}", value);
+
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut by_weekday = Vec::new();
+    let mut count = None;
+    let mut until = None;
+
+    for part in value.split(';').filter(|part| !part.is_empty()) {
+        let (key, value) = part.split_once('=').ok_or_else(unsupported)?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    _ => return Err(unsupported()),
+                })
+            }
+            "INTERVAL" => interval = value.parse().map_err(|_| unsupported())?,
+            "COUNT" => count = Some(value.parse().map_err(|_| unsupported())?),
+            "UNTIL" => {
+                let property = Property {
+                    name: key.to_string(),
+                    params: HashMap::new(),
+                    value: value.to_string(),
+                };
+                until = Some(
+                    parse_date(&property, time_zone)
+                        .ok_or_else(unsupported)?
+                        .to_utc(time_zone),
+                );
+            }
+            "BYDAY" => {
+                by_weekday = value
+                    .split(',')
+                    .map(parse_weekday)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(unsupported)?
+            }
+            // Week start only matters for rules we do not support.
+            "WKST" => {}
+            _ => return Err(unsupported()),
+        }
+    }
+
+    let frequency = frequency.ok_or_else(unsupported)?;
+    if !by_weekday.is_empty() && frequency != Frequency::Weekly {
+        return Err(unsupported());
+    }
+
+    Ok(RecurrenceRule {
+        frequency,
+        interval,
+        by_weekday,
+        count,
+        until,
+    })
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_color(color: &str) -> Option<Color> {
+    match color.trim().to_ascii_lowercase().as_str() {
+        "orange" => Some(Color::Orange),
+        "yellow" => Some(Color::Yellow),
+        "green" => Some(Color::Green),
+        "red" => Some(Color::Red),
+        "purple" => Some(Color::Purple),
+        _ => None,
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::repositories::Repositories;
+    use crate::modules::{
+        goal::service::GoalService,
+        workspace::{access::WorkspaceAccess, models::Scope},
+    };
+
+    use crate::modules::calendar::dto::ImportEntry;
+    use super::*;
+
+    fn import_service(repositories: &Repositories) -> CalendarImportService {
+        let access = WorkspaceAccess::new(repositories.workspaces.clone());
+        CalendarImportService::new(
+            TaskService::new(
+                repositories.tasks.clone(),
+                repositories.categories.clone(),
+                repositories.task_comments.clone(),
+                repositories.task_activity.clone(),
+                GoalService::new(
+                    repositories.goals.clone(),
+                    repositories.tasks.clone(),
+                    repositories.categories.clone(),
+                    access.clone(),
+                ),
+                access.clone(),
+            ),
+            CategoryService::new(repositories.categories.clone(), access),
+        )
+    }
+
+    fn event(uid: &str, lines: &[&str]) -> String {
+        format!(
+            "BEGIN:VEVENT\r\nUID:{}\r\nSUMMARY:{}\r\n{}\r\nEND:VEVENT\r\n",
+            uid,
+            uid,
+            lines.join("\r\n")
+        )
+    }
+
+    fn entry<'a>(entries: &'a [ImportEntry], uid: &str) -> &'a ImportEntry {
+        entries
+            .iter()
+            .find(|entry| entry.uid.as_deref() == Some(uid))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn events_are_checked_before_they_are_imported() {
+        let repositories = Repositories::in_memory();
+        let service = import_service(&repositories);
+        let user_id = ObjectId::new();
+        let content = [
+            "BEGIN:VCALENDAR\r\n".to_string(),
+            event(
+                "future",
+                &["DTSTART:20990101T090000Z", "DTEND:20990101T100000Z"],
+            ),
+            event(
+                "backwards",
+                &["DTSTART:20240101T100000Z", "DTEND:20240101T090000Z"],
+            ),
+            event(
+                "weekly",
+                &[
+                    "DTSTART:20240101T090000Z",
+                    "DTEND:20240101T100000Z",
+                    "RRULE:FREQ=WEEKLY;COUNT=4",
+                    "EXDATE:20240108T090000Z",
+                ],
+            ),
+            event(
+                "bad-exdate",
+                &[
+                    "DTSTART:20240101T090000Z",
+                    "DTEND:20240101T100000Z",
+                    "RRULE:FREQ=DAILY;COUNT=4",
+                    "EXDATE:2024-01-02",
+                ],
+            ),
+            "END:VCALENDAR\r\n".to_string(),
+        ]
+        .concat();
+
+        let report = service.import_calendar(&user_id, &Tz::UTC, &content).await;
+
+        assert_eq!((report.created, report.failed), (2, 2));
+        assert!(matches!(
+            entry(&report.entries, "backwards").outcome,
+            ImportOutcome::Failed
+        ));
+        let bad_exdate = entry(&report.entries, "bad-exdate");
+        assert!(matches!(bad_exdate.outcome, ImportOutcome::Failed));
+        assert_eq!(
+            bad_exdate.reason.as_deref(),
+            Some("Invalid EXDATE: 2024-01-02")
+        );
+
+        let tasks = repositories
+            .tasks
+            .get_all_user_tasks(&Scope::User(user_id))
+            .await
+            .unwrap();
+        let task = |title: &str| tasks.iter().find(|task| task.title == title).unwrap();
+        // Future and recurring events are not done yet, not postponed
+        assert_eq!(task("future").status, Status::Pendente);
+        assert_eq!(task("weekly").status, Status::Pendente);
+        let cancelled: Vec<String> = task("weekly")
+            .occurrence_overrides
+            .iter()
+            .filter(|exception| exception.cancelled)
+            .map(|exception| exception.occurrence_start.to_rfc3339())
+            .collect();
+        assert_eq!(cancelled, vec!["2024-01-08T09:00:00+00:00"]);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod dto;
+pub mod handlers;
+pub mod ics;
+pub mod import;
+pub mod parser;
+pub mod service;
+
+pub use handlers::handles;
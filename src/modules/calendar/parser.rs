@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+/// A content line, e.g. `DTSTART;TZID=Europe/Lisbon:20240101T090000`.
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub params: HashMap<String, String>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IcsEvent {
+    pub properties: Vec<Property>,
+    pub alarms: Vec<Vec<Property>>,
+}
+
+impl IcsEvent {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// Unescaped text value of the first property with this name.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name)
+            .map(|property| unescape_text(&property.value))
+    }
+
+    /// Every value of a comma-separated list property such as CATEGORIES.
+    pub fn list(&self, name: &str) -> Vec<String> {
+        self.properties
+            .iter()
+            .filter(|property| property.name == name)
+            .flat_map(|property| split_unescaped(&property.value, ','))
+            .map(|value| unescape_text(&value).trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    }
+}
+
+/// Extracts every VEVENT (with its VALARMs) from an iCalendar document. Other components,
+/// such as VTIMEZONE or VTODO, are ignored; malformed lines are skipped.
+pub fn parse_events(content: &str) -> Vec<IcsEvent> {
+    let mut events = Vec::new();
+    let mut components: Vec<String> = Vec::new();
+    let mut event: Option<IcsEvent> = None;
+    let mut alarm: Option<Vec<Property>> = None;
+
+    for line in unfold_lines(content) {
+        let Some(property) = parse_line(&line) else {
+            continue;
+        };
+
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_ascii_uppercase();
+                match (component.as_str(), components.last().map(String::as_str)) {
+                    ("VEVENT", Some("VCALENDAR")) => event = Some(IcsEvent::default()),
+                    ("VALARM", Some("VEVENT")) => alarm = Some(Vec::new()),
+                    _ => {}
+                }
+                components.push(component);
+            }
+            "END" => {
+                let component = property.value.to_ascii_uppercase();
+                if components.last() != Some(&component) {
+                    continue;
+                }
+                components.pop();
+                match component.as_str() {
+                    "VEVENT" => events.extend(event.take()),
+                    "VALARM" => {
+                        if let (Some(event), Some(alarm)) = (event.as_mut(), alarm.take()) {
+                            event.alarms.push(alarm);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => match components.last().map(String::as_str) {
+                Some("VEVENT") => {
+                    if let Some(event) = event.as_mut() {
+                        event.properties.push(property);
+                    }
+                }
+                Some("VALARM") => {
+                    if let Some(alarm) = alarm.as_mut() {
+                        alarm.push(property);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    events
+}
+
+/// Joins folded lines (RFC 5545 section 3.1): a line starting with a space or tab continues
+/// the previous one.
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ if raw.is_empty() => {}
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter value.
+    let mut in_quotes = false;
+    let separator = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(index),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..separator], &line[separator + 1..]);
+    let mut parts = split_unescaped(head, ';').into_iter();
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Splits on `separator`, ignoring escaped separators and separators inside quotes.
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    let mut in_quotes = false;
+
+    for c in text.chars() {
+        match c {
+            _ if escaped => {
+                current.push(c);
+                escaped = false;
+            }
+            '\\' => {
+                current.push(c);
+                escaped = true;
+            }
+            '"' => {
+                current.push(c);
+                in_quotes = !in_quotes;
+            }
+            c if c == separator && !in_quotes => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
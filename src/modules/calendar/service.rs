@@ -0,0 +1,97 @@
+use std::{collections::HashMap, sync::Arc};
+
+use mongodb::bson::oid::ObjectId;
+use thiserror::Error;
+
+use crate::helpers::token_helper::{generate_token, hash_token};
+use crate::modules::{
+    category::repository::CategoryRepository, task::repository::TaskRepository,
+    user::repository::UserRepository, workspace::models::Scope,
+};
+
+use super::ics::CalendarWriter;
+
+#[derive(Error, Debug)]
+pub enum CalendarServiceError {
+    #[error("Invalid calendar feed token")]
+    InvalidFeedToken,
+
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct CalendarService {
+    user_repository: Arc<dyn UserRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+}
+
+impl CalendarService {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+    ) -> Self {
+        CalendarService {
+            user_repository,
+            task_repository,
+            category_repository,
+        }
+    }
+
+    /// Issues a new feed token, invalidating any previous one. Only its hash is stored.
+    pub async fn rotate_feed_token(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<String, CalendarServiceError> {
+        let token = generate_token();
+        if !self
+            .user_repository
+            .set_feed_token_hash(user_id, Some(&hash_token(&token)))
+            .await?
+        {
+            return Err(CalendarServiceError::UserNotFound);
+        }
+        Ok(token)
+    }
+
+    pub async fn revoke_feed_token(&self, user_id: &ObjectId) -> Result<(), CalendarServiceError> {
+        if !self
+            .user_repository
+            .set_feed_token_hash(user_id, None)
+            .await?
+        {
+            return Err(CalendarServiceError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    /// The feed of a disabled account is refused like a revoked token.
+    pub async fn render_feed(&self, token: &str) -> Result<String, CalendarServiceError> {
+        let user = self
+            .user_repository
+            .find_user_by_feed_token_hash(&hash_token(token))
+            .await?
+            .filter(|user| !user.disabled)
+            .ok_or(CalendarServiceError::InvalidFeedToken)?;
+        let scope = Scope::User(user.id.unwrap());
+
+        let tasks = self.task_repository.get_all_user_tasks(&scope).await?;
+        let categories: HashMap<ObjectId, _> = self
+            .category_repository
+            .get_all_user_categories(&scope)
+            .await?
+            .into_iter()
+            .filter_map(|category| category.id.map(|id| (id, category)))
+            .collect();
+
+        let mut writer = CalendarWriter::new(&format!("PlanIt - {}", user.name), user.time_zone);
+        for task in &tasks {
+            writer.add_task(task, categories.get(&task.category_id));
+        }
+        Ok(writer.finish())
+    }
+}
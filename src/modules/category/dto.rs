@@ -1,3 +1,4 @@
+use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -8,6 +9,7 @@ pub struct CreateCategoryRequest {
     #[validate(length(min = 1, max = 30))]
     pub title: String,
     pub color: Color,
+    pub workspace_id: Option<ObjectId>,
 }
 #[derive(Deserialize, Validate)]
 pub struct UpdateCategoryRequest {
@@ -1,41 +1,55 @@
 use axum::{
     extract::Path,
-    extract::{Json, State},
+    extract::{Json, Query, State},
     middleware,
     response::IntoResponse,
     routing::delete,
     routing::post,
-    routing::put,
     Extension, Router,
 };
 use mongodb::bson::oid::ObjectId;
 use std::sync::Arc;
 use validator::Validate;
 
-use crate::{helpers::api_response, AppState};
+use crate::AppState;
 use crate::{
     helpers::api_response::ApiResponse,
     modules::auth::{self, dto::AuthState},
+    modules::workspace::{
+        dto::WorkspaceQuery,
+        handlers::{access_error_response, workspace_access},
+    },
 };
 
+use super::dto::{CategoryResponse, CreateCategoryRequest};
 use super::service::{CategoryService, CategoryServiceError};
-use super::{dto::UpdateCategoryRequest, repository::CategoryRepository};
-use super::{
-    dto::{CategoryResponse, CreateCategoryRequest},
-    repository,
-};
+use super::dto::UpdateCategoryRequest;
+
+pub fn category_service(state: &AppState) -> CategoryService {
+    CategoryService::new(
+        state.repositories.categories.clone(),
+        workspace_access(state),
+    )
+}
 
 async fn delete_category(
     State(state): State<Arc<AppState>>,
-    Path(category_id): Path<String>,
+    Path(category_id): Path<ObjectId>,
     Extension(user): Extension<AuthState>,
 ) -> impl IntoResponse {
-    let repository = CategoryRepository::new(&state.mongodb);
-    let service = CategoryService::new(repository);
+    let service = category_service(&state);
 
-    let category_id = ObjectId::parse_str(&category_id).expect("Invalid ObjectId");
     match service.delete_user_category(category_id, &user.id).await {
         Ok(_) => ApiResponse::ok("Category deleted successfully", None::<()>).into_response(),
+        Err(CategoryServiceError::CategoryNotFound) => {
+            ApiResponse::not_found(CategoryServiceError::CategoryNotFound.to_string().as_str())
+                .into_response()
+        }
+        Err(CategoryServiceError::CategoryForbidden) => {
+            ApiResponse::forbidden(CategoryServiceError::CategoryForbidden.to_string().as_str())
+                .into_response()
+        }
+        Err(CategoryServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => ApiResponse::server_error(
             Some(format!("Failed to delete category: {}", err).as_str()),
             None::<()>,
@@ -55,11 +69,15 @@ async fn create_category(
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    let repository = CategoryRepository::new(&state.mongodb);
-    let service = CategoryService::new(repository);
+    let service = category_service(&state);
 
     match service
-        .create_category_for_user(&user.id, payload.title.clone(), payload.color)
+        .create_category_for_user(
+            &user.id,
+            payload.workspace_id,
+            payload.title.clone(),
+            payload.color,
+        )
         .await
     {
         Ok(id) => ApiResponse::created("Category created successfully", Some(id.to_string()))
@@ -71,6 +89,7 @@ async fn create_category(
             None::<()>,
         )
         .into_response(),
+        Err(CategoryServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
@@ -79,14 +98,11 @@ async fn create_category(
 
 async fn update_category(
     State(state): State<Arc<AppState>>,
-    Path(category_id): Path<String>,
+    Path(category_id): Path<ObjectId>,
     Extension(user): Extension<AuthState>,
     Json(payload): Json<UpdateCategoryRequest>,
 ) -> impl IntoResponse {
-    let repository = CategoryRepository::new(&state.mongodb);
-    let service = CategoryService::new(repository);
-
-    let category_id = ObjectId::parse_str(&category_id).expect("Invalid ObjectId");
+    let service = category_service(&state);
 
     if let Err(errors) = payload.validate() {
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
@@ -97,11 +113,15 @@ async fn update_category(
         .await
     {
         Ok(_) => ApiResponse::ok("Category updated successfully", None::<()>).into_response(),
-        Err(CategoryServiceError::CategoryNotFound) => ApiResponse::unprocessable_entity(
-            CategoryServiceError::CategoryNotFound.to_string().as_str(),
-            None::<()>,
-        )
-        .into_response(),
+        Err(CategoryServiceError::CategoryNotFound) => {
+            ApiResponse::not_found(CategoryServiceError::CategoryNotFound.to_string().as_str())
+                .into_response()
+        }
+        Err(CategoryServiceError::CategoryForbidden) => {
+            ApiResponse::forbidden(CategoryServiceError::CategoryForbidden.to_string().as_str())
+                .into_response()
+        }
+        Err(CategoryServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
@@ -111,11 +131,14 @@ async fn update_category(
 async fn get_categories(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    Query(query): Query<WorkspaceQuery>,
 ) -> impl IntoResponse {
-    let repository = CategoryRepository::new(&state.mongodb);
-    let service = CategoryService::new(repository);
+    let service = category_service(&state);
 
-    match service.get_all_user_categories(&user.id).await {
+    match service
+        .get_all_user_categories(&user.id, query.workspace_id)
+        .await
+    {
         Ok(categories) => {
             let response_categories: Vec<_> = categories
                 .into_iter()
@@ -132,18 +155,22 @@ async fn get_categories(
             )
             .into_response()
         }
+        Err(CategoryServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route("/v1/categories", post(create_category).get(get_categories))
         .route(
             "/v1/categories/:category_id",
             delete(delete_category).put(update_category),
         )
-        .layer(middleware::from_fn(auth::middlewares::authorize))
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
 }
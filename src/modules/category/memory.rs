@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, Bson};
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+use crate::modules::workspace::models::Scope;
+
+use super::models::{Category, Color};
+use super::repository::CategoryRepository;
+
+pub struct InMemoryCategoryRepository {
+    categories: MemoryCollection<Category>,
+}
+
+impl Default for InMemoryCategoryRepository {
+    /// Titles are unique per user among personal categories and per workspace among shared
+    /// ones, like the `ensure_indexes` indexes.
+    fn default() -> Self {
+        let categories = MemoryCollection::default()
+            .unique("user_title_unique", |category: &Category| {
+                Some(Bson::Array(vec![
+                    category.user_id.into(),
+                    category.workspace_id.into(),
+                    category.title.clone().into(),
+                ]))
+            })
+            .unique("workspace_title_unique", |category: &Category| {
+                let workspace_id = category.workspace_id?;
+                Some(Bson::Array(vec![
+                    workspace_id.into(),
+                    category.title.clone().into(),
+                ]))
+            });
+        InMemoryCategoryRepository { categories }
+    }
+}
+
+#[async_trait]
+impl CategoryRepository for InMemoryCategoryRepository {
+    async fn create_category(&self, new_category: Category) -> Result<ObjectId, Error> {
+        self.categories.insert(&new_category)
+    }
+
+    async fn update_category(
+        &self,
+        scope: &Scope,
+        id: ObjectId,
+        title: String,
+        color: Color,
+    ) -> Result<(), Error> {
+        self.categories.update_one(
+            |category| category.id == Some(id) && category.scope() == *scope,
+            |category| {
+                category.title = title;
+                category.color = color;
+            },
+        )?;
+        Ok(())
+    }
+
+    async fn delete_category(&self, scope: &Scope, category_id: ObjectId) -> Result<(), Error> {
+        self.categories.delete_one(|category| {
+            category.id == Some(category_id) && category.scope() == *scope
+        })?;
+        Ok(())
+    }
+
+    async fn delete_all_user_categories(&self, scope: &Scope) -> Result<u64, Error> {
+        self.categories
+            .delete_many(|category| category.scope() == *scope)
+    }
+
+    async fn count_categories(&self) -> Result<u64, Error> {
+        self.categories.count(|_| true)
+    }
+
+    async fn get_all_user_categories(&self, scope: &Scope) -> Result<Vec<Category>, Error> {
+        self.categories.find(|category| category.scope() == *scope)
+    }
+
+    async fn get_user_categories_by_ids(
+        &self,
+        scope: &Scope,
+        category_ids: &[ObjectId],
+    ) -> Result<Vec<Category>, Error> {
+        self.categories.find(|category| {
+            category.scope() == *scope && category.id.is_some_and(|id| category_ids.contains(&id))
+        })
+    }
+
+    async fn get_category_by_title(
+        &self,
+        scope: &Scope,
+        title: &str,
+    ) -> Result<Option<Category>, Error> {
+        self.categories
+            .find_one(|category| category.scope() == *scope && category.title == title)
+    }
+
+    async fn get_category_by_id(&self, category_id: &ObjectId) -> Result<Option<Category>, Error> {
+        self.categories
+            .find_one(|category| category.id.as_ref() == Some(category_id))
+    }
+}
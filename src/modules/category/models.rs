@@ -1,4 +1,5 @@
 use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+use crate::modules::workspace::models::Scope;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
@@ -38,6 +39,14 @@ pub struct Category {
     )]
     pub id: Option<ObjectId>,
     pub user_id: ObjectId,
+    #[serde(default)]
+    pub workspace_id: Option<ObjectId>,
     pub title: String,
     pub color: Color,
 }
+
+impl Category {
+    pub fn scope(&self) -> Scope {
+        Scope::new(self.user_id, self.workspace_id)
+    }
+}
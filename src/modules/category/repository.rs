@@ -1,21 +1,63 @@
 use crate::category::models::Color;
+use async_trait::async_trait;
 use mongodb::bson::oid::ObjectId;
 use mongodb::error::Error;
 use mongodb::{bson::doc, Collection, Database};
 
 use super::models::Category;
+use crate::modules::workspace::models::Scope;
 
-pub struct CategoryRepository {
+#[async_trait]
+pub trait CategoryRepository: Send + Sync {
+    async fn create_category(&self, new_category: Category) -> Result<ObjectId, Error>;
+
+    async fn update_category(
+        &self,
+        scope: &Scope,
+        id: ObjectId,
+        title: String,
+        color: Color,
+    ) -> Result<(), Error>;
+
+    async fn delete_category(&self, scope: &Scope, category_id: ObjectId) -> Result<(), Error>;
+
+    async fn delete_all_user_categories(&self, scope: &Scope) -> Result<u64, Error>;
+
+    async fn count_categories(&self) -> Result<u64, Error>;
+
+    async fn get_all_user_categories(&self, scope: &Scope) -> Result<Vec<Category>, Error>;
+
+    async fn get_user_categories_by_ids(
+        &self,
+        scope: &Scope,
+        category_ids: &[ObjectId],
+    ) -> Result<Vec<Category>, Error>;
+
+    async fn get_category_by_title(
+        &self,
+        scope: &Scope,
+        title: &str,
+    ) -> Result<Option<Category>, Error>;
+
+    /// Looks a category up by id alone; callers check it is accessible from its `scope`.
+    async fn get_category_by_id(&self, category_id: &ObjectId)
+        -> Result<Option<Category>, Error>;
+}
+
+pub struct MongoCategoryRepository {
     collection: Collection<Category>,
 }
 
-impl CategoryRepository {
+impl MongoCategoryRepository {
     pub fn new(db: &Database) -> Self {
         let collection = db.collection("categories");
-        CategoryRepository { collection }
+        MongoCategoryRepository { collection }
     }
+}
 
-    pub async fn create_category(
+#[async_trait]
+impl CategoryRepository for MongoCategoryRepository {
+    async fn create_category(
         &self,
         new_category: Category,
     ) -> Result<mongodb::bson::oid::ObjectId, Error> {
@@ -24,13 +66,14 @@ impl CategoryRepository {
         Ok(id)
     }
 
-    pub async fn update_category(
+    async fn update_category(
         &self,
+        scope: &Scope,
         id: ObjectId,
         title: String,
         color: Color,
     ) -> Result<(), Error> {
-        let filter = doc! { "_id": id };
+        let filter = scope.filter_by_id(&id);
         let update = doc! { "$set": {
             "title": title,
             "color": color.as_str(),
@@ -40,22 +83,47 @@ impl CategoryRepository {
         Ok(())
     }
 
-    pub async fn delete_category(&self, category_id: ObjectId) -> Result<(), Error> {
-        let filter = doc! {"_id": category_id};
+    async fn delete_category(
+        &self,
+        scope: &Scope,
+        category_id: ObjectId,
+    ) -> Result<(), Error> {
+        let filter = scope.filter_by_id(&category_id);
         self.collection.delete_one(filter).await?;
         Ok(())
     }
 
-    pub async fn get_all_user_categories(
+    async fn delete_all_user_categories(&self, scope: &Scope) -> Result<u64, Error> {
+        let result = self.collection.delete_many(scope.filter()).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn count_categories(&self) -> Result<u64, Error> {
+        self.collection.count_documents(doc! {}).await
+    }
+
+    async fn get_all_user_categories(
+        &self,
+        scope: &Scope,
+    ) -> Result<Vec<Category>, Error> {
+        let mut cursor = self.collection.find(scope.filter()).await?;
+        let mut categories: Vec<Category> = Vec::new();
+
+        while cursor.advance().await? {
+            categories.push(cursor.deserialize_current()?);
+        }
+
+        Ok(categories)
+    }
+
+    async fn get_user_categories_by_ids(
         &self,
-        &user_id: &ObjectId,
+        scope: &Scope,
+        category_ids: &[ObjectId],
     ) -> Result<Vec<Category>, Error> {
-        let mut cursor = self
-            .collection
-            .find(doc! {
-                "user_id": user_id
-            })
-            .await?;
+        let mut filter = scope.filter();
+        filter.insert("_id", doc! { "$in": category_ids });
+        let mut cursor = self.collection.find(filter).await?;
         let mut categories: Vec<Category> = Vec::new();
 
         while cursor.advance().await? {
@@ -65,24 +133,20 @@ impl CategoryRepository {
         Ok(categories)
     }
 
-    pub async fn get_category_by_title(
+    async fn get_category_by_title(
         &self,
-        &user_id: &ObjectId,
+        scope: &Scope,
         title: &str,
     ) -> Result<Option<Category>, Error> {
-        let filter = doc! {"user_id": user_id, "title": title};
+        let mut filter = scope.filter();
+        filter.insert("title", title);
         self.collection.find_one(filter).await
     }
 
-    pub async fn get_category_by_id(
+    async fn get_category_by_id(
         &self,
-        user_id: &ObjectId,
         category_id: &ObjectId,
     ) -> Result<Option<Category>, Error> {
-        let filter = doc! {
-            "_id": category_id,
-            "user_id": user_id
-        };
-        self.collection.find_one(filter).await
+        self.collection.find_one(doc! { "_id": category_id }).await
     }
 }
@@ -1,9 +1,15 @@
+use std::sync::Arc;
+
 use mongodb::bson::oid::ObjectId;
 use mongodb::error::Error;
 
 use thiserror::Error;
 
-use crate::modules::user;
+use crate::helpers::mongo_error_helper::duplicate_key_as;
+use crate::modules::workspace::{
+    access::{WorkspaceAccess, WorkspaceAccessError},
+    models::{Access, Scope},
+};
 
 use super::models::{Category, Color};
 use super::repository::CategoryRepository;
@@ -18,26 +24,38 @@ pub enum CategoryServiceError {
 
     #[error("Category not found")]
     CategoryNotFound,
+
+    #[error("You do not have permission to access this category")]
+    CategoryForbidden,
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceAccessError),
 }
 
 pub struct CategoryService {
-    repository: CategoryRepository,
+    repository: Arc<dyn CategoryRepository>,
+    access: WorkspaceAccess,
 }
 
 impl CategoryService {
-    pub fn new(repository: CategoryRepository) -> Self {
-        CategoryService { repository }
+    pub fn new(repository: Arc<dyn CategoryRepository>, access: WorkspaceAccess) -> Self {
+        CategoryService { repository, access }
     }
 
     pub async fn create_category_for_user(
         &self,
         &user_id: &ObjectId,
+        workspace_id: Option<ObjectId>,
         title: String,
         color: Color,
     ) -> Result<ObjectId, CategoryServiceError> {
+        let scope = self
+            .access
+            .resolve(&user_id, workspace_id, Access::Write)
+            .await?;
         if let Some(_existing_category) = self
             .repository
-            .get_category_by_title(&user_id, &title)
+            .get_category_by_title(&scope, &title)
             .await?
         {
             return Err(CategoryServiceError::CategoryAlreadyExists);
@@ -45,14 +63,38 @@ impl CategoryService {
         let new_category = Category {
             id: None,
             user_id,
+            workspace_id,
             title,
             color,
         };
 
-        let result = self.repository.create_category(new_category).await?;
+        let result = self
+            .repository
+            .create_category(new_category)
+            .await
+            .map_err(duplicate_key_as(CategoryServiceError::CategoryAlreadyExists))?;
         Ok(result)
     }
 
+    /// Returns the id of the user's category with this title, creating it when missing.
+    pub async fn get_or_create_category_for_user(
+        &self,
+        &user_id: &ObjectId,
+        title: String,
+        color: Color,
+    ) -> Result<ObjectId, CategoryServiceError> {
+        if let Some(category) = self
+            .repository
+            .get_category_by_title(&Scope::User(user_id), &title)
+            .await?
+        {
+            return Ok(category.id.unwrap());
+        }
+
+        self.create_category_for_user(&user_id, None, title, color)
+            .await
+    }
+
     pub async fn update_category(
         &self,
         user_id: &ObjectId,
@@ -60,19 +102,27 @@ impl CategoryService {
         title: String,
         color: Color,
     ) -> Result<(), CategoryServiceError> {
-        if let None = self.repository.get_category_by_id(user_id, &id).await? {
-            return Err(CategoryServiceError::CategoryNotFound);
-        }
+        let category = self
+            .get_accessible_category(user_id, &id, Access::Write)
+            .await?;
 
-        self.repository.update_category(id, title, color).await?;
+        self.repository
+            .update_category(&category.scope(), id, title, color)
+            .await
+            .map_err(duplicate_key_as(CategoryServiceError::CategoryAlreadyExists))?;
         Ok(())
     }
 
     pub async fn get_all_user_categories(
         &self,
-        &user_id: &ObjectId,
-    ) -> Result<Vec<Category>, Error> {
-        self.repository.get_all_user_categories(&user_id).await
+        user_id: &ObjectId,
+        workspace_id: Option<ObjectId>,
+    ) -> Result<Vec<Category>, CategoryServiceError> {
+        let scope = self
+            .access
+            .resolve(user_id, workspace_id, Access::Read)
+            .await?;
+        Ok(self.repository.get_all_user_categories(&scope).await?)
     }
 
     pub async fn delete_user_category(
@@ -80,18 +130,127 @@ impl CategoryService {
         category_id: ObjectId,
         user_id: &ObjectId,
     ) -> Result<(), CategoryServiceError> {
-        let result = self
+        let category = self
+            .get_accessible_category(user_id, &category_id, Access::Write)
+            .await?;
+
+        self.repository
+            .delete_category(&category.scope(), category_id)
+            .await
+            .map_err(CategoryServiceError::DatabaseError)?;
+        Ok(())
+    }
+
+    async fn get_accessible_category(
+        &self,
+        user_id: &ObjectId,
+        category_id: &ObjectId,
+        access: Access,
+    ) -> Result<Category, CategoryServiceError> {
+        let category = self
             .repository
-            .get_category_by_id(user_id,&category_id)
-            .await;
-        if let Ok(Some(_category)) = result {
-            self.repository
-                .delete_category(category_id)
-                .await
-                .map_err(CategoryServiceError::DatabaseError)?;
-            Ok(())
-        } else {
-            Err(CategoryServiceError::CategoryNotFound)
+            .get_category_by_id(category_id)
+            .await?
+            .ok_or(CategoryServiceError::CategoryNotFound)?;
+
+        if !self
+            .access
+            .allows(user_id, &category.scope(), access)
+            .await?
+        {
+            return Err(CategoryServiceError::CategoryForbidden);
         }
+        Ok(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::repositories::Repositories;
+
+    use super::*;
+
+    fn category_service(repositories: &Repositories) -> CategoryService {
+        CategoryService::new(
+            repositories.categories.clone(),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        )
+    }
+
+    #[tokio::test]
+    async fn categories_of_another_user_are_forbidden() {
+        let repositories = Repositories::in_memory();
+        let service = category_service(&repositories);
+        let owner_id = ObjectId::new();
+        let other_id = ObjectId::new();
+        let category_id = service
+            .create_category_for_user(&owner_id, None, "Work".to_string(), Color::Green)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .update_category(&other_id, category_id, "Mine".to_string(), Color::Red)
+                .await,
+            Err(CategoryServiceError::CategoryForbidden)
+        ));
+        assert!(matches!(
+            service.delete_user_category(category_id, &other_id).await,
+            Err(CategoryServiceError::CategoryForbidden)
+        ));
+        assert!(service
+            .get_all_user_categories(&other_id, None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let categories = service
+            .get_all_user_categories(&owner_id, None)
+            .await
+            .unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].title, "Work");
+    }
+
+    #[tokio::test]
+    async fn renaming_to_a_taken_title_is_rejected() {
+        let repositories = Repositories::in_memory();
+        let service = category_service(&repositories);
+        let user_id = ObjectId::new();
+        service
+            .create_category_for_user(&user_id, None, "Work".to_string(), Color::Green)
+            .await
+            .unwrap();
+        let category_id = service
+            .create_category_for_user(&user_id, None, "Home".to_string(), Color::Red)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .update_category(&user_id, category_id, "Work".to_string(), Color::Red)
+                .await,
+            Err(CategoryServiceError::CategoryAlreadyExists)
+        ));
+        // Other users keep their own titles.
+        service
+            .create_category_for_user(&ObjectId::new(), None, "Work".to_string(), Color::Green)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_workspaces_are_rejected() {
+        let repositories = Repositories::in_memory();
+        let service = category_service(&repositories);
+
+        assert!(matches!(
+            service
+                .get_all_user_categories(&ObjectId::new(), Some(ObjectId::new()))
+                .await,
+            Err(CategoryServiceError::Workspace(
+                WorkspaceAccessError::WorkspaceNotFound
+            ))
+        ));
     }
 }
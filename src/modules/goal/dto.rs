@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -9,21 +9,29 @@ use super::models::{Priority, Status};
 
 #[derive(Debug, Validate, Serialize, Deserialize)]
 pub struct CreateGoalRequest {
+    #[validate(length(min = 1, max = 30))]
     pub title: String,
+    #[validate(length(min = 1, max = 100))]
     pub description: String,
     pub category_id: Option<ObjectId>,
     pub end_date: Option<DateTime<Utc>>,
     pub priority: Priority,
+    #[serde(default)]
+    pub auto_status: bool,
+    pub workspace_id: Option<ObjectId>,
 }
 
 #[derive(Debug, Validate, Serialize, Deserialize)]
 pub struct UpdateGoalRequest {
+    #[validate(length(min = 1, max = 30))]
     pub title: Option<String>,
+    #[validate(length(min = 1, max = 100))]
     pub description: Option<String>,
     pub category_id: Option<ObjectId>,
     pub end_date: Option<DateTime<Utc>>,
     pub priority: Option<Priority>,
     pub status: Option<Status>,
+    pub auto_status: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -32,7 +40,9 @@ pub struct GoalResponse {
     pub title: String,
     pub description: String,
     pub category: Option<CategoryResponse>,
-    pub end_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<FixedOffset>>,
     pub priority: Priority,
     pub status: Status,
+    pub auto_status: bool,
+    pub progress: u8,
 }
@@ -1,22 +1,31 @@
 use std::sync::Arc;
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     middleware,
     response::IntoResponse,
-    routing::{post, put},
+    routing::{get, post, put},
     Extension, Router,
 };
 use mongodb::bson::oid::ObjectId;
 use validator::Validate;
 
 use crate::{
-    helpers::api_response::ApiResponse,
-    modules::{auth::{self, dto::AuthState}, category::{dto::CategoryResponse, repository::CategoryRepository}, goal::{dto::{CreateGoalRequest, UpdateGoalRequest}, repository::GoalRepository, service::{GoalService, GoalServiceError}}},
+    helpers::{api_response::ApiResponse, time_zone_helper::{to_zone, TimeZoneQuery}},
+    modules::{auth::{self, dto::AuthState}, category::dto::CategoryResponse, goal::{dto::{CreateGoalRequest, UpdateGoalRequest}, service::{GoalService, GoalServiceError}}, task::{dto::TaskResponse, handlers::task_service}, user::extractors::UserTimeZone, workspace::{dto::WorkspaceQuery, handlers::{access_error_response, workspace_access}, models::Access}},
     AppState,
 };
 
 use super::dto::GoalResponse;
 
+pub fn goal_service(state: &AppState) -> GoalService {
+    GoalService::new(
+        state.repositories.goals.clone(),
+        state.repositories.tasks.clone(),
+        state.repositories.categories.clone(),
+        workspace_access(state),
+    )
+}
+
 async fn create_goal(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
@@ -26,8 +35,7 @@ async fn create_goal(
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    let repository = GoalRepository::new(&state.mongodb);
-    let service = GoalService::new(repository);
+    let service = goal_service(&state);
 
     match service.create_goal_for_user(user.id, payload).await {
         Ok(id) => ApiResponse::created("Goal created successfully", Some(id.to_string())).into_response(),
@@ -35,6 +43,11 @@ async fn create_goal(
             GoalServiceError::GoalAlreadyExists.to_string().as_str(),
             None::<()>,
         ).into_response(),
+        Err(GoalServiceError::CategoryNotFound) => ApiResponse::unprocessable_entity(
+            GoalServiceError::CategoryNotFound.to_string().as_str(),
+            None::<()>,
+        ).into_response(),
+        Err(GoalServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
     }
 }
@@ -49,12 +62,19 @@ async fn update_goal(
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    let repository = GoalRepository::new(&state.mongodb);
-    let service = GoalService::new(repository);
+    let service = goal_service(&state);
 
     match service.update_user_goal(user.id, goal_id, payload).await {
         Ok(goal) => ApiResponse::ok("Goal updated successfully", Some(goal)).into_response(),
         Err(GoalServiceError::GoalNotFound) => ApiResponse::not_found("Goal not found").into_response(),
+        Err(GoalServiceError::GoalForbidden) => ApiResponse::forbidden(
+            GoalServiceError::GoalForbidden.to_string().as_str(),
+        ).into_response(),
+        Err(GoalServiceError::CategoryNotFound) => ApiResponse::unprocessable_entity(
+            GoalServiceError::CategoryNotFound.to_string().as_str(),
+            None::<()>,
+        ).into_response(),
+        Err(GoalServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
     }
 }
@@ -64,12 +84,15 @@ async fn delete_goal(
     Extension(user): Extension<AuthState>,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    let repository = GoalRepository::new(&state.mongodb);
-    let service = GoalService::new(repository);
+    let service = goal_service(&state);
 
     match service.delete_user_goal(user.id, goal_id).await {
         Ok(_) => ApiResponse::ok("Goal deleted successfully", None::<()>).into_response(),
         Err(GoalServiceError::GoalNotFound) => ApiResponse::not_found("Goal not found").into_response(),
+        Err(GoalServiceError::GoalForbidden) => ApiResponse::forbidden(
+            GoalServiceError::GoalForbidden.to_string().as_str(),
+        ).into_response(),
+        Err(GoalServiceError::Workspace(err)) => access_error_response(err),
         Err(err) => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
     }
 }
@@ -77,19 +100,30 @@ async fn delete_goal(
 async fn list_goals(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    Query(query): Query<TimeZoneQuery>,
+    Query(workspace): Query<WorkspaceQuery>,
 ) -> impl IntoResponse {
-    let repository = GoalRepository::new(&state.mongodb);
-    let category_repository = CategoryRepository::new(&state.mongodb);
-    let service = GoalService::new(repository);
+    let scope = match workspace_access(&state).resolve(&user.id, workspace.workspace_id, Access::Read).await {
+        Ok(scope) => scope,
+        Err(err) => return access_error_response(err),
+    };
+    let time_zone = query.local.then_some(time_zone);
+
+    let category_repository = state.repositories.categories.clone();
+    let service = goal_service(&state);
 
-    match service.get_all_user_goals(&user.id).await {
+    match service.get_all_user_goals(&scope).await {
         Ok(goals) => {
-            let mut response_goals = Vec::new();
-            let category_result = category_repository.get_all_user_categories(&user.id).await;
-            let categories = match category_result {
-                Ok(cats) => cats,
-                Err(_) => Vec::new(),
+            let task_counts = match task_service(&state).count_tasks_by_goal(&scope).await {
+                Ok(counts) => counts,
+                Err(err) => return ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
             };
+            let mut response_goals = Vec::new();
+            let categories = category_repository
+                .get_all_user_categories(&scope)
+                .await
+                .unwrap_or_default();
 
             for goal in goals {
                 let category_response = categories
@@ -105,10 +139,16 @@ async fn list_goals(
                     _id: goal.id.unwrap().to_string(),
                     title: goal.title,
                     description: goal.description,
-                    end_date: goal.end_date,
+                    end_date: goal.end_date.map(|date| to_zone(date, time_zone.as_ref())),
                     status: goal.status,
                     category: category_response,
                     priority: goal.priority,
+                    auto_status: goal.auto_status,
+                    progress: task_counts
+                        .iter()
+                        .find(|counts| Some(counts.goal_id) == goal.id)
+                        .map(|counts| counts.progress())
+                        .unwrap_or(0),
                 });
             }
 
@@ -118,9 +158,61 @@ async fn list_goals(
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
+async fn list_goal_tasks(
+    Path(goal_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    Query(query): Query<TimeZoneQuery>,
+) -> impl IntoResponse {
+    let service = goal_service(&state);
+    let scope = match service.get_user_goal(user.id, goal_id).await {
+        Ok(goal) => goal.scope(),
+        Err(GoalServiceError::GoalNotFound) => return ApiResponse::not_found("Goal not found").into_response(),
+        Err(GoalServiceError::GoalForbidden) => return ApiResponse::forbidden(
+            GoalServiceError::GoalForbidden.to_string().as_str(),
+        ).into_response(),
+        Err(GoalServiceError::Workspace(err)) => return access_error_response(err),
+        Err(err) => return ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
+    };
+
+    let time_zone = query.local.then_some(time_zone);
+
+    match task_service(&state).get_user_tasks_by_goal(&scope, &goal_id).await {
+        Ok(tasks) => {
+            let categories = state.repositories.categories
+                .get_all_user_categories(&scope)
+                .await
+                .unwrap_or_default();
+
+            let response_tasks: Vec<TaskResponse> = tasks
+                .into_iter()
+                .map(|task| {
+                    let category_response = categories
+                        .iter()
+                        .find(|cat| cat.id == Some(task.category_id))
+                        .map(|category| CategoryResponse {
+                            _id: category.id.unwrap().to_string(),
+                            title: category.title.clone(),
+                            color: category.color.clone(),
+                        });
+                    TaskResponse::new(task, category_response, time_zone.as_ref())
+                })
+                .collect();
+
+            ApiResponse::ok("Goal tasks retrieved successfully", Some(response_tasks)).into_response()
+        }
+        Err(err) => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route("/v1/goals", post(create_goal).get(list_goals))
         .route("/v1/goals/:goal_id", put(update_goal).delete(delete_goal))
-        .layer(middleware::from_fn(auth::middlewares::authorize))
+        .route("/v1/goals/:goal_id/tasks", get(list_goal_tasks))
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+use crate::modules::workspace::models::Scope;
+
+use super::models::{Goal, Priority, Status};
+use super::repository::GoalRepository;
+
+#[derive(Default)]
+pub struct InMemoryGoalRepository {
+    goals: MemoryCollection<Goal>,
+}
+
+#[async_trait]
+impl GoalRepository for InMemoryGoalRepository {
+    async fn create_goal(&self, new_goal: Goal) -> Result<ObjectId, Error> {
+        self.goals.insert(&new_goal)
+    }
+
+    async fn update_goal(
+        &self,
+        scope: &Scope,
+        id: ObjectId,
+        title: Option<String>,
+        description: Option<String>,
+        end_date: Option<DateTime<Utc>>,
+        priority: Option<Priority>,
+        status: Option<Status>,
+        category_id: Option<ObjectId>,
+        auto_status: Option<bool>,
+    ) -> Result<bool, Error> {
+        if title.is_none()
+            && description.is_none()
+            && end_date.is_none()
+            && priority.is_none()
+            && status.is_none()
+            && category_id.is_none()
+            && auto_status.is_none()
+        {
+            return Ok(false);
+        }
+
+        let result = self.goals.update_one(
+            |goal| goal.id == Some(id) && goal.scope() == *scope,
+            |goal| {
+                if let Some(title) = title {
+                    goal.title = title;
+                }
+                if let Some(description) = description {
+                    goal.description = description;
+                }
+                if let Some(end_date) = end_date {
+                    goal.end_date = Some(end_date);
+                }
+                if let Some(priority) = priority {
+                    goal.priority = priority;
+                }
+                if let Some(status) = status {
+                    goal.status = status;
+                }
+                if let Some(category_id) = category_id {
+                    goal.category_id = Some(category_id);
+                }
+                if let Some(auto_status) = auto_status {
+                    goal.auto_status = auto_status;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn update_status(
+        &self,
+        scope: &Scope,
+        goal_id: ObjectId,
+        status: Status,
+    ) -> Result<bool, Error> {
+        let result = self.goals.update_one(
+            |goal| goal.id == Some(goal_id) && goal.scope() == *scope,
+            |goal| goal.status = status,
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn delete_goal(&self, scope: &Scope, goal_id: ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .goals
+            .delete_one(|goal| goal.id == Some(goal_id) && goal.scope() == *scope)?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn delete_all_user_goals(&self, scope: &Scope) -> Result<u64, Error> {
+        self.goals.delete_many(|goal| goal.scope() == *scope)
+    }
+
+    async fn get_all_user_goals(&self, scope: &Scope) -> Result<Vec<Goal>, Error> {
+        self.goals.find(|goal| goal.scope() == *scope)
+    }
+
+    async fn get_goal_by_id(&self, goal_id: ObjectId) -> Result<Option<Goal>, Error> {
+        self.goals.find_one(|goal| goal.id == Some(goal_id))
+    }
+
+    async fn get_goal_by_title(&self, scope: &Scope, title: &str) -> Result<Option<Goal>, Error> {
+        self.goals
+            .find_one(|goal| goal.scope() == *scope && goal.title == title)
+    }
+
+    async fn count_all_goals_by_status(&self) -> Result<Vec<(String, u64)>, Error> {
+        let mut result: Vec<(String, u64)> = Vec::new();
+        for goal in self.goals.find(|_| true)? {
+            match result
+                .iter_mut()
+                .find(|(status, _)| status == goal.status.as_str())
+            {
+                Some((_, count)) => *count += 1,
+                None => result.push((goal.status.as_str().to_string(), 1)),
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -1,5 +1,7 @@
 pub mod dto;
 pub mod handlers;
+#[cfg(test)]
+pub mod memory;
 pub mod models;
 pub mod repository;
 pub mod service;
@@ -1,4 +1,8 @@
+use crate::helpers::date_helper::{
+    deserialize_option_bson_datetime, serialize_option_bson_datetime,
+};
 use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+use crate::modules::workspace::models::Scope;
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
@@ -23,7 +27,10 @@ impl Priority {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const PARTIALLY_REACHED_THRESHOLD: u8 = 50;
+pub const REACHED_THRESHOLD: u8 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     #[serde(rename = "NOT_REACHED")]
     NotReached,
@@ -41,6 +48,15 @@ impl Status {
             Status::Reached => "REACHED",
         }
     }
+
+    /// Status derived from linked task progress, used by goals with `auto_status`.
+    pub fn from_progress(progress: u8) -> Self {
+        match progress {
+            REACHED_THRESHOLD.. => Status::Reached,
+            PARTIALLY_REACHED_THRESHOLD.. => Status::PartiallyReached,
+            _ => Status::NotReached,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,8 +71,23 @@ pub struct Goal {
     pub title: String,
     pub description: String,
     pub category_id: Option<ObjectId>,
+    #[serde(
+        default,
+        serialize_with = "serialize_option_bson_datetime",
+        deserialize_with = "deserialize_option_bson_datetime"
+    )]
     pub end_date: Option<DateTime<Utc>>,
     pub priority: Priority,
     pub status: Status,
     pub user_id: ObjectId,
+    #[serde(default)]
+    pub workspace_id: Option<ObjectId>,
+    #[serde(default)]
+    pub auto_status: bool,
+}
+
+impl Goal {
+    pub fn scope(&self) -> Scope {
+        Scope::new(self.user_id, self.workspace_id)
+    }
 }
\ No newline at end of file
@@ -1,28 +1,76 @@
+use async_trait::async_trait;
 use mongodb::bson::{doc, oid::ObjectId};
 use mongodb::error::Error;
 use mongodb::Collection;
-use chrono::{DateTime, SecondsFormat, Utc};
+use chrono::{DateTime, Utc};
 
 use super::models::{Goal, Priority, Status};
+use crate::helpers::date_helper::to_bson_datetime;
+use crate::modules::workspace::models::Scope;
 
-pub struct GoalRepository {
+#[async_trait]
+pub trait GoalRepository: Send + Sync {
+    async fn create_goal(&self, new_goal: Goal) -> Result<ObjectId, Error>;
+
+    /// Sets the given fields; returns `false` when there is nothing to change.
+    #[allow(clippy::too_many_arguments)]
+    async fn update_goal(
+        &self,
+        scope: &Scope,
+        id: ObjectId,
+        title: Option<String>,
+        description: Option<String>,
+        end_date: Option<DateTime<Utc>>,
+        priority: Option<Priority>,
+        status: Option<Status>,
+        category_id: Option<ObjectId>,
+        auto_status: Option<bool>,
+    ) -> Result<bool, Error>;
+
+    async fn update_status(
+        &self,
+        scope: &Scope,
+        goal_id: ObjectId,
+        status: Status,
+    ) -> Result<bool, Error>;
+
+    async fn delete_goal(&self, scope: &Scope, goal_id: ObjectId) -> Result<bool, Error>;
+
+    async fn delete_all_user_goals(&self, scope: &Scope) -> Result<u64, Error>;
+
+    async fn get_all_user_goals(&self, scope: &Scope) -> Result<Vec<Goal>, Error>;
+
+    /// Looks a goal up by id alone; callers check it is accessible from its `scope`.
+    async fn get_goal_by_id(&self, goal_id: ObjectId) -> Result<Option<Goal>, Error>;
+
+    async fn get_goal_by_title(&self, scope: &Scope, title: &str) -> Result<Option<Goal>, Error>;
+
+    async fn count_all_goals_by_status(&self) -> Result<Vec<(String, u64)>, Error>;
+}
+
+pub struct MongoGoalRepository {
     collection: Collection<Goal>,
 }
 
-impl GoalRepository {
+impl MongoGoalRepository {
     pub fn new(db: &mongodb::Database) -> Self {
         let collection = db.collection("goals");
-        GoalRepository { collection }
+        MongoGoalRepository { collection }
     }
+}
 
-    pub async fn create_goal(&self, new_goal: Goal) -> Result<ObjectId, Error> {
+#[async_trait]
+impl GoalRepository for MongoGoalRepository {
+    async fn create_goal(&self, new_goal: Goal) -> Result<ObjectId, Error> {
         let result = self.collection.insert_one(new_goal).await?;
         let id = result.inserted_id.as_object_id().unwrap();
         Ok(id)
     }
 
-    pub async fn update_goal(
+    #[allow(clippy::too_many_arguments)]
+    async fn update_goal(
         &self,
+        scope: &Scope,
         id: ObjectId,
         title: Option<String>,
         description: Option<String>,
@@ -30,8 +78,9 @@ impl GoalRepository {
         priority: Option<Priority>,
         status: Option<Status>,
         category_id: Option<ObjectId>,
+        auto_status: Option<bool>,
     ) -> Result<bool, Error> {
-        let filter = doc! { "_id": id };
+        let filter = scope.filter_by_id(&id);
         let mut update_doc = doc! {};
 
         if let Some(title) = title {
@@ -41,7 +90,7 @@ impl GoalRepository {
             update_doc.insert("description", description);
         }
         if let Some(end_date) = end_date {
-            update_doc.insert("end_date", end_date.to_rfc3339_opts(SecondsFormat::Secs, true));
+            update_doc.insert("end_date", to_bson_datetime(end_date));
         }
         if let Some(priority) = priority {
             update_doc.insert("priority", priority.as_str());
@@ -52,6 +101,13 @@ impl GoalRepository {
         if let Some(category_id) = category_id {
             update_doc.insert("category_id", category_id);
         }
+        if let Some(auto_status) = auto_status {
+            update_doc.insert("auto_status", auto_status);
+        }
+
+        if update_doc.is_empty() {
+            return Ok(false);
+        }
 
         let update = doc! { "$set": update_doc };
 
@@ -60,16 +116,35 @@ impl GoalRepository {
         Ok(result.modified_count > 0)
     }
 
-    pub async fn delete_goal(&self, goal_id: ObjectId) -> Result<bool, Error> {
-        let query = doc! { "_id": goal_id };
+    async fn update_status(
+        &self,
+        scope: &Scope,
+        goal_id: ObjectId,
+        status: Status,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(&goal_id);
+        let update = doc! { "$set": { "status": status.as_str() } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn delete_goal(&self, scope: &Scope, goal_id: ObjectId) -> Result<bool, Error> {
+        let query = scope.filter_by_id(&goal_id);
 
         let result = self.collection.delete_one(query).await?;
 
         Ok(result.deleted_count > 0)
     }
 
-    pub async fn get_all_user_goals(&self, user_id: &ObjectId) -> Result<Vec<Goal>, Error> {
-        let mut cursor = self.collection.find(doc! { "user_id": user_id }).await?;
+    async fn delete_all_user_goals(&self, scope: &Scope) -> Result<u64, Error> {
+        let result = self.collection.delete_many(scope.filter()).await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn get_all_user_goals(&self, scope: &Scope) -> Result<Vec<Goal>, Error> {
+        let mut cursor = self.collection.find(scope.filter()).await?;
         let mut goals: Vec<Goal> = Vec::new();
 
         while cursor.advance().await? {
@@ -79,23 +154,34 @@ impl GoalRepository {
         Ok(goals)
     }
 
-    pub async fn get_user_goal_by_id(&self, user_id: ObjectId, goal_id: ObjectId) -> Result<Option<Goal>, Error> {
-        let filter = doc! { "_id": goal_id, "user_id": user_id };
-        self.collection.find_one(filter).await
+    async fn get_goal_by_id(&self, goal_id: ObjectId) -> Result<Option<Goal>, Error> {
+        self.collection.find_one(doc! { "_id": goal_id }).await
     }
 
-    pub async fn get_goal_by_title(&self, user_id: &ObjectId, title: &str) -> Result<Option<Goal>, Error> {
-        let filter = doc! { "user_id": user_id, "title": title };
+    async fn get_goal_by_title(&self, scope: &Scope, title: &str) -> Result<Option<Goal>, Error> {
+        let mut filter = scope.filter();
+        filter.insert("title", title);
         self.collection.find_one(filter).await
     }
 
-    pub async fn get_all_goals(&self) -> Result<Vec<Goal>, Error> {
-        let mut cursor = self.collection.find(doc! {}).await?;
-        let mut goals: Vec<Goal> = Vec::new();
+    async fn count_all_goals_by_status(&self) -> Result<Vec<(String, u64)>, Error> {
+        let pipeline = vec![doc! {
+            "$group": {
+                "_id": "$status",
+                "count": { "$sum": 1 }
+            }
+        }];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut result: Vec<(String, u64)> = Vec::new();
+
         while cursor.advance().await? {
-            goals.push(cursor.deserialize_current()?);
+            let doc = cursor.deserialize_current()?;
+            if let (Ok(status), Ok(count)) = (doc.get_str("_id"), doc.get_i32("count")) {
+                result.push((status.to_string(), count as u64));
+            }
         }
 
-        Ok(goals)
+        Ok(result)
     }
 }
\ No newline at end of file
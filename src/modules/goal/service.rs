@@ -1,9 +1,18 @@
+use std::sync::Arc;
+
+use log::error;
 use mongodb::bson::oid::ObjectId;
 use mongodb::error::Error;
 use thiserror::Error;
-use chrono::Utc;
 
-use super::dto::{CreateGoalRequest, UpdateGoalRequest, GoalResponse};
+use crate::modules::category::repository::CategoryRepository;
+use crate::modules::task::repository::TaskRepository;
+use crate::modules::workspace::{
+    access::{WorkspaceAccess, WorkspaceAccessError},
+    models::{Access, Scope},
+};
+
+use super::dto::{CreateGoalRequest, UpdateGoalRequest};
 use super::models::{Goal, Status};
 use super::repository::GoalRepository;
 
@@ -17,15 +26,37 @@ pub enum GoalServiceError {
 
     #[error("Goal not found")]
     GoalNotFound,
+
+    #[error("You do not have permission to access this goal")]
+    GoalForbidden,
+
+    #[error("Category not found")]
+    CategoryNotFound,
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceAccessError),
 }
 
 pub struct GoalService {
-    repository: GoalRepository,
+    repository: Arc<dyn GoalRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+    access: WorkspaceAccess,
 }
 
 impl GoalService {
-    pub fn new(repository: GoalRepository) -> Self {
-        GoalService { repository }
+    pub fn new(
+        repository: Arc<dyn GoalRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+        access: WorkspaceAccess,
+    ) -> Self {
+        GoalService {
+            repository,
+            task_repository,
+            category_repository,
+            access,
+        }
     }
 
     pub async fn create_goal_for_user(
@@ -33,23 +64,32 @@ impl GoalService {
         user_id: ObjectId,
         request: CreateGoalRequest,
     ) -> Result<ObjectId, GoalServiceError> {
+        let scope = self
+            .access
+            .resolve(&user_id, request.workspace_id, Access::Write)
+            .await?;
         if let Some(_existing_goal) = self
             .repository
-            .get_goal_by_title(&user_id, &request.title)
+            .get_goal_by_title(&scope, &request.title)
             .await?
         {
             return Err(GoalServiceError::GoalAlreadyExists);
         }
+        if let Some(category_id) = &request.category_id {
+            self.ensure_category(&scope, category_id).await?;
+        }
 
         let goal = Goal {
             id: None,
             user_id,
+            workspace_id: request.workspace_id,
             title: request.title,
             description: request.description,
             end_date: request.end_date,
             category_id: request.category_id,
             priority: request.priority,
             status: Status::NotReached,
+            auto_status: request.auto_status,
         };
 
         Ok(self.repository.create_goal(goal).await?)
@@ -61,11 +101,14 @@ impl GoalService {
         id: ObjectId,
         request: UpdateGoalRequest,
     ) -> Result<bool, GoalServiceError> {
-        if self.repository.get_user_goal_by_id(user_id, id).await?.is_none() {
-            return Err(GoalServiceError::GoalNotFound);
+        let goal = self.get_accessible_goal(user_id, id, Access::Write).await?;
+        if let Some(category_id) = &request.category_id {
+            self.ensure_category(&goal.scope(), category_id).await?;
         }
+        let enables_auto_status = request.auto_status == Some(true);
 
         let result = self.repository.update_goal(
+            &goal.scope(),
             id,
             request.title,
             request.description,
@@ -73,31 +116,211 @@ impl GoalService {
             request.priority,
             request.status,
             request.category_id,
+            request.auto_status,
         ).await?;
+        if enables_auto_status {
+            self.sync_progress(&goal.scope()).await;
+        }
         Ok(result)
     }
 
+    /// Deletes the goal and unlinks its tasks, which stay in the same scope.
     pub async fn delete_user_goal(
         &self,
         user_id: ObjectId,
         goal_id: ObjectId,
     ) -> Result<bool, GoalServiceError> {
-        let result = self
-            .repository
-            .get_user_goal_by_id(user_id, goal_id)
-            .await;
-        if let Ok(Some(_goal)) = result {
-            Ok(self.repository.delete_goal(goal_id).await?)
-        } else {
-            Err(GoalServiceError::GoalNotFound)
+        let goal = self
+            .get_accessible_goal(user_id, goal_id, Access::Write)
+            .await?;
+        let scope = goal.scope();
+
+        let result = self.repository.delete_goal(&scope, goal_id).await?;
+        self.task_repository.unlink_goal(&scope, &goal_id).await?;
+        Ok(result)
+    }
+
+    pub async fn get_all_user_goals(&self, scope: &Scope) -> Result<Vec<Goal>, Error> {
+        let goals = self.repository.get_all_user_goals(scope).await?;
+        Ok(goals)
+    }
+
+    pub async fn get_user_goal(
+        &self,
+        user_id: ObjectId,
+        goal_id: ObjectId,
+    ) -> Result<Goal, GoalServiceError> {
+        self.get_accessible_goal(user_id, goal_id, Access::Read)
+            .await
+    }
+
+    /// Whether the goal exists in the scope, for records linking to it.
+    pub async fn is_in_scope(&self, scope: &Scope, goal_id: &ObjectId) -> Result<bool, Error> {
+        let goal = self.repository.get_goal_by_id(*goal_id).await?;
+        Ok(goal.is_some_and(|goal| goal.scope() == *scope))
+    }
+
+    /// Moves every `auto_status` goal of the scope to the status matching its linked task
+    /// progress, after those tasks change. Goals without linked tasks keep their status.
+    ///
+    /// The change that triggers it is already saved, so failures are logged, not returned.
+    pub async fn sync_progress(&self, scope: &Scope) {
+        if let Err(err) = self.sync_auto_status(scope).await {
+            error!("Failed to sync goal status for {:?}: {}", scope, err);
+        }
+    }
+
+    async fn sync_auto_status(&self, scope: &Scope) -> Result<(), Error> {
+        let task_counts = self.task_repository.count_tasks_by_goal(scope).await?;
+        let goals = self.repository.get_all_user_goals(scope).await?;
+
+        for goal in goals.into_iter().filter(|goal| goal.auto_status) {
+            let Some(counts) = task_counts.iter().find(|counts| Some(counts.goal_id) == goal.id)
+            else {
+                continue;
+            };
+
+            let status = Status::from_progress(counts.progress());
+            if status != goal.status {
+                self.repository
+                    .update_status(scope, goal.id.unwrap(), status)
+                    .await?;
+            }
         }
+
+        Ok(())
     }
 
-    pub async fn get_all_user_goals(
+    /// Checks the category belongs to the same scope as the goal. Categories of other
+    /// scopes are reported as missing, so their ids reveal nothing.
+    async fn ensure_category(
         &self,
-        user_id: &ObjectId,
-    ) -> Result<Vec<Goal>, Error> {
-        let goals = self.repository.get_all_user_goals(user_id).await?;
-        Ok(goals)
+        scope: &Scope,
+        category_id: &ObjectId,
+    ) -> Result<(), GoalServiceError> {
+        match self.category_repository.get_category_by_id(category_id).await? {
+            Some(category) if category.scope() == *scope => Ok(()),
+            _ => Err(GoalServiceError::CategoryNotFound),
+        }
+    }
+
+    async fn get_accessible_goal(
+        &self,
+        user_id: ObjectId,
+        goal_id: ObjectId,
+        access: Access,
+    ) -> Result<Goal, GoalServiceError> {
+        let goal = self
+            .repository
+            .get_goal_by_id(goal_id)
+            .await?
+            .ok_or(GoalServiceError::GoalNotFound)?;
+
+        if !self.access.allows(&user_id, &goal.scope(), access).await? {
+            return Err(GoalServiceError::GoalForbidden);
+        }
+        Ok(goal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::category::models::{Category, Color};
+
+    use super::*;
+
+    fn goal_service(repositories: &Repositories) -> GoalService {
+        GoalService::new(
+            repositories.goals.clone(),
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        )
+    }
+
+    fn create_request(title: &str) -> CreateGoalRequest {
+        serde_json::from_value(json!({
+            "title": title,
+            "description": "Description",
+            "priority": "HIGH",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn goals_of_another_user_are_forbidden() {
+        let repositories = Repositories::in_memory();
+        let service = goal_service(&repositories);
+        let owner_id = ObjectId::new();
+        let other_id = ObjectId::new();
+        let goal_id = service
+            .create_goal_for_user(owner_id, create_request("Run"))
+            .await
+            .unwrap();
+
+        assert!(service.get_user_goal(owner_id, goal_id).await.is_ok());
+        assert!(matches!(
+            service.get_user_goal(other_id, goal_id).await,
+            Err(GoalServiceError::GoalForbidden)
+        ));
+        let update: UpdateGoalRequest = serde_json::from_value(json!({ "title": "Mine" })).unwrap();
+        assert!(matches!(
+            service.update_user_goal(other_id, goal_id, update).await,
+            Err(GoalServiceError::GoalForbidden)
+        ));
+        assert!(matches!(
+            service.delete_user_goal(other_id, goal_id).await,
+            Err(GoalServiceError::GoalForbidden)
+        ));
+        assert!(service
+            .get_all_user_goals(&Scope::User(other_id))
+            .await
+            .unwrap()
+            .is_empty());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn categories_of_another_scope_are_rejected() {
+        let repositories = Repositories::in_memory();
+        let service = goal_service(&repositories);
+        let user_id = ObjectId::new();
+        let other_category = repositories
+            .categories
+            .create_category(Category {
+                id: None,
+                user_id: ObjectId::new(),
+                workspace_id: None,
+                title: "Theirs".to_string(),
+                color: Color::Green,
+            })
+            .await
+            .unwrap();
+
+        let mut request = create_request("Run");
+        request.category_id = Some(other_category);
+        assert!(matches!(
+            service.create_goal_for_user(user_id, request).await,
+            Err(GoalServiceError::CategoryNotFound)
+        ));
+
+        let goal_id = service
+            .create_goal_for_user(user_id, create_request("Run"))
+            .await
+            .unwrap();
+        let update: UpdateGoalRequest =
+            serde_json::from_value(json!({ "category_id": other_category.to_hex() })).unwrap();
+        assert!(matches!(
+            service.update_user_goal(user_id, goal_id, update).await,
+            Err(GoalServiceError::CategoryNotFound)
+        ));
+        assert!(service
+            .get_user_goal(user_id, goal_id)
+            .await
+            .unwrap()
+            .category_id
+            .is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::warn;
+use thiserror::Error;
+
+use crate::config::app::{Environment, SmtpConfig};
+use crate::modules::user::types::Email;
+
+use super::{outbox::InMemoryOutbox, smtp::SmtpMailer};
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("Invalid email address: {0}")]
+    InvalidAddress(#[from] lettre::address::AddressError),
+
+    #[error("Failed to build email: {0}")]
+    Build(#[from] lettre::error::Error),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("SMTP_HOST must be set unless APP_ENV is development")]
+    MissingSmtpHost,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to_name: Option<String>,
+    pub to: Email,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), MailError>;
+}
+
+/// SMTP when a host is configured. Without one, development runs keep mail in an in-memory
+/// outbox, where nothing leaves the process; other environments refuse to start rather than
+/// silently dropping verification and reset emails.
+pub fn from_config(
+    config: &SmtpConfig,
+    environment: Option<Environment>,
+) -> Result<Arc<dyn Mailer>, MailError> {
+    match SmtpMailer::from_config(config) {
+        Some(mailer) => Ok(Arc::new(mailer?)),
+        None if environment != Some(Environment::Development) => Err(MailError::MissingSmtpHost),
+        None => {
+            warn!("SMTP_HOST is not set, emails will only be kept in memory");
+            Ok(Arc::new(InMemoryOutbox::default()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_outbox_needs_an_explicit_development_environment() {
+        let config = SmtpConfig::default();
+
+        assert!(from_config(&config, Some(Environment::Development)).is_ok());
+        assert!(matches!(
+            from_config(&config, None),
+            Err(MailError::MissingSmtpHost)
+        ));
+        assert!(matches!(
+            from_config(&config, Some(Environment::Production)),
+            Err(MailError::MissingSmtpHost)
+        ));
+    }
+}
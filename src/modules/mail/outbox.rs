@@ -0,0 +1,26 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::mailer::{Mail, MailError, Mailer};
+
+/// Keeps sent mail in memory instead of delivering it, for tests and local runs.
+#[derive(Default)]
+pub struct InMemoryOutbox {
+    messages: Mutex<Vec<Mail>>,
+}
+
+impl InMemoryOutbox {
+    #[cfg(test)]
+    pub fn messages(&self) -> Vec<Mail> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryOutbox {
+    async fn send(&self, mail: Mail) -> Result<(), MailError> {
+        self.messages.lock().unwrap().push(mail);
+        Ok(())
+    }
+}
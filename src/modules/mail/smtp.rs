@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::config::app::SmtpConfig;
+
+use super::mailer::{Mail, MailError, Mailer};
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// Builds the mailer, or `None` when no SMTP host is configured.
+    pub fn from_config(config: &SmtpConfig) -> Option<Result<Self, MailError>> {
+        let host = config.host.as_deref().filter(|host| !host.is_empty())?;
+        Some(Self::build(host, config))
+    }
+
+    fn build(host: &str, config: &SmtpConfig) -> Result<Self, MailError> {
+        let mut builder = if config.tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let from = config.from.parse()?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), MailError> {
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(Mailbox::new(mail.to_name, mail.to.as_str().parse()?))
+            .subject(mail.subject)
+            .body(mail.body)?;
+
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
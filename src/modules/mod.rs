@@ -1,6 +1,13 @@
+pub mod admin;
+pub mod archive;
+pub mod audit;
 pub mod auth;
+pub mod calendar;
 pub mod category;
 pub mod goal;
+pub mod mail;
+pub mod rate_limit;
 pub mod user;
 pub mod task;
 pub mod notification;
+pub mod workspace;
@@ -0,0 +1,409 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use mongodb::{bson::oid::ObjectId, Database};
+use reqwest::{redirect, Url};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::net::lookup_host;
+
+use crate::config::app::SmtpConfig;
+use crate::modules::{
+    mail::{
+        mailer::{Mail, MailError, Mailer},
+        smtp::SmtpMailer,
+    },
+    task::models::Task,
+    user::models::User,
+};
+
+use super::{
+    models::{InboxMessage, NotificationChannelKind},
+    repository::InboxRepository,
+};
+
+#[derive(Error, Debug)]
+pub enum NotificationChannelError {
+    #[error("Channel is not configured: {0}")]
+    NotConfigured(&'static str),
+
+    #[error("User has no destination for this channel")]
+    MissingDestination,
+
+    #[error("Email error: {0}")]
+    Mail(#[from] MailError),
+
+    #[error("Webhook error: {0}")]
+    Webhook(#[from] reqwest::Error),
+
+    #[error("Webhook URL is not allowed: {0}")]
+    WebhookNotAllowed(&'static str),
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationMessage {
+    pub user_id: ObjectId,
+    pub task_id: ObjectId,
+    pub title: String,
+    pub body: String,
+}
+
+impl NotificationMessage {
+    /// The reminder of the occurrence of `task` starting at `occurrence_start`, with the
+    /// time written in the recipient's `time_zone`.
+    pub fn for_task(task: &Task, occurrence_start: DateTime<Utc>, time_zone: &Tz) -> Self {
+        Self {
+            user_id: task.recipient_id(),
+            task_id: task.id.unwrap(),
+            title: format!("Reminder: {}", task.title),
+            body: format!(
+                "Your task \"{}\" starts at {}.\n\n{}",
+                task.title,
+                occurrence_start
+                    .with_timezone(time_zone)
+                    .format("%Y-%m-%d %H:%M %Z"),
+                task.description
+            ),
+        }
+    }
+}
+
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    async fn send(
+        &self,
+        user: &User,
+        message: &NotificationMessage,
+    ) -> Result<(), NotificationChannelError>;
+}
+
+pub struct EmailChannel {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl EmailChannel {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    async fn send(
+        &self,
+        user: &User,
+        message: &NotificationMessage,
+    ) -> Result<(), NotificationChannelError> {
+        self.mailer
+            .send(Mail {
+                to_name: Some(user.name.clone()),
+                to: user.email.clone(),
+                subject: message.title.clone(),
+                body: message.body.clone(),
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+const WEBHOOK_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether the address can be reached from the internet. Loopback, private, link-local
+/// and other reserved ranges are refused, so webhooks cannot probe the internal network.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // Shared address space (carrier-grade NAT)
+        || (a == 198 && (18..20).contains(&b)) // Benchmarking
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // Unique local
+        || (first & 0xffc0) == 0xfe80 // Link-local
+        || first == 0x2001 && ip.segments()[1] == 0x0db8) // Documentation
+}
+
+/// Host of the URL, without the brackets of IPv6 literals.
+fn webhook_host(url: &Url) -> Option<&str> {
+    url.host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+}
+
+/// Checks a webhook URL before it is stored or called: it must use https and must not name
+/// a local host or a non-public address. Names are checked again once resolved, in `send`.
+pub fn validate_webhook_url(url: &str) -> Result<Url, NotificationChannelError> {
+    let url =
+        Url::parse(url).map_err(|_| NotificationChannelError::WebhookNotAllowed("invalid URL"))?;
+    if url.scheme() != "https" {
+        return Err(NotificationChannelError::WebhookNotAllowed(
+            "https is required",
+        ));
+    }
+
+    let host = webhook_host(&url)
+        .ok_or(NotificationChannelError::WebhookNotAllowed(
+            "a host is required",
+        ))?
+        .to_ascii_lowercase();
+    let local = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public_ip(ip),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    };
+    if local {
+        return Err(NotificationChannelError::WebhookNotAllowed(
+            "private and loopback hosts are not allowed",
+        ));
+    }
+    Ok(url)
+}
+
+pub struct WebhookChannel;
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    async fn send(
+        &self,
+        user: &User,
+        message: &NotificationMessage,
+    ) -> Result<(), NotificationChannelError> {
+        let url = user
+            .webhook_url
+            .as_ref()
+            .ok_or(NotificationChannelError::MissingDestination)?;
+        let url = validate_webhook_url(url)?;
+
+        // The host is resolved once and the request pinned to that address, so a name
+        // cannot resolve to a public address here and to a private one on connect.
+        let host = webhook_host(&url).unwrap_or_default().to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addresses: Vec<_> = lookup_host((host.as_str(), port))
+            .await
+            .map_err(|_| NotificationChannelError::WebhookNotAllowed("host not found"))?
+            .collect();
+        let address = match addresses.first() {
+            Some(address) if addresses.iter().all(|address| is_public_ip(address.ip())) => *address,
+            _ => {
+                return Err(NotificationChannelError::WebhookNotAllowed(
+                    "host resolves to a private address",
+                ))
+            }
+        };
+
+        let client = reqwest::Client::builder()
+            .connect_timeout(WEBHOOK_CONNECT_TIMEOUT)
+            .timeout(WEBHOOK_TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .resolve(&host, address)
+            .build()?;
+        client
+            .post(url)
+            .json(&serde_json::json!({
+                "user_id": message.user_id.to_string(),
+                "task_id": message.task_id.to_string(),
+                "title": message.title,
+                "body": message.body,
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+pub struct InAppChannel {
+    repository: InboxRepository,
+}
+
+impl InAppChannel {
+    pub fn new(repository: InboxRepository) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for InAppChannel {
+    async fn send(
+        &self,
+        _user: &User,
+        message: &NotificationMessage,
+    ) -> Result<(), NotificationChannelError> {
+        self.repository
+            .create_message(InboxMessage {
+                id: None,
+                user_id: message.user_id,
+                task_id: message.task_id,
+                title: message.title.clone(),
+                body: message.body.clone(),
+                created_at: Utc::now(),
+                read: false,
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+pub struct NotificationDispatcher {
+    email: Option<Box<dyn NotificationChannel>>,
+    webhook: Box<dyn NotificationChannel>,
+    in_app: Box<dyn NotificationChannel>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(
+        email: Option<Box<dyn NotificationChannel>>,
+        webhook: Box<dyn NotificationChannel>,
+        in_app: Box<dyn NotificationChannel>,
+    ) -> Self {
+        Self {
+            email,
+            webhook,
+            in_app,
+        }
+    }
+
+    pub fn from_config(
+        config: &SmtpConfig,
+        db: &Database,
+    ) -> Result<Self, NotificationChannelError> {
+        // Unlike account emails, reminders are never sent to the in-memory outbox.
+        let email = match SmtpMailer::from_config(config) {
+            Some(mailer) => Some(Box::new(EmailChannel::new(Arc::new(mailer?)))
+                as Box<dyn NotificationChannel>),
+            None => None,
+        };
+
+        Ok(Self::new(
+            email,
+            Box::new(WebhookChannel),
+            Box::new(InAppChannel::new(InboxRepository::new(db))),
+        ))
+    }
+
+    pub async fn dispatch(
+        &self,
+        user: &User,
+        message: &NotificationMessage,
+    ) -> Result<(), NotificationChannelError> {
+        let channel = match user.notification_channel {
+            NotificationChannelKind::Email => self
+                .email
+                .as_deref()
+                .ok_or(NotificationChannelError::NotConfigured("email"))?,
+            NotificationChannelKind::Webhook => self.webhook.as_ref(),
+            NotificationChannelKind::InApp => self.in_app.as_ref(),
+        };
+
+        channel.send(user, message).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::modules::task::models::Status;
+
+    use super::*;
+
+    #[test]
+    fn webhooks_require_https() {
+        assert!(validate_webhook_url("https://hooks.example.com/planit").is_ok());
+        assert!(validate_webhook_url("http://hooks.example.com/planit").is_err());
+        assert!(validate_webhook_url("ftp://hooks.example.com/planit").is_err());
+        assert!(validate_webhook_url("not a url").is_err());
+    }
+
+    #[test]
+    fn webhooks_to_local_hosts_are_rejected() {
+        for url in [
+            "https://localhost/hook",
+            "https://api.localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://10.0.0.5/hook",
+            "https://172.16.0.1/hook",
+            "https://192.168.1.10/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hook",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(validate_webhook_url(url).is_err(), "{url} was accepted");
+        }
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        assert!(validate_webhook_url("https://93.184.216.34/hook").is_ok());
+        assert!(validate_webhook_url("https://[2606:4700::1111]/hook").is_ok());
+        assert!(is_public_ip("8.8.8.8".parse().unwrap()));
+        assert!(!is_public_ip("192.168.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn reminders_show_the_occurrence_in_the_recipient_time_zone() {
+        let start_date = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let task = Task {
+            id: Some(ObjectId::new()),
+            title: "Report".to_string(),
+            description: "Weekly report".to_string(),
+            start_date,
+            end_date: start_date + chrono::Duration::hours(1),
+            status: Status::Adiada,
+            user_id: ObjectId::new(),
+            workspace_id: None,
+            assignee_id: None,
+            category_id: ObjectId::new(),
+            goal_id: None,
+            notification: None,
+            recurrence: Some(
+                serde_json::from_value(serde_json::json!({ "frequency": "WEEKLY" })).unwrap(),
+            ),
+            occurrence_overrides: Vec::new(),
+            subtasks: Vec::new(),
+            derive_status: false,
+        };
+
+        let message = NotificationMessage::for_task(
+            &task,
+            start_date + chrono::Duration::weeks(2),
+            &Tz::America__Sao_Paulo,
+        );
+        assert_eq!(
+            message.body,
+            "Your task \"Report\" starts at 2024-03-15 09:00 -03.\n\nWeekly report"
+        );
+    }
+}
@@ -1,9 +1,6 @@
 use crate::{
     helpers::api_response::ApiResponse,
-    modules::{
-        auth::{self, dto::AuthState},
-        task::repository::TaskRepository,
-    },
+    modules::auth::{self, dto::AuthState},
     AppState,
 };
 use axum::{
@@ -15,11 +12,13 @@ use axum::{
 };
 use std::sync::Arc;
 
+use super::repository::InboxRepository;
+
 async fn get_notifications(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
 ) -> impl IntoResponse {
-    let task_repository = TaskRepository::new(&state.mongodb);
+    let task_repository = state.repositories.tasks.clone();
 
     match task_repository.get_all_with_notifications(&user.id).await {
         Ok(notifications) => {
@@ -34,8 +33,30 @@ async fn get_notifications(
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
+async fn get_inbox(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    let inbox_repository = InboxRepository::new(&state.mongodb);
+
+    match inbox_repository.get_all_user_messages(&user.id).await {
+        Ok(messages) => {
+            ApiResponse::ok("Inbox retrieved successfully", Some(messages)).into_response()
+        }
+        Err(err) => ApiResponse::server_error(
+            Some("Failed to retrieve inbox"),
+            Some(err.to_string()),
+        )
+        .into_response(),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route("/v1/notifications", get(get_notifications))
-        .layer(middleware::from_fn(auth::middlewares::authorize))
+        .route("/v1/notifications/inbox", get(get_inbox))
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
 }
@@ -1,5 +1,7 @@
 
+pub mod channels;
 pub mod models;
+pub mod repository;
 pub mod scheduler;
 pub mod handles;
 
@@ -1,5 +1,9 @@
-use crate::helpers::object_id_helper::{deserialize_object_id, serialize_object_id};
-use chrono::{DateTime, Utc};
+use crate::helpers::date_helper::{deserialize_bson_datetime, serialize_bson_datetime};
+use crate::helpers::object_id_helper::{
+    deserialize_object_id, deserialize_option_object_id, serialize_object_id,
+    serialize_option_object_id,
+};
+use chrono::{DateTime, Duration, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +22,24 @@ impl TimeUnit {
             TimeUnit::Hour => "HOUR",
         }
     }
+
+    pub fn duration(&self, value: u16) -> Duration {
+        match self {
+            TimeUnit::Minute => Duration::minutes(value as i64),
+            TimeUnit::Hour => Duration::hours(value as i64),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub enum NotificationChannelKind {
+    #[serde(rename = "EMAIL")]
+    Email,
+    #[serde(rename = "WEBHOOK")]
+    Webhook,
+    #[default]
+    #[serde(rename = "IN_APP")]
+    InApp,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,8 +52,33 @@ pub struct Notification {
     pub id: ObjectId,
     pub time_unit: TimeUnit,
     pub time_value: u16,
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
     pub scheduled_time: DateTime<Utc>,
     pub sent: bool,
     #[serde(default)]
     pub viewed: bool,
+    #[serde(default)]
+    pub attempts: u32, // Failed deliveries of the current occurrence
+    #[serde(default)]
+    pub missed: bool, // Not sent because it was overdue when the scheduler got to it
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboxMessage {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub task_id: ObjectId,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub read: bool,
 }
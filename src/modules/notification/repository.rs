@@ -0,0 +1,43 @@
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::Error;
+use mongodb::{bson::doc, Collection, Database};
+
+use super::models::InboxMessage;
+
+pub struct InboxRepository {
+    collection: Collection<InboxMessage>,
+}
+
+impl InboxRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("inbox");
+        InboxRepository { collection }
+    }
+
+    pub async fn create_message(&self, message: InboxMessage) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(message).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    pub async fn delete_all_user_messages(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.collection.delete_many(doc! { "user_id": user_id }).await?;
+        Ok(result.deleted_count)
+    }
+
+    pub async fn get_all_user_messages(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<Vec<InboxMessage>, Error> {
+        let mut cursor = self
+            .collection
+            .find(doc! { "user_id": user_id })
+            .sort(doc! { "created_at": -1 })
+            .await?;
+        let mut messages = Vec::new();
+        while cursor.advance().await? {
+            messages.push(cursor.deserialize_current()?);
+        }
+
+        Ok(messages)
+    }
+}
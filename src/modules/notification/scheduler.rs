@@ -1,20 +1,42 @@
-use crate::modules::task::{models::Task, repository::TaskRepository};
+use crate::helpers::time_zone_helper::default_time_zone;
+use crate::modules::{
+    task::{models::Task, recurrence, repository::TaskRepository},
+    user::{models::User, repository::UserRepository},
+};
 
-use chrono::{Duration, Utc};
-use log::{debug, error};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use log::{debug, error, info, warn};
 use tokio::{
     sync::Semaphore,
     time::{sleep, Duration as TokioDuration},
 };
 
+use super::channels::{NotificationDispatcher, NotificationMessage};
+
 const MAX_NOTIFICATIONS: usize = 1;
 
-pub async fn boot(task_repository: &TaskRepository) {
+/// A reminder that fails is retried on each check, once a minute, until it has failed this
+/// many times; then its occurrence is skipped.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Reminders found later than this, after the scheduler was down, are not sent any more:
+/// they are marked as missed, and recurring ones move on to their next occurrence.
+const MISSED_AFTER_MINUTES: i64 = 60;
+
+pub async fn boot(
+    task_repository: &dyn TaskRepository,
+    user_repository: &dyn UserRepository,
+    dispatcher: &NotificationDispatcher,
+) {
     let semaphore = Semaphore::new(MAX_NOTIFICATIONS);
 
     loop {
         debug!("Looping to check notifications");
-        if let Err(e) = check_and_send_notifications(task_repository, &semaphore).await {
+        if let Err(e) =
+            check_and_send_notifications(task_repository, user_repository, dispatcher, &semaphore)
+                .await
+        {
             error!("Error while checking notifications: {}", e);
         }
 
@@ -23,14 +45,17 @@ pub async fn boot(task_repository: &TaskRepository) {
 }
 
 pub async fn check_and_send_notifications(
-    repository: &TaskRepository,
+    repository: &dyn TaskRepository,
+    user_repository: &dyn UserRepository,
+    dispatcher: &NotificationDispatcher,
     semaphore: &Semaphore,
 ) -> Result<(), Box<dyn std::error::Error>> {
     debug!("Checking for new notifications");
-    let now = Utc::now() - Duration::hours(3); // TODO: Remove hardcoded timezone
-    let upper_bound = now + chrono::Duration::seconds(60);
+    let now = Utc::now();
+    // Every pending reminder that is due, however old, so none is dropped after downtime.
+    let upper_bound = now + Duration::seconds(60);
     let tasks = repository
-        .get_all_not_sent_notifications(now, upper_bound)
+        .get_all_not_sent_notifications(upper_bound)
         .await?;
     debug!("Found {} tasks to notify", tasks.len());
 
@@ -38,8 +63,11 @@ pub async fn check_and_send_notifications(
         let permit = semaphore.acquire().await;
         match permit {
             Ok(_permit) => {
-                if let Some(notification) = &task.notification {
-                    if let Err(e) = process_notification(repository, &task).await {
+                if task.notification.is_some() {
+                    if let Err(e) =
+                        process_notification(repository, user_repository, dispatcher, &task, now)
+                            .await
+                    {
                         error!(
                             "Error while processing notification for task {}: {}",
                             task.id.unwrap(),
@@ -56,12 +84,458 @@ pub async fn check_and_send_notifications(
 }
 
 async fn process_notification(
-    repository: &TaskRepository,
+    repository: &dyn TaskRepository,
+    user_repository: &dyn UserRepository,
+    dispatcher: &NotificationDispatcher,
     task: &Task,
+    now: DateTime<Utc>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    repository
-        .mark_notification_as_sent(&task.id.unwrap())
+    let Some(notification) = task.notification.as_ref() else {
+        return Ok(());
+    };
+    let recipient = user_repository
+        .find_user_by_id(&task.recipient_id())
         .await?;
-    // TODO: Send notification to user
+    let missed = notification.scheduled_time < now - Duration::minutes(MISSED_AFTER_MINUTES);
+
+    // Reminders that cannot or should not be delivered still move on as if delivered.
+    let delivery = match recipient.as_ref() {
+        _ if missed => {
+            warn!(
+                "Notification for task {} was due at {}: marked as missed",
+                task.id.unwrap(),
+                notification.scheduled_time
+            );
+            None
+        }
+        None => {
+            warn!(
+                "Skipping notification for task {}: user not found",
+                task.id.unwrap()
+            );
+            None
+        }
+        Some(user) if user.disabled => {
+            info!(
+                "Skipping notification for task {}: user {} is disabled",
+                task.id.unwrap(),
+                user.id.unwrap()
+            );
+            None
+        }
+        Some(user) => {
+            let occurrence_start = notification.scheduled_time
+                + notification.time_unit.duration(notification.time_value);
+            let message = NotificationMessage::for_task(task, occurrence_start, &user.time_zone);
+            Some(dispatcher.dispatch(user, &message).await)
+        }
+    };
+    if let Some(Err(err)) = delivery {
+        let attempts = notification.attempts + 1;
+        if attempts < MAX_ATTEMPTS {
+            repository.record_failed_attempt(&task.id.unwrap()).await?;
+            return Err(err.into());
+        }
+        warn!(
+            "Giving up on notification for task {} after {} attempts: {}",
+            task.id.unwrap(),
+            attempts,
+            err
+        );
+    }
+
+    let time_zone = task_time_zone(user_repository, task, recipient.as_ref()).await?;
+    // A missed reminder skips every occurrence whose reminder time has passed.
+    let not_before = if missed {
+        now
+    } else {
+        notification.scheduled_time
+    };
+    match next_scheduled_time(task, &time_zone, not_before) {
+        Some(scheduled_time) => {
+            repository
+                .reschedule_notification(&task.id.unwrap(), scheduled_time)
+                .await?;
+        }
+        None if missed => {
+            repository
+                .mark_notification_as_missed(&task.id.unwrap())
+                .await?;
+        }
+        None => {
+            repository
+                .mark_notification_as_sent(&task.id.unwrap())
+                .await?;
+        }
+    }
     Ok(())
 }
+
+/// The zone the task's occurrences are expanded in: its owner's, as in the task API and
+/// the calendar feed, whoever the reminder goes to.
+async fn task_time_zone(
+    user_repository: &dyn UserRepository,
+    task: &Task,
+    recipient: Option<&User>,
+) -> Result<Tz, Box<dyn std::error::Error>> {
+    if let Some(user) = recipient.filter(|user| user.id == Some(task.user_id)) {
+        return Ok(user.time_zone);
+    }
+    let owner = user_repository.find_user_by_id(&task.user_id).await?;
+    Ok(owner
+        .as_ref()
+        .or(recipient)
+        .map_or_else(default_time_zone, |user| user.time_zone))
+}
+
+/// For recurring tasks, the notification time of the next non-cancelled occurrence whose
+/// reminder comes after `not_before`.
+fn next_scheduled_time(
+    task: &Task,
+    time_zone: &Tz,
+    not_before: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let rule = task.recurrence.as_ref()?;
+    let notification = task.notification.as_ref()?;
+    let offset = notification.time_unit.duration(notification.time_value);
+
+    let mut current = not_before + offset;
+    loop {
+        let next = recurrence::next_after(task.start_date, rule, time_zone, current)?;
+        let cancelled = task
+            .occurrence_overrides
+            .iter()
+            .any(|exception| exception.occurrence_start == next && exception.cancelled);
+        if !cancelled {
+            return Some(next - offset);
+        }
+        current = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use mongodb::bson::oid::ObjectId;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::{
+        notification::{
+            channels::{NotificationChannel, NotificationChannelError},
+            models::{Notification, NotificationChannelKind, TimeUnit},
+        },
+        task::models::Status,
+        user::{
+            models::Role,
+            types::{Email, HashedPassword, PhoneNumber},
+        },
+    };
+
+    use super::*;
+
+    /// Counts deliveries, failing every one when `fail` is set.
+    struct CountingChannel {
+        fail: bool,
+        sent: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for CountingChannel {
+        async fn send(
+            &self,
+            _user: &User,
+            _message: &NotificationMessage,
+        ) -> Result<(), NotificationChannelError> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(NotificationChannelError::MissingDestination);
+            }
+            Ok(())
+        }
+    }
+
+    fn dispatcher(fail: bool, sent: &Arc<AtomicUsize>) -> NotificationDispatcher {
+        let channel = || {
+            Box::new(CountingChannel {
+                fail,
+                sent: sent.clone(),
+            }) as Box<dyn NotificationChannel>
+        };
+        NotificationDispatcher::new(None, channel(), channel())
+    }
+
+    async fn create_user(
+        repositories: &Repositories,
+        notification_channel: NotificationChannelKind,
+    ) -> ObjectId {
+        create_user_in(
+            repositories,
+            "ana@example.com",
+            notification_channel,
+            chrono_tz::UTC,
+        )
+        .await
+    }
+
+    async fn create_user_in(
+        repositories: &Repositories,
+        email: &str,
+        notification_channel: NotificationChannelKind,
+        time_zone: Tz,
+    ) -> ObjectId {
+        repositories
+            .users
+            .create_user(User {
+                id: None,
+                name: "Ana".to_string(),
+                email: Email::parse(email).unwrap(),
+                password: HashedPassword::new("hash".to_string()),
+                phone: PhoneNumber::parse("+5511999999999").unwrap(),
+                notification_channel,
+                webhook_url: None,
+                time_zone,
+                feed_token_hash: None,
+                language: Default::default(),
+                pending_email: None,
+                verified: true,
+                role: Role::User,
+                disabled: false,
+                deleted: false,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn create_task(repositories: &Repositories, user_id: ObjectId) -> ObjectId {
+        let task = new_task(user_id, Utc::now() + Duration::minutes(10));
+        repositories.tasks.create_task(task).await.unwrap()
+    }
+
+    /// A task reminding its owner 10 minutes before `start_date`.
+    fn new_task(user_id: ObjectId, start_date: DateTime<Utc>) -> Task {
+        Task {
+            id: None,
+            title: "Report".to_string(),
+            description: "Description".to_string(),
+            start_date,
+            end_date: start_date + Duration::hours(1),
+            status: Status::Adiada,
+            user_id,
+            workspace_id: None,
+            assignee_id: None,
+            category_id: ObjectId::new(),
+            goal_id: None,
+            notification: Some(Notification {
+                id: ObjectId::new(),
+                time_unit: TimeUnit::Minute,
+                time_value: 10,
+                scheduled_time: start_date - Duration::minutes(10),
+                sent: false,
+                viewed: false,
+                attempts: 0,
+                missed: false,
+            }),
+            recurrence: None,
+            occurrence_overrides: Vec::new(),
+            subtasks: Vec::new(),
+            derive_status: false,
+        }
+    }
+
+    async fn notification(repositories: &Repositories, task_id: &ObjectId) -> Notification {
+        repositories
+            .tasks
+            .get_task_by_id(task_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .notification
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn delivered_notifications_are_marked_as_sent() {
+        let repositories = Repositories::in_memory();
+        let user_id = create_user(&repositories, NotificationChannelKind::InApp).await;
+        let task_id = create_task(&repositories, user_id).await;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let dispatcher = dispatcher(false, &sent);
+        let semaphore = Semaphore::new(MAX_NOTIFICATIONS);
+
+        for _ in 0..2 {
+            check_and_send_notifications(
+                repositories.tasks.as_ref(),
+                repositories.users.as_ref(),
+                &dispatcher,
+                &semaphore,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert!(notification(&repositories, &task_id).await.sent);
+    }
+
+    #[tokio::test]
+    async fn failing_notifications_give_up_after_the_last_attempt() {
+        let repositories = Repositories::in_memory();
+        // Email is not configured in the dispatcher, so every delivery fails.
+        let email_user = create_user(&repositories, NotificationChannelKind::Email).await;
+        let email_task = create_task(&repositories, email_user).await;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let dispatcher = dispatcher(true, &sent);
+        let semaphore = Semaphore::new(MAX_NOTIFICATIONS);
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            check_and_send_notifications(
+                repositories.tasks.as_ref(),
+                repositories.users.as_ref(),
+                &dispatcher,
+                &semaphore,
+            )
+            .await
+            .unwrap();
+
+            let notification = notification(&repositories, &email_task).await;
+            if attempt < MAX_ATTEMPTS {
+                assert_eq!(notification.attempts, attempt);
+                assert!(!notification.sent);
+            } else {
+                assert!(notification.sent);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_users_are_not_notified() {
+        let repositories = Repositories::in_memory();
+        let user_id = create_user(&repositories, NotificationChannelKind::InApp).await;
+        repositories
+            .users
+            .set_disabled(&user_id, true)
+            .await
+            .unwrap();
+        let task_id = create_task(&repositories, user_id).await;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let dispatcher = dispatcher(false, &sent);
+        let semaphore = Semaphore::new(MAX_NOTIFICATIONS);
+
+        check_and_send_notifications(
+            repositories.tasks.as_ref(),
+            repositories.users.as_ref(),
+            &dispatcher,
+            &semaphore,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+        assert!(notification(&repositories, &task_id).await.sent);
+    }
+
+    #[tokio::test]
+    async fn the_scheduler_catches_up_after_a_gap() {
+        let repositories = Repositories::in_memory();
+        let user_id = create_user(&repositories, NotificationChannelKind::InApp).await;
+        // Stored dates keep milliseconds.
+        let now = DateTime::from_timestamp_millis(Utc::now().timestamp_millis()).unwrap();
+        // Left behind while the scheduler was down: a one-off and a daily reminder due
+        // hours and days ago, and one due half an hour ago.
+        let mut missed = new_task(user_id, now - Duration::hours(3));
+        missed.title = "Missed".to_string();
+        let missed_task = repositories.tasks.create_task(missed).await.unwrap();
+        let mut daily = new_task(user_id, now - Duration::days(3));
+        daily.title = "Daily".to_string();
+        daily.recurrence =
+            Some(serde_json::from_value(serde_json::json!({ "frequency": "DAILY" })).unwrap());
+        let daily_task = repositories.tasks.create_task(daily).await.unwrap();
+        let late_task = repositories
+            .tasks
+            .create_task(new_task(user_id, now - Duration::minutes(20)))
+            .await
+            .unwrap();
+        let sent = Arc::new(AtomicUsize::new(0));
+        let dispatcher = dispatcher(false, &sent);
+        let semaphore = Semaphore::new(MAX_NOTIFICATIONS);
+
+        check_and_send_notifications(
+            repositories.tasks.as_ref(),
+            repositories.users.as_ref(),
+            &dispatcher,
+            &semaphore,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        let late = notification(&repositories, &late_task).await;
+        assert!(late.sent && !late.missed);
+        let missed = notification(&repositories, &missed_task).await;
+        assert!(missed.sent && missed.missed);
+        // The series keeps reminding from its next occurrence on.
+        let daily = notification(&repositories, &daily_task).await;
+        assert!(!daily.sent);
+        assert!(daily.scheduled_time > now);
+        assert!(daily.scheduled_time <= now + Duration::days(1));
+        let first = now - Duration::days(3) - Duration::minutes(10);
+        assert_eq!((daily.scheduled_time - first).num_seconds() % 86_400, 0);
+    }
+
+    #[tokio::test]
+    async fn recurring_reminders_follow_the_owner_time_zone() {
+        let repositories = Repositories::in_memory();
+        let owner_id = create_user_in(
+            &repositories,
+            "owner@example.com",
+            NotificationChannelKind::InApp,
+            Tz::America__New_York,
+        )
+        .await;
+        let assignee_id = create_user_in(
+            &repositories,
+            "assignee@example.com",
+            NotificationChannelKind::InApp,
+            chrono_tz::UTC,
+        )
+        .await;
+        // Daily at 9:00 in New York, which moves to daylight saving time on March 10th.
+        let start_date = Utc.with_ymd_and_hms(2024, 3, 8, 14, 0, 0).unwrap();
+        let mut task = new_task(owner_id, start_date);
+        task.assignee_id = Some(assignee_id);
+        task.recurrence =
+            Some(serde_json::from_value(serde_json::json!({ "frequency": "DAILY" })).unwrap());
+        task.notification.as_mut().unwrap().scheduled_time =
+            Utc.with_ymd_and_hms(2024, 3, 9, 13, 50, 0).unwrap();
+        let task_id = repositories.tasks.create_task(task).await.unwrap();
+        let task = repositories
+            .tasks
+            .get_task_by_id(&task_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let sent = Arc::new(AtomicUsize::new(0));
+
+        process_notification(
+            repositories.tasks.as_ref(),
+            repositories.users.as_ref(),
+            &dispatcher(false, &sent),
+            &task,
+            Utc.with_ymd_and_hms(2024, 3, 9, 13, 50, 30).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            notification(&repositories, &task_id).await.scheduled_time,
+            Utc.with_ymd_and_hms(2024, 3, 10, 12, 50, 0).unwrap()
+        );
+    }
+}
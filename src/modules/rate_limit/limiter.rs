@@ -0,0 +1,104 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use log::error;
+use mongodb::Database;
+
+use crate::config::app::RateLimitBackend;
+
+use super::{memory::InMemoryStore, mongo::MongoStore};
+
+/// A token bucket: up to `capacity` requests in a burst, refilled evenly so that
+/// `capacity` more are allowed every `period`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub const fn new(capacity: u32, period: Duration) -> Self {
+        RateLimit { capacity, period }
+    }
+
+    /// Tokens added back per second.
+    pub fn refill_rate(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+
+    /// Time until a bucket holding `tokens` has a whole token again.
+    pub fn retry_after(&self, tokens: f64) -> Duration {
+        let missing = (1.0 - tokens).max(0.0);
+        Duration::from_secs((missing / self.refill_rate()).ceil() as u64)
+    }
+}
+
+pub const LOGIN_PER_IP: RateLimit = RateLimit::new(10, Duration::from_secs(60));
+pub const LOGIN_PER_ACCOUNT: RateLimit = RateLimit::new(10, Duration::from_secs(15 * 60));
+pub const USER_EXISTS_PER_IP: RateLimit = RateLimit::new(20, Duration::from_secs(60));
+pub const ACCOUNT_RECOVERY_PER_IP: RateLimit = RateLimit::new(5, Duration::from_secs(60));
+pub const SIGNUP_PER_IP: RateLimit = RateLimit::new(10, Duration::from_secs(60 * 60));
+// Longer than the period of every limit above, so expiring idle buckets changes no decision.
+pub const IDLE_BUCKET_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateDecision {
+    Allowed,
+    Limited(Duration),
+}
+
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes one token from the bucket stored under `key`, creating it full if missing.
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateDecision, mongodb::error::Error>;
+}
+
+/// A limit applied to one kind of key (client IP, account, ...) for one group of routes.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    limit: RateLimit,
+    scope: &'static str,
+    pub trust_proxy_headers: bool,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, limit: RateLimit, scope: &'static str) -> Self {
+        RateLimiter {
+            store,
+            limit,
+            scope,
+            trust_proxy_headers: false,
+        }
+    }
+
+    /// Keys requests limited by IP on the `X-Forwarded-For` address, see `client_ip`.
+    pub fn trusting_proxy_headers(mut self, trust: bool) -> Self {
+        self.trust_proxy_headers = trust;
+        self
+    }
+
+    /// Fails open: a broken store should not lock every user out.
+    pub async fn check(&self, key: &str) -> RateDecision {
+        let key = format!("{}:{}", self.scope, key);
+        match self.store.acquire(&key, &self.limit).await {
+            Ok(decision) => decision,
+            Err(err) => {
+                error!("Rate limit check failed for {}: {}", key, err);
+                RateDecision::Allowed
+            }
+        }
+    }
+}
+
+/// MongoDB shares buckets between instances; memory is only accurate with a single instance.
+pub fn from_config(backend: RateLimitBackend, db: &Database) -> Arc<dyn RateLimitStore> {
+    match backend {
+        RateLimitBackend::MongoDb => Arc::new(MongoStore::new(db)),
+        RateLimitBackend::Memory => Arc::new(InMemoryStore::default()),
+    }
+}
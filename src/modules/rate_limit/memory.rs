@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use super::limiter::{RateDecision, RateLimit, RateLimitStore};
+
+// Full buckets behave like missing ones, so they are dropped at most this often.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+    /// When the bucket is full again under the limit it was last taken from.
+    full_at: Instant,
+}
+
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    pruned_at: Instant,
+}
+
+pub struct InMemoryStore {
+    buckets: Mutex<Buckets>,
+}
+
+impl Default for InMemoryStore {
+    fn default() -> Self {
+        InMemoryStore {
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                pruned_at: Instant::now(),
+            }),
+        }
+    }
+}
+
+fn refill(bucket: &Bucket, limit: &RateLimit, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+    (bucket.tokens + elapsed * limit.refill_rate()).min(limit.capacity as f64)
+}
+
+impl InMemoryStore {
+    fn take(&self, key: &str, limit: &RateLimit, now: Instant) -> RateDecision {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if now.duration_since(buckets.pruned_at) >= PRUNE_INTERVAL {
+            buckets.by_key.retain(|_, bucket| bucket.full_at > now);
+            buckets.pruned_at = now;
+        }
+
+        let bucket = buckets.by_key.entry(key.to_string()).or_insert(Bucket {
+            tokens: limit.capacity as f64,
+            updated_at: now,
+            full_at: now,
+        });
+        bucket.tokens = refill(bucket, limit, now);
+        bucket.updated_at = now;
+
+        let decision = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            RateDecision::Allowed
+        } else {
+            RateDecision::Limited(limit.retry_after(bucket.tokens))
+        };
+        let missing = limit.capacity as f64 - bucket.tokens;
+        bucket.full_at = now + Duration::from_secs_f64(missing / limit.refill_rate());
+        decision
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryStore {
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateDecision, mongodb::error::Error> {
+        Ok(self.take(key, limit, Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit::new(2, Duration::from_secs(60));
+
+    #[test]
+    fn buckets_refill_over_the_period() {
+        let store = InMemoryStore::default();
+        let start = Instant::now();
+
+        assert_eq!(store.take("ip", &LIMIT, start), RateDecision::Allowed);
+        assert_eq!(store.take("ip", &LIMIT, start), RateDecision::Allowed);
+        assert_eq!(
+            store.take("ip", &LIMIT, start),
+            RateDecision::Limited(Duration::from_secs(30))
+        );
+        assert_eq!(
+            store.take("ip", &LIMIT, start + Duration::from_secs(30)),
+            RateDecision::Allowed
+        );
+        assert_eq!(store.take("other", &LIMIT, start), RateDecision::Allowed);
+    }
+
+    #[test]
+    fn pruning_keeps_buckets_of_slower_limits() {
+        let store = InMemoryStore::default();
+        let start = Instant::now();
+        let slow = RateLimit::new(1, Duration::from_secs(60 * 60));
+
+        store.take("slow", &slow, start);
+        store.take("fast", &LIMIT, start);
+        // Under the fast limit, the slow bucket would have been full for a while.
+        store.take("fast", &LIMIT, start + 2 * PRUNE_INTERVAL);
+
+        assert!(store.buckets.lock().unwrap().by_key.contains_key("slow"));
+        assert!(matches!(
+            store.take("slow", &slow, start + 2 * PRUNE_INTERVAL),
+            RateDecision::Limited(_)
+        ));
+    }
+}
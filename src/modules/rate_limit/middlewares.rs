@@ -0,0 +1,49 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{Extensions, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::limiter::{RateDecision, RateLimiter};
+use crate::helpers::api_response::ApiResponse;
+
+/// Uses the first `X-Forwarded-For` address when `trust_proxy` is set, which is only safe
+/// behind a proxy that overwrites the header; otherwise the peer address.
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions, trust_proxy: bool) -> String {
+    if trust_proxy {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        if let Some(ip) = forwarded {
+            return ip.to_string();
+        }
+    }
+
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+pub async fn limit_by_ip(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    match limiter
+        .check(&client_ip(
+            req.headers(),
+            req.extensions(),
+            limiter.trust_proxy_headers,
+        ))
+        .await
+    {
+        RateDecision::Allowed => next.run(req).await,
+        RateDecision::Limited(retry_after) => {
+            ApiResponse::too_many_requests("Too many requests, please try again later", retry_after)
+                .into_response()
+        }
+    }
+}
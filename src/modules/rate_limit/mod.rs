@@ -0,0 +1,4 @@
+pub mod limiter;
+pub mod memory;
+pub mod middlewares;
+pub mod mongo;
@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use mongodb::{
+    bson::{doc, DateTime, Document},
+    options::ReturnDocument,
+    Collection, Database,
+};
+
+use super::limiter::{RateDecision, RateLimit, RateLimitStore};
+
+pub struct MongoStore {
+    collection: Collection<Document>,
+}
+
+impl MongoStore {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("rate_limits");
+        MongoStore { collection }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MongoStore {
+    /// Refills and takes a token in a single pipeline update, so concurrent requests
+    /// from several instances cannot spend the same token twice.
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: &RateLimit,
+    ) -> Result<RateDecision, mongodb::error::Error> {
+        let now = DateTime::now();
+        let capacity = limit.capacity as f64;
+        let refill_per_ms = limit.refill_rate() / 1000.0;
+
+        let pipeline = vec![
+            doc! { "$set": {
+                "tokens": { "$min": [
+                    capacity,
+                    { "$add": [
+                        { "$ifNull": ["$tokens", capacity] },
+                        { "$multiply": [
+                            { "$subtract": [now, { "$ifNull": ["$updated_at", now] }] },
+                            refill_per_ms,
+                        ] },
+                    ] },
+                ] },
+                "updated_at": now,
+            } },
+            doc! { "$set": {
+                "allowed": { "$gte": ["$tokens", 1.0] },
+                "tokens": { "$cond": [
+                    { "$gte": ["$tokens", 1.0] },
+                    { "$subtract": ["$tokens", 1.0] },
+                    "$tokens",
+                ] },
+            } },
+        ];
+
+        let bucket = self
+            .collection
+            .find_one_and_update(doc! { "_id": key }, pipeline)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .unwrap_or_default();
+
+        if bucket.get_bool("allowed").unwrap_or(true) {
+            Ok(RateDecision::Allowed)
+        } else {
+            let tokens = bucket.get_f64("tokens").unwrap_or(0.0);
+            Ok(RateDecision::Limited(limit.retry_after(tokens)))
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+use super::models::{FieldChange, Task};
+
+/// Fields whose changes are recorded in the task history.
+const TRACKED_FIELDS: [&str; 13] = [
+    "title",
+    "description",
+    "start_date",
+    "end_date",
+    "status",
+    "category_id",
+    "goal_id",
+    "assignee_id",
+    "notification",
+    "recurrence",
+    "occurrence_overrides",
+    "derive_status",
+    "subtasks",
+];
+
+/// Tracked fields that differ between two versions of a task. A missing version stands for a
+/// task that does not exist yet (creation) or anymore (deletion), so every set field is listed.
+pub fn changes(before: Option<&Task>, after: Option<&Task>) -> Vec<FieldChange> {
+    let before = before.map(fields).unwrap_or_default();
+    let after = after.map(fields).unwrap_or_default();
+
+    TRACKED_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let old = before.get(*field).cloned().unwrap_or(Value::Null);
+            let new = after.get(*field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: field.to_string(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+fn fields(task: &Task) -> Map<String, Value> {
+    let Ok(Value::Object(mut fields)) = serde_json::to_value(task) else {
+        return Map::new();
+    };
+    // Only the reminder settings matter; the rest is bookkeeping of the scheduler.
+    if let Some(Value::Object(notification)) = fields.get_mut("notification") {
+        notification.retain(|key, _| key == "time_unit" || key == "time_value");
+    }
+    fields
+        .into_iter()
+        .map(|(key, value)| (key, plain_values(value)))
+        .collect()
+}
+
+/// Turns the extended JSON of an `ObjectId` (`{"$oid": "..."}`) into its hex string, and
+/// that of a date (`{"$date": {"$numberLong": "..."}}`) into the RFC 3339 string the API uses.
+fn plain_values(value: Value) -> Value {
+    match value {
+        Value::Object(mut map) if map.len() == 1 && map.contains_key("$oid") => {
+            map.remove("$oid").unwrap_or(Value::Null)
+        }
+        Value::Object(map) if map.len() == 1 && map.contains_key("$date") => {
+            let date = map["$date"]["$numberLong"]
+                .as_str()
+                .and_then(|millis| millis.parse().ok())
+                .and_then(DateTime::from_timestamp_millis);
+            match date {
+                Some(date) => serde_json::to_value(date).unwrap_or(Value::Null),
+                None => Value::Object(map),
+            }
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, plain_values(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
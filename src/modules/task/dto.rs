@@ -1,11 +1,20 @@
+use crate::helpers::{
+    nullable_helper::deserialize_nullable,
+    pagination_helper::SortDirection,
+    time_zone_helper::{to_zone, DateTimeInput},
+};
 use crate::modules::{category::dto::CategoryResponse, notification::models::TimeUnit};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use super::models::Status;
+use super::models::{
+    ActivityAction, FieldChange, RecurrenceRule, Status, Subtask, Task, TaskActivity,
+    TaskComment, TaskSortField, TimelineEntry,
+};
 
 #[derive(Deserialize, Validate)]
 pub struct CreateTaskRequest {
@@ -14,15 +23,22 @@ pub struct CreateTaskRequest {
     #[validate(length(min = 1, max = 100))]
     pub description: String,
     #[allow(dead_code)]
-    pub start_date: DateTime<Utc>,
+    pub start_date: DateTimeInput,
     #[allow(dead_code)]
-    pub end_date: DateTime<Utc>,
+    pub end_date: DateTimeInput,
     #[allow(dead_code)]
     pub status: Status,
     #[allow(dead_code)]
     pub category_id: ObjectId,
+    pub goal_id: Option<ObjectId>,
+    pub workspace_id: Option<ObjectId>,
+    pub assignee_id: Option<ObjectId>,
     pub notification_time_unit: Option<TimeUnit>,
     pub notification_time_value: Option<u16>,
+    #[validate]
+    pub recurrence: Option<RecurrenceRule>,
+    #[serde(default)]
+    pub derive_status: bool,
 }
 
 #[derive(Deserialize, Validate)]
@@ -32,15 +48,25 @@ pub struct UpdateTaskRequest {
     #[validate(length(min = 1, max = 100))]
     pub description: Option<String>,
     #[allow(dead_code)]
-    pub start_date: Option<DateTime<Utc>>,
+    pub start_date: Option<DateTimeInput>,
     #[allow(dead_code)]
-    pub end_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTimeInput>,
     #[allow(dead_code)]
     pub status: Option<Status>,
     #[allow(dead_code)]
     pub category_id: Option<ObjectId>,
+    // `null` unlinks the task from its goal
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub goal_id: Option<Option<ObjectId>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
     pub notification_time_unit: Option<Option<TimeUnit>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
     pub notification_time_value: Option<Option<u16>>,
+    // `null` removes the rule and its occurrence overrides
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[validate]
+    pub recurrence: Option<Option<RecurrenceRule>>,
+    pub derive_status: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -48,10 +74,188 @@ pub struct TaskResponse {
     pub _id: String,
     pub title: String,
     pub description: String,
-    pub start_date: DateTime<Utc>,
-    pub end_date: DateTime<Utc>,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: DateTime<FixedOffset>,
     pub status: Status,
     pub category: Option<CategoryResponse>,
+    pub goal_id: Option<String>,
+    pub workspace_id: Option<String>,
+    pub assignee_id: Option<String>,
     pub notification_time_unit: Option<TimeUnit>,
     pub notification_time_value: Option<u16>,
+    pub recurrence: Option<RecurrenceRule>,
+    pub subtasks: Vec<SubtaskResponse>,
+    pub derive_status: bool,
+}
+
+impl TaskResponse {
+    pub fn new(task: Task, category: Option<CategoryResponse>, time_zone: Option<&Tz>) -> Self {
+        TaskResponse {
+            _id: task.id.unwrap().to_string(),
+            title: task.title,
+            description: task.description,
+            start_date: to_zone(task.start_date, time_zone),
+            end_date: to_zone(task.end_date, time_zone),
+            status: task.status,
+            category,
+            goal_id: task.goal_id.map(|goal_id| goal_id.to_string()),
+            workspace_id: task.workspace_id.map(|workspace_id| workspace_id.to_string()),
+            assignee_id: task.assignee_id.map(|assignee_id| assignee_id.to_string()),
+            notification_time_unit: task.notification.as_ref().map(|n| n.time_unit.clone()),
+            notification_time_value: task.notification.as_ref().map(|n| n.time_value),
+            recurrence: task.recurrence,
+            subtasks: task.subtasks.into_iter().map(SubtaskResponse::from).collect(),
+            derive_status: task.derive_status,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssignTaskRequest {
+    pub assignee_id: Option<ObjectId>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateSubtaskRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub title: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateSubtaskRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub title: Option<String>,
+    pub done: Option<bool>,
+    pub position: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct SubtaskResponse {
+    pub _id: String,
+    pub title: String,
+    pub done: bool,
+    pub position: u32,
+}
+
+impl From<Subtask> for SubtaskResponse {
+    fn from(subtask: Subtask) -> Self {
+        SubtaskResponse {
+            _id: subtask.id.to_string(),
+            title: subtask.title,
+            done: subtask.done,
+            position: subtask.position,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+pub struct TaskListQuery {
+    pub status: Option<Status>,
+    pub category_id: Option<ObjectId>,
+    pub workspace_id: Option<ObjectId>,
+    pub assignee_id: Option<ObjectId>,
+    pub start_from: Option<DateTime<Utc>>,
+    pub start_to: Option<DateTime<Utc>>,
+    pub end_from: Option<DateTime<Utc>>,
+    pub end_to: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 100))]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub sort: TaskSortField,
+    #[serde(default)]
+    pub direction: SortDirection,
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub local: bool,
+}
+
+#[derive(Deserialize)]
+pub struct OccurrencesQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(default)]
+    pub local: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateOccurrenceRequest {
+    pub occurrence_start: DateTime<Utc>,
+    pub status: Option<Status>,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+#[derive(Serialize)]
+pub struct TaskOccurrenceResponse {
+    pub task_id: String,
+    pub title: String,
+    pub description: String,
+    pub start_date: DateTime<FixedOffset>,
+    pub end_date: DateTime<FixedOffset>,
+    pub status: Status,
+    pub recurring: bool,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateCommentRequest {
+    #[validate(length(min = 1, max = 1000))]
+    pub body: String,
+}
+
+#[derive(Serialize)]
+pub struct CommentResponse {
+    pub _id: String,
+    pub user_id: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TaskComment> for CommentResponse {
+    fn from(comment: TaskComment) -> Self {
+        CommentResponse {
+            _id: comment.id.unwrap().to_string(),
+            user_id: comment.user_id.to_string(),
+            body: comment.body,
+            created_at: comment.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ActivityResponse {
+    pub _id: String,
+    pub user_id: Option<String>, // None once the author deleted their account
+    pub action: ActivityAction,
+    pub changes: Vec<FieldChange>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<TaskActivity> for ActivityResponse {
+    fn from(activity: TaskActivity) -> Self {
+        ActivityResponse {
+            _id: activity.id.unwrap().to_string(),
+            user_id: activity.user_id.map(|id| id.to_string()),
+            action: activity.action,
+            changes: activity.changes,
+            created_at: activity.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TimelineEntryResponse {
+    Activity(ActivityResponse),
+    Comment(CommentResponse),
+}
+
+impl From<TimelineEntry> for TimelineEntryResponse {
+    fn from(entry: TimelineEntry) -> Self {
+        match entry {
+            TimelineEntry::Activity(activity) => TimelineEntryResponse::Activity(activity.into()),
+            TimelineEntry::Comment(comment) => TimelineEntryResponse::Comment(comment.into()),
+        }
+    }
 }
@@ -1,49 +1,119 @@
 use crate::{
-    helpers::api_response::ApiResponse,
+    helpers::{
+        api_response::ApiResponse,
+        time_zone_helper::to_zone,
+    },
     modules::auth::{self, dto::AuthState},
-    modules::category::{dto::CategoryResponse, repository::CategoryRepository},
+    modules::category::dto::CategoryResponse,
+    modules::goal::{handlers::goal_service, service::GoalServiceError},
+    modules::user::extractors::UserTimeZone,
+    modules::workspace::{
+        dto::WorkspaceQuery,
+        handlers::{access_error_response, workspace_access},
+        models::{Access, Scope},
+    },
     AppState,
 };
 
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     middleware,
-    response::IntoResponse,
-    routing::{get, post, put},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
     Extension, Router,
 };
 use mongodb::bson::oid::ObjectId;
 use std::sync::Arc;
 use validator::Validate;
 
-use super::dto::{CreateTaskRequest, TaskResponse, UpdateTaskRequest};
-use super::repository::TaskRepository;
+use super::dto::{
+    AssignTaskRequest, CommentResponse, CreateCommentRequest, CreateSubtaskRequest,
+    CreateTaskRequest, TimelineEntryResponse, OccurrencesQuery, SubtaskResponse, TaskListQuery,
+    TaskOccurrenceResponse, TaskResponse, UpdateOccurrenceRequest, UpdateSubtaskRequest,
+    UpdateTaskRequest,
+};
 use super::service::{TaskService, TaskServiceError};
 
+pub fn task_service(state: &AppState) -> TaskService {
+    TaskService::new(
+        state.repositories.tasks.clone(),
+        state.repositories.categories.clone(),
+        state.repositories.task_comments.clone(),
+        state.repositories.task_activity.clone(),
+        goal_service(state),
+        workspace_access(state),
+    )
+}
+
+/// Checks the goal is readable by the user and belongs to the same scope as the task.
+async fn ensure_goal_access(
+    state: &AppState,
+    user_id: &ObjectId,
+    goal_id: Option<ObjectId>,
+    scope: &Scope,
+) -> Result<(), Response> {
+    let Some(goal_id) = goal_id else {
+        return Ok(());
+    };
+
+    match goal_service(state).get_user_goal(*user_id, goal_id).await {
+        Ok(goal) if goal.scope() == *scope => Ok(()),
+        Ok(_) => Err(ApiResponse::unprocessable_entity(
+            GoalServiceError::GoalNotFound.to_string().as_str(),
+            None::<()>,
+        )
+        .into_response()),
+        Err(GoalServiceError::Workspace(err)) => Err(access_error_response(err)),
+        Err(err @ GoalServiceError::GoalNotFound) => {
+            Err(ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response())
+        }
+        Err(err @ GoalServiceError::GoalForbidden) => {
+            Err(ApiResponse::forbidden(err.to_string().as_str()).into_response())
+        }
+        Err(err) => {
+            Err(ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response())
+        }
+    }
+}
+
+/// Looks up the scope of a task the user can read, for the checks around a change.
+async fn task_scope(
+    state: &AppState,
+    user_id: &ObjectId,
+    task_id: &ObjectId,
+) -> Result<Scope, Response> {
+    task_service(state)
+        .get_user_task(user_id, task_id)
+        .await
+        .map(|task| task.scope())
+        .map_err(task_error_response)
+}
+
 async fn create_task(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
     Json(payload): Json<CreateTaskRequest>,
 ) -> impl IntoResponse {
     if let Err(errors) = payload.validate() {
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    let repository = TaskRepository::new(&state.mongodb);
-    let service = TaskService::new(repository);
+    let scope = Scope::new(user.id, payload.workspace_id);
+    if let Err(response) = ensure_goal_access(&state, &user.id, payload.goal_id, &scope).await {
+        return response;
+    }
 
-    match service.create_task_for_user(&user.id, payload).await {
-        Ok(id) => {
-            ApiResponse::created("Task created successfully", Some(id.to_string())).into_response()
-        }
+    let service = task_service(&state);
+
+    match service.create_task_for_user(&user.id, &time_zone, payload).await {
+        Ok(id) => ApiResponse::created("Task created successfully", Some(id.to_string())).into_response(),
         Err(TaskServiceError::TaskAlreadyExists) => ApiResponse::unprocessable_entity(
             TaskServiceError::TaskAlreadyExists.to_string().as_str(),
             None::<()>,
         )
         .into_response(),
-        Err(err) => {
-            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
-        }
+        Err(err) => task_error_response(err),
     }
 }
 
@@ -51,60 +121,76 @@ async fn update_task(
     Path(task_id): Path<ObjectId>,
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
     Json(payload): Json<UpdateTaskRequest>,
 ) -> impl IntoResponse {
     if let Err(errors) = payload.validate() {
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    let repository = TaskRepository::new(&state.mongodb);
-    let service = TaskService::new(repository);
+    let scope = match task_scope(&state, &user.id, &task_id).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let goal_id = payload.goal_id.flatten();
+    if let Err(response) = ensure_goal_access(&state, &user.id, goal_id, &scope).await {
+        return response;
+    }
+
+    let service = task_service(&state);
 
     match service
         .update_user_task(
             &user.id,
+            &time_zone,
             &task_id,
             payload,
         )
         .await
     {
-        Ok(result) => {
-            Json(ApiResponse::ok("Task updated successfully", Some(result))).into_response()
-        }
-        Err(TaskServiceError::TaskNotFound) => ApiResponse::bad_request(
-            TaskServiceError::TaskNotFound.to_string().as_str(),
-            None::<()>,
-        )
-        .into_response(),
-        Err(TaskServiceError::TaskAlreadyExists) => ApiResponse::unprocessable_entity(
-            TaskServiceError::TaskAlreadyExists.to_string().as_str(),
-            None::<()>,
-        )
-        .into_response(),
-        Err(err) => {
-            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
-        }
+        Ok(result) => Json(ApiResponse::ok("Task updated successfully", Some(result))).into_response(),
+        Err(err) => task_error_response(err),
     }
 }
 
 async fn get_tasks(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    Query(query): Query<TaskListQuery>,
 ) -> impl IntoResponse {
-    let repository = TaskRepository::new(&state.mongodb);
-    let service = TaskService::new(repository);
+    if let Err(errors) = query.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
 
-    let category_repository = CategoryRepository::new(&state.mongodb);
+    let scope = match workspace_access(&state)
+        .resolve(&user.id, query.workspace_id, Access::Read)
+        .await
+    {
+        Ok(scope) => scope,
+        Err(err) => return access_error_response(err),
+    };
 
-    match service.get_all_user_tasks(&user.id).await {
-        Ok(tasks) => {
-            let mut response_tasks = Vec::new();
+    let time_zone = query.local.then_some(time_zone);
+
+    let service = task_service(&state);
 
-            let category_result = category_repository.get_all_user_categories(&user.id).await;
+    let category_repository = state.repositories.categories.clone();
+
+    match service.get_user_tasks_page(&scope, query).await {
+        Ok((tasks, next_cursor)) => {
+            let mut response_tasks = Vec::new();
 
-            let categories = match category_result {
-                Ok(cats) => cats,
-                Err(_) => Vec::new(),
+            let category_ids: Vec<ObjectId> = tasks.iter().map(|task| task.category_id).collect();
+            let categories = match category_repository
+                .get_user_categories_by_ids(&scope, &category_ids)
+                .await
+            {
+                Ok(categories) => categories,
+                Err(err) => {
+                    return ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>)
+                        .into_response()
+                }
             };
 
             for task in tasks {
@@ -117,25 +203,21 @@ async fn get_tasks(
                         color: category.color.clone(),
                     });
 
-                response_tasks.push(TaskResponse {
-                    _id: task.id.unwrap().to_string(),
-                    title: task.title,
-                    description: task.description,
-                    start_date: task.start_date,
-                    end_date: task.end_date,
-                    status: task.status,
-                    category: category_response,
-                    notification_time_unit: task.notification.as_ref().map(|n| n.time_unit.clone()),
-                    notification_time_value: task.notification.as_ref().map(|n| n.time_value),
-                });
+                response_tasks.push(TaskResponse::new(
+                    task,
+                    category_response,
+                    time_zone.as_ref(),
+                ));
             }
 
-            Json(ApiResponse::ok(
-                "Tasks retrieved successfully",
-                Some(response_tasks),
-            ))
-            .into_response()
+            ApiResponse::page("Tasks retrieved successfully", response_tasks, next_cursor)
+                .into_response()
         }
+        Err(TaskServiceError::InvalidCursor) => ApiResponse::bad_request(
+            TaskServiceError::InvalidCursor.to_string().as_str(),
+            None::<()>,
+        )
+        .into_response(),
         Err(err) => Json(ApiResponse::server_error(
             Some(&err.to_string()),
             None::<()>,
@@ -147,35 +229,194 @@ async fn get_tasks(
 async fn delete_task(
     Path(task_id): Path<ObjectId>,
     State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match task_service(&state).delete_user_task(&user.id, &task_id).await {
+        Ok(result) => Json(ApiResponse::ok("Task deleted successfully", Some(result))).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn get_task_occurrences(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    Query(query): Query<OccurrencesQuery>,
+    Query(workspace): Query<WorkspaceQuery>,
 ) -> impl IntoResponse {
-    let repository = TaskRepository::new(&state.mongodb);
-    let service = TaskService::new(repository);
+    if query.from > query.to {
+        return ApiResponse::bad_request("`from` must be before `to`", None::<()>).into_response();
+    }
 
-    match service.delete_user_task(&task_id).await {
-        Ok(result) => {
-            Json(ApiResponse::ok("Task deleted successfully", Some(result))).into_response()
+    let scope = match workspace_access(&state)
+        .resolve(&user.id, workspace.workspace_id, Access::Read)
+        .await
+    {
+        Ok(scope) => scope,
+        Err(err) => return access_error_response(err),
+    };
+
+    let render_zone = query.local.then_some(&time_zone);
+
+    match task_service(&state)
+        .get_user_task_occurrences(&scope, &time_zone, query.from, query.to)
+        .await
+    {
+        Ok(occurrences) => {
+            let response: Vec<_> = occurrences
+                .into_iter()
+                .map(|occurrence| TaskOccurrenceResponse {
+                    task_id: occurrence.task_id.to_string(),
+                    title: occurrence.title,
+                    description: occurrence.description,
+                    start_date: to_zone(occurrence.start_date, render_zone),
+                    end_date: to_zone(occurrence.end_date, render_zone),
+                    status: occurrence.status,
+                    recurring: occurrence.recurring,
+                })
+                .collect();
+
+            ApiResponse::ok("Task occurrences retrieved successfully", Some(response))
+                .into_response()
         }
-        Err(TaskServiceError::TaskNotFound) => ApiResponse::bad_request(
-            TaskServiceError::TaskNotFound.to_string().as_str(),
-            None::<()>,
-        )
-        .into_response(),
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
     }
 }
 
+async fn update_task_occurrence(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    UserTimeZone(time_zone): UserTimeZone,
+    Json(payload): Json<UpdateOccurrenceRequest>,
+) -> impl IntoResponse {
+    match task_service(&state)
+        .update_user_task_occurrence(&user.id, &time_zone, &task_id, payload)
+        .await
+    {
+        Ok(result) => ApiResponse::ok("Occurrence updated successfully", Some(result)).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+fn task_error_response(err: TaskServiceError) -> Response {
+    match err {
+        TaskServiceError::TaskNotFound
+        | TaskServiceError::SubtaskNotFound
+        | TaskServiceError::OccurrenceNotFound
+        | TaskServiceError::CommentNotFound => {
+            ApiResponse::not_found(err.to_string().as_str()).into_response()
+        }
+        TaskServiceError::TaskForbidden | TaskServiceError::CommentForbidden => {
+            ApiResponse::forbidden(err.to_string().as_str()).into_response()
+        }
+        TaskServiceError::TaskAlreadyExists
+        | TaskServiceError::CategoryNotFound
+        | TaskServiceError::GoalNotFound
+        | TaskServiceError::TaskNotRecurring
+        | TaskServiceError::TaskNotAssignable
+        | TaskServiceError::AssigneeNotMember => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        TaskServiceError::InvalidCursor => {
+            ApiResponse::bad_request(err.to_string().as_str(), None::<()>).into_response()
+        }
+        TaskServiceError::Workspace(err) => access_error_response(err),
+        err => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
+    }
+}
+
+async fn get_subtasks(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    let service = task_service(&state);
+
+    match service.get_user_task_subtasks(&user.id, &task_id).await {
+        Ok(subtasks) => {
+            let response: Vec<SubtaskResponse> =
+                subtasks.into_iter().map(SubtaskResponse::from).collect();
+            ApiResponse::ok("Subtasks retrieved successfully", Some(response)).into_response()
+        }
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn create_subtask(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(mut payload): Json<CreateSubtaskRequest>,
+) -> impl IntoResponse {
+    payload.title = payload.title.trim().to_string();
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    let service = task_service(&state);
+
+    match service.add_subtask(&user.id, &task_id, payload).await {
+        Ok(id) => ApiResponse::created("Subtask created successfully", Some(id.to_string()))
+            .into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn update_subtask(
+    Path((task_id, subtask_id)): Path<(ObjectId, ObjectId)>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(payload): Json<UpdateSubtaskRequest>,
+) -> impl IntoResponse {
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    let service = task_service(&state);
+
+    match service
+        .update_subtask(&user.id, &task_id, &subtask_id, payload)
+        .await
+    {
+        Ok(result) => ApiResponse::ok("Subtask updated successfully", Some(result)).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn delete_subtask(
+    Path((task_id, subtask_id)): Path<(ObjectId, ObjectId)>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    let service = task_service(&state);
+
+    match service.delete_subtask(&user.id, &task_id, &subtask_id).await {
+        Ok(result) => ApiResponse::ok("Subtask deleted successfully", Some(result)).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
 pub async fn get_task_stats(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthState>,
+    Query(workspace): Query<WorkspaceQuery>,
 ) -> impl IntoResponse {
-    // Inicializa o repositório e serviço
-    let repository = TaskRepository::new(&state.mongodb);
-    let service = TaskService::new(repository);
+    let scope = match workspace_access(&state)
+        .resolve(&user.id, workspace.workspace_id, Access::Read)
+        .await
+    {
+        Ok(scope) => scope,
+        Err(err) => return access_error_response(err),
+    };
+
+    // Inicializa o serviço
+    let service = task_service(&state);
 
     // Chama o serviço para obter as tarefas por categoria e status
-    match service.count_tasks_by_category_and_status(&user.id).await {
+    match service.count_tasks_by_category_and_status(&scope).await {
         Ok(task_stats) => Json(ApiResponse::ok(
             "Tasks by category and status retrieved successfully",
             Some(task_stats),
@@ -189,10 +430,114 @@ pub async fn get_task_stats(
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
+async fn assign_task(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(payload): Json<AssignTaskRequest>,
+) -> impl IntoResponse {
+    match task_service(&state)
+        .assign_task(&user.id, &task_id, payload.assignee_id)
+        .await
+    {
+        Ok(result) => ApiResponse::ok("Task assigned successfully", Some(result)).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn get_comments(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match task_service(&state).get_task_comments(&user.id, &task_id).await {
+        Ok(comments) => {
+            let response: Vec<CommentResponse> = comments.into_iter().map(Into::into).collect();
+            ApiResponse::ok("Comments retrieved successfully", Some(response)).into_response()
+        }
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn create_comment(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(mut payload): Json<CreateCommentRequest>,
+) -> impl IntoResponse {
+    payload.body = payload.body.trim().to_string();
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match task_service(&state)
+        .add_comment(&user.id, &task_id, payload)
+        .await
+    {
+        Ok(id) => {
+            ApiResponse::created("Comment created successfully", Some(id.to_string()))
+                .into_response()
+        }
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn delete_comment(
+    Path((task_id, comment_id)): Path<(ObjectId, ObjectId)>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match task_service(&state)
+        .delete_comment(&user.id, &task_id, &comment_id)
+        .await
+    {
+        Ok(result) => ApiResponse::ok("Comment deleted successfully", Some(result)).into_response(),
+        Err(err) => task_error_response(err),
+    }
+}
+
+async fn get_timeline(
+    Path(task_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match task_service(&state).get_task_timeline(&user.id, &task_id).await {
+        Ok(timeline) => {
+            let response: Vec<TimelineEntryResponse> =
+                timeline.into_iter().map(Into::into).collect();
+            ApiResponse::ok("Task activity retrieved successfully", Some(response)).into_response()
+        }
+        Err(err) => task_error_response(err),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
         .route("/v1/tasks", post(create_task).get(get_tasks))
         .route("/v1/tasks/:task_id", put(update_task).delete(delete_task))
         .route("/v1/tasks/categories", get(get_task_stats))
-        .layer(middleware::from_fn(auth::middlewares::authorize))
+        .route("/v1/tasks/occurrences", get(get_task_occurrences))
+        .route("/v1/tasks/:task_id/occurrences", put(update_task_occurrence))
+        .route("/v1/tasks/:task_id/assignee", put(assign_task))
+        .route(
+            "/v1/tasks/:task_id/comments",
+            get(get_comments).post(create_comment),
+        )
+        .route(
+            "/v1/tasks/:task_id/comments/:comment_id",
+            delete(delete_comment),
+        )
+        .route("/v1/tasks/:task_id/activity", get(get_timeline))
+        .route(
+            "/v1/tasks/:task_id/subtasks",
+            get(get_subtasks).post(create_subtask),
+        )
+        .route(
+            "/v1/tasks/:task_id/subtasks/:subtask_id",
+            put(update_subtask).delete(delete_subtask),
+        )
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
 }
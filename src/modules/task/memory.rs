@@ -0,0 +1,647 @@
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{oid::ObjectId, to_document, Bson};
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::{compare_bson, MemoryCollection};
+use crate::helpers::pagination_helper::{Cursor, SortDirection};
+use crate::modules::category::repository::CategoryRepository;
+use crate::modules::notification::models::Notification;
+use crate::modules::workspace::models::Scope;
+
+use super::models::{
+    OccurrenceOverride, RecurrenceRule, Status, Subtask, Task, TaskActivity,
+    TaskByCategoryAndStatus, TaskComment, TaskCountByGoal, TaskFilter, TaskSortField,
+};
+use super::repository::{TaskActivityRepository, TaskCommentRepository, TaskRepository};
+
+/// Tasks kept in memory. Categories are read through their own repository, which stands in
+/// for the `$lookup` of `count_tasks_by_status`.
+pub struct InMemoryTaskRepository {
+    tasks: MemoryCollection<Task>,
+    categories: Arc<dyn CategoryRepository>,
+}
+
+impl InMemoryTaskRepository {
+    pub fn new(categories: Arc<dyn CategoryRepository>) -> Self {
+        // Titles are unique per scope, like the `ensure_indexes` indexes.
+        let tasks = MemoryCollection::default()
+            .unique("user_title_unique", |task: &Task| {
+                Some(Bson::Array(vec![
+                    task.user_id.into(),
+                    task.workspace_id.into(),
+                    task.title.clone().into(),
+                ]))
+            })
+            .unique("workspace_title_unique", |task: &Task| {
+                let workspace_id = task.workspace_id?;
+                Some(Bson::Array(vec![workspace_id.into(), task.title.clone().into()]))
+            });
+        InMemoryTaskRepository { tasks, categories }
+    }
+}
+
+fn is_task(task: &Task, scope: &Scope, task_id: &ObjectId) -> bool {
+    task.id.as_ref() == Some(task_id) && task.scope() == *scope
+}
+
+fn within(date: DateTime<Utc>, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> bool {
+    from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+}
+
+fn has_unsent_notification(task: &Task) -> bool {
+    task.notification
+        .as_ref()
+        .is_some_and(|notification| !notification.sent)
+}
+
+#[async_trait]
+impl TaskRepository for InMemoryTaskRepository {
+    async fn create_task(&self, new_task: Task) -> Result<ObjectId, Error> {
+        self.tasks.insert(&new_task)
+    }
+
+    async fn update_task(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        title: Option<String>,
+        description: Option<String>,
+        start_date: Option<DateTime<Utc>>,
+        end_date: Option<DateTime<Utc>>,
+        status: Option<Status>,
+        category_id: Option<ObjectId>,
+        notification: Option<Option<Notification>>,
+    ) -> Result<bool, Error> {
+        if title.is_none()
+            && description.is_none()
+            && start_date.is_none()
+            && end_date.is_none()
+            && status.is_none()
+            && category_id.is_none()
+            && notification.is_none()
+        {
+            return Ok(false);
+        }
+
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| {
+                if let Some(title) = title {
+                    task.title = title;
+                }
+                if let Some(description) = description {
+                    task.description = description;
+                }
+                if let Some(start_date) = start_date {
+                    task.start_date = start_date;
+                }
+                if let Some(end_date) = end_date {
+                    task.end_date = end_date;
+                }
+                if let Some(status) = status {
+                    task.status = status;
+                }
+                if let Some(category_id) = category_id {
+                    task.category_id = category_id;
+                }
+                if let Some(notification) = notification {
+                    task.notification = notification;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_recurrence(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        recurrence: Option<RecurrenceRule>,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.recurrence = recurrence,
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_occurrence_overrides(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        overrides: &[OccurrenceOverride],
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.occurrence_overrides = overrides.to_vec(),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_goal(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        goal_id: Option<&ObjectId>,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.goal_id = goal_id.copied(),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_derive_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        derive_status: bool,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.derive_status = derive_status,
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        status: Status,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.status = status,
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn push_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask: &Subtask,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.subtasks.push(subtask.clone()),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn update_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+        title: Option<&str>,
+        done: Option<bool>,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| {
+                for subtask in task.subtasks.iter_mut() {
+                    if subtask.id == *subtask_id {
+                        if let Some(title) = title {
+                            subtask.title = title.to_string();
+                        }
+                        if let Some(done) = done {
+                            subtask.done = done;
+                        }
+                    }
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn pull_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.subtasks.retain(|subtask| subtask.id != *subtask_id),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_subtask_positions(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_ids: &[ObjectId],
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| {
+                for subtask in task.subtasks.iter_mut() {
+                    if let Some(position) = subtask_ids.iter().position(|id| *id == subtask.id) {
+                        subtask.position = position as u32;
+                    }
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn unlink_goal(&self, scope: &Scope, goal_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.tasks.update_many(
+            |task| task.scope() == *scope && task.goal_id.as_ref() == Some(goal_id),
+            |task| task.goal_id = None,
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn delete_task(&self, scope: &Scope, task_id: &ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .tasks
+            .delete_one(|task| is_task(task, scope, task_id))?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn delete_all_user_tasks(&self, scope: &Scope) -> Result<u64, Error> {
+        self.tasks.delete_many(|task| task.scope() == *scope)
+    }
+
+    async fn get_all_user_tasks(&self, scope: &Scope) -> Result<Vec<Task>, Error> {
+        self.tasks.find(|task| task.scope() == *scope)
+    }
+
+    async fn find_user_tasks(
+        &self,
+        scope: &Scope,
+        filter: &TaskFilter,
+        sort: TaskSortField,
+        direction: SortDirection,
+        cursor: Option<&Cursor>,
+        limit: Option<i64>,
+    ) -> Result<(Vec<Task>, Option<Cursor>), Error> {
+        let text = filter.text.as_ref().map(|text| text.to_lowercase());
+        let tasks = self.tasks.find(|task| {
+            task.scope() == *scope
+                && filter
+                    .status
+                    .as_ref()
+                    .is_none_or(|status| task.status == *status)
+                && filter
+                    .category_id
+                    .is_none_or(|category_id| task.category_id == category_id)
+                && filter
+                    .assignee_id
+                    .is_none_or(|assignee_id| task.assignee_id == Some(assignee_id))
+                && within(task.start_date, filter.start_from, filter.start_to)
+                && within(task.end_date, filter.end_from, filter.end_to)
+                && text.as_ref().is_none_or(|text| {
+                    task.title.to_lowercase().contains(text)
+                        || task.description.to_lowercase().contains(text)
+                })
+        })?;
+
+        // Sort on the stored value, as MongoDB does, so cursors work with either backend.
+        let mut rows = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let value = to_document(&task)?
+                .get(sort.as_str())
+                .cloned()
+                .unwrap_or(Bson::Null);
+            rows.push((value, task.id, task));
+        }
+        let order =
+            |value: &Bson, id: &Option<ObjectId>, other: &Bson, other_id: &Option<ObjectId>| {
+                let ordering = compare_bson(value, other).then_with(|| id.cmp(other_id));
+                match direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                }
+            };
+        rows.sort_by(|(a, a_id, _), (b, b_id, _)| order(a, a_id, b, b_id));
+        if let Some(cursor) = cursor {
+            let cursor_id = Some(cursor.id);
+            rows.retain(|(value, id, _)| {
+                order(value, id, &cursor.value, &cursor_id) == Ordering::Greater
+            });
+        }
+        let next_cursor = match limit {
+            Some(limit) if rows.len() as i64 > limit => {
+                rows.truncate(limit.max(0) as usize);
+                rows.last().and_then(|(value, id, _)| {
+                    id.map(|id| Cursor {
+                        sort: sort.as_str().to_string(),
+                        value: value.clone(),
+                        id,
+                    })
+                })
+            }
+            _ => None,
+        };
+
+        let tasks = rows.into_iter().map(|(_, _, task)| task).collect();
+        Ok((tasks, next_cursor))
+    }
+
+    async fn get_user_tasks_by_goal(
+        &self,
+        scope: &Scope,
+        goal_id: &ObjectId,
+    ) -> Result<Vec<Task>, Error> {
+        self.tasks
+            .find(|task| task.scope() == *scope && task.goal_id.as_ref() == Some(goal_id))
+    }
+
+    async fn count_tasks_by_goal(&self, scope: &Scope) -> Result<Vec<TaskCountByGoal>, Error> {
+        let tasks = self
+            .tasks
+            .find(|task| task.scope() == *scope && task.goal_id.is_some())?;
+
+        let mut result: Vec<TaskCountByGoal> = Vec::new();
+        for task in tasks {
+            let Some(goal_id) = task.goal_id else {
+                continue;
+            };
+            let completed = (task.status == Status::Executada) as i32;
+            match result.iter_mut().find(|count| count.goal_id == goal_id) {
+                Some(count) => {
+                    count.total += 1;
+                    count.completed += completed;
+                }
+                None => result.push(TaskCountByGoal {
+                    goal_id,
+                    total: 1,
+                    completed,
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_task_by_id(&self, task_id: &ObjectId) -> Result<Option<Task>, Error> {
+        self.tasks
+            .find_one(|task| task.id.as_ref() == Some(task_id))
+    }
+
+    async fn get_task_by_title(&self, scope: &Scope, title: &str) -> Result<Option<Task>, Error> {
+        self.tasks
+            .find_one(|task| task.scope() == *scope && task.title == title)
+    }
+
+    /// Tasks whose category no longer exists are left out, like the unmatched documents of
+    /// the `$unwind` that follows the `$lookup`.
+    async fn count_tasks_by_status(
+        &self,
+        scope: &Scope,
+    ) -> Result<Vec<TaskByCategoryAndStatus>, Error> {
+        let tasks = self.tasks.find(|task| task.scope() == *scope)?;
+
+        let mut titles: HashMap<ObjectId, Option<String>> = HashMap::new();
+        let mut result: Vec<TaskByCategoryAndStatus> = Vec::new();
+        for task in tasks {
+            let title = match titles.get(&task.category_id) {
+                Some(title) => title.clone(),
+                None => {
+                    let title = self
+                        .categories
+                        .get_category_by_id(&task.category_id)
+                        .await?
+                        .map(|category| category.title);
+                    titles.insert(task.category_id, title.clone());
+                    title
+                }
+            };
+            let Some(category) = title else {
+                continue;
+            };
+
+            let status = task.status.as_str();
+            match result
+                .iter_mut()
+                .find(|count| count.category == category && count.status == status)
+            {
+                Some(count) => count.count += 1,
+                None => result.push(TaskByCategoryAndStatus {
+                    category,
+                    status: status.to_string(),
+                    count: 1,
+                }),
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn count_all_tasks_by_status(&self) -> Result<Vec<(String, u64)>, Error> {
+        let mut result: Vec<(String, u64)> = Vec::new();
+        for task in self.tasks.find(|_| true)? {
+            match result
+                .iter_mut()
+                .find(|(status, _)| status == task.status.as_str())
+            {
+                Some((_, count)) => *count += 1,
+                None => result.push((task.status.as_str().to_string(), 1)),
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn set_assignee(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        assignee_id: Option<&ObjectId>,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| is_task(task, scope, task_id),
+            |task| task.assignee_id = assignee_id.copied(),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn unassign_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<u64, Error> {
+        let result = self.tasks.update_many(
+            |task| {
+                task.workspace_id.as_ref() == Some(workspace_id)
+                    && task.assignee_id.as_ref() == Some(user_id)
+            },
+            |task| task.assignee_id = None,
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn get_all_not_sent_notifications(
+        &self,
+        due_by: DateTime<Utc>,
+    ) -> Result<Vec<Task>, Error> {
+        self.tasks.find(|task| {
+            task.notification.as_ref().is_some_and(|notification| {
+                !notification.sent && notification.scheduled_time <= due_by
+            })
+        })
+    }
+
+    async fn mark_notification_as_sent(&self, task_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| task.id.as_ref() == Some(task_id) && has_unsent_notification(task),
+            |task| {
+                if let Some(notification) = task.notification.as_mut() {
+                    notification.sent = true;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn mark_notification_as_missed(&self, task_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| task.id.as_ref() == Some(task_id) && has_unsent_notification(task),
+            |task| {
+                if let Some(notification) = task.notification.as_mut() {
+                    notification.sent = true;
+                    notification.missed = true;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn record_failed_attempt(&self, task_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| task.id.as_ref() == Some(task_id) && has_unsent_notification(task),
+            |task| {
+                if let Some(notification) = task.notification.as_mut() {
+                    notification.attempts += 1;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn reschedule_notification(
+        &self,
+        task_id: &ObjectId,
+        scheduled_time: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let result = self.tasks.update_one(
+            |task| task.id.as_ref() == Some(task_id) && has_unsent_notification(task),
+            |task| {
+                if let Some(notification) = task.notification.as_mut() {
+                    notification.scheduled_time = scheduled_time;
+                    notification.viewed = false;
+                    notification.attempts = 0;
+                }
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn get_all_with_notifications(&self, user_id: &ObjectId) -> Result<Vec<Task>, Error> {
+        self.tasks
+            .find(|task| task.notification.is_some() && task.recipient_id() == *user_id)
+    }
+}
+
+/// Comments kept in memory, in insertion order, which is oldest first.
+#[derive(Default)]
+pub struct InMemoryTaskCommentRepository {
+    comments: MemoryCollection<TaskComment>,
+}
+
+#[async_trait]
+impl TaskCommentRepository for InMemoryTaskCommentRepository {
+    async fn create_comment(&self, comment: TaskComment) -> Result<ObjectId, Error> {
+        self.comments.insert(&comment)
+    }
+
+    async fn find_comment(
+        &self,
+        task_id: &ObjectId,
+        comment_id: &ObjectId,
+    ) -> Result<Option<TaskComment>, Error> {
+        self.comments.find_one(|comment| {
+            comment.id.as_ref() == Some(comment_id) && comment.task_id == *task_id
+        })
+    }
+
+    async fn get_task_comments(&self, task_id: &ObjectId) -> Result<Vec<TaskComment>, Error> {
+        self.comments.find(|comment| comment.task_id == *task_id)
+    }
+
+    async fn delete_comment(&self, comment_id: &ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .comments
+            .delete_one(|comment| comment.id.as_ref() == Some(comment_id))?;
+        Ok(deleted > 0)
+    }
+
+    async fn delete_task_comments(&self, task_id: &ObjectId) -> Result<u64, Error> {
+        self.comments.delete_many(|comment| comment.task_id == *task_id)
+    }
+
+    async fn delete_tasks_comments(&self, task_ids: &[ObjectId]) -> Result<u64, Error> {
+        self.comments
+            .delete_many(|comment| task_ids.contains(&comment.task_id))
+    }
+
+    async fn delete_all_user_comments(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        self.comments.delete_many(|comment| comment.user_id == *user_id)
+    }
+}
+
+/// Task history kept in memory, in insertion order, which is oldest first.
+#[derive(Default)]
+pub struct InMemoryTaskActivityRepository {
+    activity: MemoryCollection<TaskActivity>,
+}
+
+#[async_trait]
+impl TaskActivityRepository for InMemoryTaskActivityRepository {
+    async fn record(&self, activity: TaskActivity) -> Result<ObjectId, Error> {
+        self.activity.insert(&activity)
+    }
+
+    async fn get_task_activity(&self, task_id: &ObjectId) -> Result<Vec<TaskActivity>, Error> {
+        self.activity.find(|activity| activity.task_id == *task_id)
+    }
+
+    async fn delete_tasks_activity(&self, task_ids: &[ObjectId]) -> Result<u64, Error> {
+        self.activity
+            .delete_many(|activity| task_ids.contains(&activity.task_id))
+    }
+
+    async fn anonymize_user_activity(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.activity.update_many(
+            |activity| activity.user_id.as_ref() == Some(user_id),
+            |activity| activity.user_id = None,
+        )?;
+        Ok(result.modified)
+    }
+}
@@ -1,6 +1,10 @@
+pub mod activity;
 pub mod dto;
 pub mod handlers;
+#[cfg(test)]
+pub mod memory;
 pub mod models;
+pub mod recurrence;
 pub mod repository;
 pub mod service;
 
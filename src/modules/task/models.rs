@@ -1,9 +1,15 @@
+use crate::helpers::date_helper::{
+    deserialize_bson_datetime, deserialize_option_bson_datetime, serialize_bson_datetime,
+    serialize_option_bson_datetime,
+};
 use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
-use chrono::{DateTime, Utc};
+use crate::modules::workspace::models::Scope;
+use chrono::{DateTime, Utc, Weekday};
 use mongodb::bson::oid::ObjectId;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Status {
     #[serde(rename = "EXECUTADA")]
     Executada,
@@ -11,6 +17,8 @@ pub enum Status {
     ParcialmenteExecutada,
     #[serde(rename = "ADIADA")]
     Adiada,
+    #[serde(rename = "PENDENTE")]
+    Pendente, // Not due yet
 }
 
 impl Status {
@@ -19,6 +27,7 @@ impl Status {
             Status::Executada => "EXECUTADA",
             Status::ParcialmenteExecutada => "PARCIALMENTE_EXECUTADA",
             Status::Adiada => "ADIADA",
+            Status::Pendente => "PENDENTE",
         }
     }
 }
@@ -34,12 +43,220 @@ pub struct Task {
     pub id: Option<ObjectId>,
     pub title: String,
     pub description: String,
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
     pub start_date: DateTime<Utc>,
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
     pub end_date: DateTime<Utc>,
     pub status: Status,
-    pub user_id: ObjectId,
+    pub user_id: ObjectId, // Creator
+    #[serde(default)]
+    pub workspace_id: Option<ObjectId>,
+    #[serde(default)]
+    pub assignee_id: Option<ObjectId>, // Workspace member responsible for the task
     pub category_id: ObjectId,
+    #[serde(default)]
+    pub goal_id: Option<ObjectId>,
     pub notification: Option<crate::modules::notification::models::Notification>,
+    #[serde(
+        default,
+        serialize_with = "serialize_stored_recurrence",
+        deserialize_with = "deserialize_stored_recurrence"
+    )]
+    pub recurrence: Option<RecurrenceRule>,
+    #[serde(default)]
+    pub occurrence_overrides: Vec<OccurrenceOverride>,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    #[serde(default)]
+    pub derive_status: bool, // Status follows checklist completion
+}
+
+impl Task {
+    pub fn scope(&self) -> Scope {
+        Scope::new(self.user_id, self.workspace_id)
+    }
+
+    /// Who gets the task's reminders: the assignee, or else its creator.
+    pub fn recipient_id(&self) -> ObjectId {
+        self.assignee_id.unwrap_or(self.user_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtask {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub title: String,
+    pub done: bool,
+    pub position: u32,
+}
+
+impl Subtask {
+    /// Status implied by a checklist: `Pendente` until something is done, or `None` for an
+    /// empty checklist, which leaves the status as set by hand.
+    pub fn derived_status(subtasks: &[Subtask]) -> Option<Status> {
+        let done = subtasks.iter().filter(|subtask| subtask.done).count();
+        match done {
+            _ if subtasks.is_empty() => None,
+            0 => Some(Status::Pendente),
+            done if done == subtasks.len() => Some(Status::Executada),
+            _ => Some(Status::ParcialmenteExecutada),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Frequency {
+    #[serde(rename = "DAILY")]
+    Daily,
+    #[serde(rename = "WEEKLY")]
+    Weekly,
+    #[serde(rename = "MONTHLY")]
+    Monthly,
+}
+
+fn default_interval() -> u16 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    #[serde(default = "default_interval")]
+    #[validate(range(min = 1, max = 366))]
+    pub interval: u16,
+    #[serde(default)]
+    pub by_weekday: Vec<Weekday>, // Only used by WEEKLY rules
+    #[validate(range(min = 1))]
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// A `RecurrenceRule` as stored on a task, with `until` as a BSON date. The rule itself is
+/// also the API shape, where `until` stays an RFC 3339 string.
+#[derive(Serialize, Deserialize)]
+pub struct StoredRecurrenceRule {
+    frequency: Frequency,
+    #[serde(default = "default_interval")]
+    interval: u16,
+    #[serde(default)]
+    by_weekday: Vec<Weekday>,
+    count: Option<u32>,
+    #[serde(
+        default,
+        serialize_with = "serialize_option_bson_datetime",
+        deserialize_with = "deserialize_option_bson_datetime"
+    )]
+    until: Option<DateTime<Utc>>,
+}
+
+impl From<RecurrenceRule> for StoredRecurrenceRule {
+    fn from(rule: RecurrenceRule) -> Self {
+        StoredRecurrenceRule {
+            frequency: rule.frequency,
+            interval: rule.interval,
+            by_weekday: rule.by_weekday,
+            count: rule.count,
+            until: rule.until,
+        }
+    }
+}
+
+impl From<StoredRecurrenceRule> for RecurrenceRule {
+    fn from(rule: StoredRecurrenceRule) -> Self {
+        RecurrenceRule {
+            frequency: rule.frequency,
+            interval: rule.interval,
+            by_weekday: rule.by_weekday,
+            count: rule.count,
+            until: rule.until,
+        }
+    }
+}
+
+fn serialize_stored_recurrence<S>(
+    rule: &Option<RecurrenceRule>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    rule.clone()
+        .map(StoredRecurrenceRule::from)
+        .serialize(serializer)
+}
+
+fn deserialize_stored_recurrence<'de, D>(
+    deserializer: D,
+) -> Result<Option<RecurrenceRule>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<StoredRecurrenceRule>::deserialize(deserializer)?.map(RecurrenceRule::from))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OccurrenceOverride {
+    #[serde(
+        serialize_with = "serialize_bson_datetime",
+        deserialize_with = "deserialize_bson_datetime"
+    )]
+    pub occurrence_start: DateTime<Utc>,
+    pub status: Option<Status>,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum TaskSortField {
+    #[serde(rename = "title")]
+    Title,
+    #[default]
+    #[serde(rename = "start_date")]
+    StartDate,
+    #[serde(rename = "end_date")]
+    EndDate,
+    #[serde(rename = "status")]
+    Status,
+}
+
+impl TaskSortField {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskSortField::Title => "title",
+            TaskSortField::StartDate => "start_date",
+            TaskSortField::EndDate => "end_date",
+            TaskSortField::Status => "status",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TaskFilter {
+    pub status: Option<Status>,
+    pub category_id: Option<ObjectId>,
+    pub assignee_id: Option<ObjectId>,
+    pub start_from: Option<DateTime<Utc>>,
+    pub start_to: Option<DateTime<Utc>>,
+    pub end_from: Option<DateTime<Utc>>,
+    pub end_to: Option<DateTime<Utc>>,
+    pub text: Option<String>,
+}
+
+pub struct TaskOccurrence {
+    pub task_id: ObjectId,
+    pub title: String,
+    pub description: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub status: Status,
+    pub recurring: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -49,10 +266,94 @@ pub struct TaskByCategoryAndStatus {
     pub count: i32,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct TaskCountByGoal {
+    pub goal_id: ObjectId,
+    pub total: i32,
+    pub completed: i32,
+}
+
+impl TaskCountByGoal {
+    /// Percentage (0-100) of linked tasks that are `Executada`.
+    pub fn progress(&self) -> u8 {
+        if self.total <= 0 {
+            return 0;
+        }
+        (self.completed * 100 / self.total) as u8
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct TaskStatsByCategory {
     pub category: String,
     pub completed_count: i32,
     pub postponed_count: i32,
     pub partially_completed_count: i32,
+    pub pending_count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskComment {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub task_id: ObjectId,
+    pub user_id: ObjectId, // Author
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActivityAction {
+    #[serde(rename = "CREATED")]
+    Created,
+    #[serde(rename = "UPDATED")]
+    Updated,
+    #[serde(rename = "DELETED")]
+    Deleted,
+}
+
+/// A field that changed, with its values serialized the way the API returns them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// Entry of the append-only task history. It outlives the task it describes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskActivity {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub task_id: ObjectId,
+    pub user_id: Option<ObjectId>, // Who made the change; None once their account is deleted
+    pub action: ActivityAction,
+    #[serde(default)]
+    pub changes: Vec<FieldChange>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What happened to a task, in the order it happened.
+pub enum TimelineEntry {
+    Activity(TaskActivity),
+    Comment(TaskComment),
+}
+
+impl TimelineEntry {
+    pub fn created_at(&self) -> DateTime<Utc> {
+        match self {
+            TimelineEntry::Activity(activity) => activity.created_at,
+            TimelineEntry::Comment(comment) => comment.created_at,
+        }
+    }
 }
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+use crate::helpers::time_zone_helper::local_to_utc;
+
+use super::models::{Frequency, RecurrenceRule};
+
+// Guards against rules that can never produce a date in range (e.g. the 31st every 2 months).
+const MAX_ITERATIONS: usize = 10_000;
+
+/// Expands the start dates of every occurrence in `[from, to]`.
+///
+/// Occurrences keep the wall-clock time of `start` in the user's time zone, so a daily 09:00
+/// task stays at 09:00 across DST changes. `count` is applied from the first occurrence, not from `from`.
+pub fn expand(
+    start: DateTime<Utc>,
+    rule: &RecurrenceRule,
+    time_zone: &Tz,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    let local_start = start.with_timezone(time_zone).naive_local();
+    let first_date = local_start.date();
+    let time = local_start.time();
+    let interval = rule.interval.max(1) as u64;
+
+    let mut occurrences = Vec::new();
+    let mut produced: u32 = 0;
+
+    for step in 0..MAX_ITERATIONS as u64 {
+        for date in candidate_dates(rule, first_date, step * interval) {
+            if date < first_date {
+                continue;
+            }
+
+            let occurrence = local_to_utc(date.and_time(time), time_zone);
+            if occurrence > to || rule.until.is_some_and(|until| occurrence > until) {
+                return occurrences;
+            }
+            if rule.count.is_some_and(|count| produced >= count) {
+                return occurrences;
+            }
+
+            produced += 1;
+            if occurrence >= from {
+                occurrences.push(occurrence);
+            }
+        }
+    }
+
+    occurrences
+}
+
+/// Returns the first occurrence strictly after `after`, if the rule has one.
+pub fn next_after(
+    start: DateTime<Utc>,
+    rule: &RecurrenceRule,
+    time_zone: &Tz,
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let horizon = after + Duration::days(366 * rule.interval.max(1) as i64);
+    expand(
+        start,
+        rule,
+        time_zone,
+        after + Duration::seconds(1),
+        horizon,
+    )
+    .into_iter()
+    .next()
+}
+
+fn candidate_dates(rule: &RecurrenceRule, first_date: NaiveDate, offset: u64) -> Vec<NaiveDate> {
+    match rule.frequency {
+        Frequency::Daily => vec![first_date + Duration::days(offset as i64)],
+        Frequency::Weekly => {
+            let week_start = first_date
+                - Duration::days(first_date.weekday().num_days_from_monday() as i64)
+                + Duration::weeks(offset as i64);
+            let mut weekdays = if rule.by_weekday.is_empty() {
+                vec![first_date.weekday()]
+            } else {
+                rule.by_weekday.clone()
+            };
+            weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+            weekdays.dedup();
+
+            weekdays
+                .into_iter()
+                .map(|weekday| week_start + Duration::days(weekday.num_days_from_monday() as i64))
+                .collect()
+        }
+        Frequency::Monthly => {
+            // Months without the start day (e.g. the 31st) are skipped, as in RFC 5545.
+            let month = first_date
+                .with_day(1)
+                .and_then(|date| date.checked_add_months(Months::new(offset as u32)));
+            month
+                .and_then(|month| month.with_day(first_date.day()))
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Tz;
+
+    use crate::modules::task::models::Frequency;
+
+    use super::*;
+
+    #[test]
+    fn daily_occurrences_keep_their_local_time_across_dst() {
+        let tz = Tz::America__New_York;
+        let start = tz
+            .with_ymd_and_hms(2024, 3, 8, 9, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let rule = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            by_weekday: Vec::new(),
+            count: None,
+            until: None,
+        };
+
+        let occurrences = expand(start, &rule, &tz, start, start + Duration::days(3));
+
+        let local: Vec<_> = occurrences
+            .iter()
+            .map(|date| date.with_timezone(&tz).naive_local())
+            .collect();
+        let expected: Vec<_> = (8..=11)
+            .map(|day| {
+                NaiveDate::from_ymd_opt(2024, 3, day)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(local, expected);
+        // The UTC offset changes on the 10th, so the occurrences are 23 hours apart there.
+        assert_eq!(occurrences[2] - occurrences[1], Duration::hours(23));
+    }
+}
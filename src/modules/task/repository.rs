@@ -1,32 +1,213 @@
 use crate::modules::notification::models::Notification;
+use crate::helpers::date_helper::to_bson_datetime;
+use crate::helpers::pagination_helper::{escape_regex, Cursor, SortDirection};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
-use mongodb::bson::Bson;
+use mongodb::bson::{from_document, to_bson, Bson, Document};
 use mongodb::error::Error;
 use mongodb::{bson::doc, Collection, Database};
 
-use crate::modules::category;
+use crate::modules::workspace::models::Scope;
 
-use super::models::{Status, Task, TaskByCategoryAndStatus};
+use super::models::{
+    OccurrenceOverride, RecurrenceRule, Status, StoredRecurrenceRule, Subtask, Task,
+    TaskActivity, TaskByCategoryAndStatus, TaskComment, TaskCountByGoal, TaskFilter, TaskSortField,
+};
 
-pub struct TaskRepository {
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn create_task(&self, new_task: Task) -> Result<ObjectId, Error>;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn update_task(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        title: Option<String>,
+        description: Option<String>,
+        start_date: Option<DateTime<Utc>>,
+        end_date: Option<DateTime<Utc>>,
+        status: Option<Status>,
+        category_id: Option<ObjectId>,
+        notification: Option<Option<Notification>>,
+    ) -> Result<bool, Error>;
+
+    async fn set_recurrence(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        recurrence: Option<RecurrenceRule>,
+    ) -> Result<bool, Error>;
+
+    async fn set_occurrence_overrides(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        overrides: &[OccurrenceOverride],
+    ) -> Result<bool, Error>;
+
+    /// Links the task to a goal, or unlinks it with `None`.
+    async fn set_goal(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        goal_id: Option<&ObjectId>,
+    ) -> Result<bool, Error>;
+
+    async fn set_derive_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        derive_status: bool,
+    ) -> Result<bool, Error>;
+
+    async fn set_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        status: Status,
+    ) -> Result<bool, Error>;
+
+    // Checklist writes touch single subtasks, so concurrent edits to others are kept.
+    async fn push_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask: &Subtask,
+    ) -> Result<bool, Error>;
+
+    async fn update_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+        title: Option<&str>,
+        done: Option<bool>,
+    ) -> Result<bool, Error>;
+
+    async fn pull_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+    ) -> Result<bool, Error>;
+
+    /// Numbers the given subtasks by their order in `subtask_ids`.
+    async fn set_subtask_positions(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_ids: &[ObjectId],
+    ) -> Result<bool, Error>;
+
+    async fn unlink_goal(&self, scope: &Scope, goal_id: &ObjectId) -> Result<u64, Error>;
+
+    async fn delete_task(&self, scope: &Scope, task_id: &ObjectId) -> Result<bool, Error>;
+
+    async fn delete_all_user_tasks(&self, scope: &Scope) -> Result<u64, Error>;
+
+    async fn get_all_user_tasks(&self, scope: &Scope) -> Result<Vec<Task>, Error>;
+
+    /// Returns one page of a user's tasks plus the cursor for the next page, if any.
+    /// Without a `limit` every matching task is returned and there is no next page.
+    async fn find_user_tasks(
+        &self,
+        scope: &Scope,
+        filter: &TaskFilter,
+        sort: TaskSortField,
+        direction: SortDirection,
+        cursor: Option<&Cursor>,
+        limit: Option<i64>,
+    ) -> Result<(Vec<Task>, Option<Cursor>), Error>;
+
+    async fn get_user_tasks_by_goal(
+        &self,
+        scope: &Scope,
+        goal_id: &ObjectId,
+    ) -> Result<Vec<Task>, Error>;
+
+    async fn count_tasks_by_goal(&self, scope: &Scope) -> Result<Vec<TaskCountByGoal>, Error>;
+
+    /// Looks a task up by id alone; callers check it is accessible from its `scope`.
+    async fn get_task_by_id(&self, task_id: &ObjectId) -> Result<Option<Task>, Error>;
+
+    async fn get_task_by_title(
+        &self,
+        scope: &Scope,
+        title: &str,
+    ) -> Result<Option<Task>, Error>;
+
+    async fn count_tasks_by_status(
+        &self,
+        scope: &Scope,
+    ) -> Result<Vec<TaskByCategoryAndStatus>, Error>;
+
+    /// Number of tasks in each status, across every user.
+    async fn count_all_tasks_by_status(&self) -> Result<Vec<(String, u64)>, Error>;
+
+    async fn set_assignee(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        assignee_id: Option<&ObjectId>,
+    ) -> Result<bool, Error>;
+
+    /// Unassigns the user from every task of the workspace, e.g. when they leave it.
+    async fn unassign_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<u64, Error>;
+
+    /// Tasks whose pending notification is due by `due_by`, however long ago.
+    async fn get_all_not_sent_notifications(
+        &self,
+        due_by: DateTime<Utc>,
+    ) -> Result<Vec<Task>, Error>;
+
+    async fn mark_notification_as_sent(&self, task_id: &ObjectId) -> Result<bool, Error>;
+
+    /// Closes the pending notification without it having been sent.
+    async fn mark_notification_as_missed(&self, task_id: &ObjectId) -> Result<bool, Error>;
+
+    /// Counts a failed delivery of the pending notification.
+    async fn record_failed_attempt(&self, task_id: &ObjectId) -> Result<bool, Error>;
+
+    /// Moves a recurring task's notification on to its next occurrence.
+    async fn reschedule_notification(
+        &self,
+        task_id: &ObjectId,
+        scheduled_time: DateTime<Utc>,
+    ) -> Result<bool, Error>;
+
+    /// Tasks with a notification whose reminders go to the user, see `Task::recipient_id`.
+    async fn get_all_with_notifications(&self, user_id: &ObjectId) -> Result<Vec<Task>, Error>;
+}
+
+pub struct MongoTaskRepository {
     collection: Collection<Task>,
 }
 
-impl TaskRepository {
+impl MongoTaskRepository {
     pub fn new(db: &Database) -> Self {
         let collection = db.collection("tasks");
-        TaskRepository { collection }
+        MongoTaskRepository { collection }
     }
+}
 
-    pub async fn create_task(&self, new_task: Task) -> Result<mongodb::bson::oid::ObjectId, Error> {
+#[async_trait]
+impl TaskRepository for MongoTaskRepository {
+    async fn create_task(&self, new_task: Task) -> Result<mongodb::bson::oid::ObjectId, Error> {
         let result = self.collection.insert_one(new_task).await?;
         let id = result.inserted_id.as_object_id().unwrap();
         Ok(id)
     }
 
-    pub async fn update_task(
+    #[allow(clippy::too_many_arguments)]
+    async fn update_task(
         &self,
+        scope: &Scope,
         task_id: &ObjectId,
         title: Option<String>,
         description: Option<String>,
@@ -36,7 +217,7 @@ impl TaskRepository {
         category_id: Option<ObjectId>,
         notification: Option<Option<Notification>>,
     ) -> Result<bool, Error> {
-        let filter = doc! { "_id": task_id };
+        let filter = scope.filter_by_id(task_id);
     
         let mut update_doc = doc! {};
         if let Some(title) = title {
@@ -46,10 +227,10 @@ impl TaskRepository {
             update_doc.insert("description", description);
         }
         if let Some(start_date) = start_date {
-            update_doc.insert("start_date", start_date.to_rfc3339());
+            update_doc.insert("start_date", to_bson_datetime(start_date));
         }
         if let Some(end_date) = end_date {
-            update_doc.insert("end_date", end_date.to_rfc3339());
+            update_doc.insert("end_date", to_bson_datetime(end_date));
         }
         if let Some(status) = status {
             update_doc.insert("status", Bson::String(status.as_str().to_string()));
@@ -58,13 +239,12 @@ impl TaskRepository {
             update_doc.insert("category_id", category_id);
         }
         if let Some(notification) = notification {
-            if notification.is_some() {
-                let notification = notification.unwrap();
+            if let Some(notification) = notification {
                 let notification_doc = doc! {
                     "_id": notification.id,
                     "time_unit": notification.time_unit.as_str().to_string(),
                     "time_value": notification.time_value as i64,
-                    "scheduled_time": notification.scheduled_time.to_rfc3339(),
+                    "scheduled_time": to_bson_datetime(notification.scheduled_time),
                     "sent": notification.sent,
                 };
                 update_doc.insert("notification", notification_doc);
@@ -72,6 +252,10 @@ impl TaskRepository {
                 update_doc.insert("notification", Bson::Null);
             }
         }
+
+        if update_doc.is_empty() {
+            return Ok(false);
+        }
     
         let update = doc! { "$set": update_doc };
         let result = self.collection.update_one(filter, update).await?;
@@ -79,21 +263,270 @@ impl TaskRepository {
         Ok(result.modified_count > 0)
     }
     
-    pub async fn delete_task(&self, task_id: &ObjectId) -> Result<bool, Error> {
-        let query = doc! {"_id": task_id};
+    async fn set_recurrence(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        recurrence: Option<RecurrenceRule>,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let recurrence = to_bson(&recurrence.map(StoredRecurrenceRule::from))?;
+        let update = doc! { "$set": { "recurrence": recurrence } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_occurrence_overrides(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        overrides: &[OccurrenceOverride],
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let overrides = to_bson(overrides)?;
+        let update = doc! { "$set": { "occurrence_overrides": overrides } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_goal(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        goal_id: Option<&ObjectId>,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$set": { "goal_id": goal_id } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_derive_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        derive_status: bool,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$set": { "derive_status": derive_status } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        status: Status,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$set": { "status": status.as_str() } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn push_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask: &Subtask,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$push": { "subtasks": to_bson(subtask)? } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn update_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+        title: Option<&str>,
+        done: Option<bool>,
+    ) -> Result<bool, Error> {
+        let mut filter = scope.filter_by_id(task_id);
+        filter.insert("subtasks._id", subtask_id);
+        let mut update_doc = Document::new();
+        if let Some(title) = title {
+            update_doc.insert("subtasks.$.title", title);
+        }
+        if let Some(done) = done {
+            update_doc.insert("subtasks.$.done", done);
+        }
+        if update_doc.is_empty() {
+            return Ok(false);
+        }
+        let update = doc! { "$set": update_doc };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn pull_subtask(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$pull": { "subtasks": { "_id": subtask_id } } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_subtask_positions(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        subtask_ids: &[ObjectId],
+    ) -> Result<bool, Error> {
+        if subtask_ids.is_empty() {
+            return Ok(false);
+        }
+
+        let filter = scope.filter_by_id(task_id);
+        let mut positions = Document::new();
+        let mut array_filters = Vec::with_capacity(subtask_ids.len());
+        for (position, subtask_id) in subtask_ids.iter().enumerate() {
+            positions.insert(format!("subtasks.$[s{position}].position"), position as u32);
+            array_filters.push(doc! { format!("s{position}._id"): subtask_id });
+        }
+        let update = doc! { "$set": positions };
+        let result = self
+            .collection
+            .update_one(filter, update)
+            .array_filters(array_filters)
+            .await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn unlink_goal(&self, scope: &Scope, goal_id: &ObjectId) -> Result<u64, Error> {
+        let mut filter = scope.filter();
+        filter.insert("goal_id", goal_id);
+        let update = doc! { "$set": { "goal_id": Bson::Null } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_task(&self, scope: &Scope, task_id: &ObjectId) -> Result<bool, Error> {
+        let query = scope.filter_by_id(task_id);
 
         let result = self.collection.delete_one(query).await?;
 
         Ok(result.deleted_count > 0)
     }
 
-    pub async fn get_all_user_tasks(&self, &user_id: &ObjectId) -> Result<Vec<Task>, Error> {
-        let mut cursor = self
+    async fn delete_all_user_tasks(&self, scope: &Scope) -> Result<u64, Error> {
+        let result = self.collection.delete_many(scope.filter()).await?;
+
+        Ok(result.deleted_count)
+    }
+
+    async fn get_all_user_tasks(&self, scope: &Scope) -> Result<Vec<Task>, Error> {
+        let mut cursor = self.collection.find(scope.filter()).await?;
+        let mut tasks: Vec<Task> = Vec::new();
+
+        while cursor.advance().await? {
+            tasks.push(cursor.deserialize_current()?);
+        }
+
+        Ok(tasks)
+    }
+
+    async fn find_user_tasks(
+        &self,
+        scope: &Scope,
+        filter: &TaskFilter,
+        sort: TaskSortField,
+        direction: SortDirection,
+        cursor: Option<&Cursor>,
+        limit: Option<i64>,
+    ) -> Result<(Vec<Task>, Option<Cursor>), Error> {
+        let mut query = scope.filter();
+        let mut conditions: Vec<Document> = Vec::new();
+
+        if let Some(status) = &filter.status {
+            query.insert("status", status.as_str());
+        }
+        if let Some(category_id) = &filter.category_id {
+            query.insert("category_id", category_id);
+        }
+        if let Some(assignee_id) = &filter.assignee_id {
+            query.insert("assignee_id", assignee_id);
+        }
+        if let Some(range) = date_range(filter.start_from, filter.start_to) {
+            query.insert("start_date", range);
+        }
+        if let Some(range) = date_range(filter.end_from, filter.end_to) {
+            query.insert("end_date", range);
+        }
+        if let Some(text) = &filter.text {
+            let pattern = escape_regex(text);
+            conditions.push(doc! {
+                "$or": [
+                    { "title": { "$regex": &pattern, "$options": "i" } },
+                    { "description": { "$regex": &pattern, "$options": "i" } },
+                ]
+            });
+        }
+        if let Some(cursor) = cursor {
+            conditions.push(cursor.after_filter(sort.as_str(), direction));
+        }
+        if !conditions.is_empty() {
+            query.insert("$and", conditions);
+        }
+
+        let order = direction.as_i32();
+        let mut documents = self
             .collection
-            .find(doc! {
-                "user_id": user_id
-            })
+            .clone_with_type::<Document>()
+            .find(query)
+            .sort(doc! { sort.as_str(): order, "_id": order })
+            .limit(limit.map_or(0, |limit| limit + 1))
             .await?;
+
+        let mut page: Vec<Document> = Vec::new();
+        while documents.advance().await? {
+            page.push(documents.deserialize_current()?);
+        }
+
+        let next_cursor = match limit {
+            Some(limit) if page.len() as i64 > limit => {
+                page.truncate(limit as usize);
+                page.last().map(|last| Cursor {
+                    sort: sort.as_str().to_string(),
+                    value: last.get(sort.as_str()).cloned().unwrap_or(Bson::Null),
+                    id: last.get_object_id("_id").unwrap(),
+                })
+            }
+            _ => None,
+        };
+
+        let tasks = page
+            .into_iter()
+            .map(from_document)
+            .collect::<Result<Vec<Task>, _>>()?;
+        Ok((tasks, next_cursor))
+    }
+
+    async fn get_user_tasks_by_goal(
+        &self,
+        scope: &Scope,
+        goal_id: &ObjectId,
+    ) -> Result<Vec<Task>, Error> {
+        let mut filter = scope.filter();
+        filter.insert("goal_id", goal_id);
+        let mut cursor = self.collection.find(filter).await?;
         let mut tasks: Vec<Task> = Vec::new();
 
         while cursor.advance().await? {
@@ -103,30 +536,67 @@ impl TaskRepository {
         Ok(tasks)
     }
 
-    pub async fn get_task_by_id(&self, &task_id: &ObjectId) -> Result<Option<Task>, Error> {
-        let filter = doc! {"_id": task_id};
-        self.collection.find_one(filter).await
+    async fn count_tasks_by_goal(&self, scope: &Scope) -> Result<Vec<TaskCountByGoal>, Error> {
+        let mut filter = scope.filter();
+        filter.insert("goal_id", doc! { "$ne": Bson::Null });
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            doc! {
+                "$group": {
+                    "_id": "$goal_id",
+                    "total": { "$sum": 1 },
+                    "completed": {
+                        "$sum": { "$cond": [{ "$eq": ["$status", Status::Executada.as_str()] }, 1, 0] }
+                    }
+                }
+            },
+        ];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut result: Vec<TaskCountByGoal> = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+
+            if let (Ok(goal_id), Ok(total), Ok(completed)) = (
+                doc.get_object_id("_id"),
+                doc.get_i32("total"),
+                doc.get_i32("completed"),
+            ) {
+                result.push(TaskCountByGoal {
+                    goal_id,
+                    total,
+                    completed,
+                });
+            }
+        }
+
+        Ok(result)
     }
 
-    pub async fn get_task_by_title(
+    async fn get_task_by_id(&self, task_id: &ObjectId) -> Result<Option<Task>, Error> {
+        self.collection.find_one(doc! { "_id": task_id }).await
+    }
+
+    async fn get_task_by_title(
         &self,
-        &user_id: &ObjectId,
+        scope: &Scope,
         title: &str,
     ) -> Result<Option<Task>, Error> {
-        let filter = doc! {"user_id": user_id, "title": title};
+        let mut filter = scope.filter();
+        filter.insert("title", title);
         self.collection.find_one(filter).await
     }
 
 
-    pub async fn count_tasks_by_status(
+    async fn count_tasks_by_status(
         &self,
-        user_id: &mongodb::bson::oid::ObjectId,
+        scope: &Scope,
     ) -> Result<Vec<TaskByCategoryAndStatus>, Error> {
         let pipeline = vec![
             doc! {
-                "$match": {
-                    "user_id": user_id,
-                }
+                "$match": scope.filter()
             },
             doc! {
                 "$lookup": {
@@ -181,9 +651,55 @@ impl TaskRepository {
         Ok(result)
     }
 
-    pub async fn get_all_not_sent_notifications(&self, greater_than: DateTime<Utc>, last_than_or_equals: DateTime<Utc>) -> Result<Vec<Task>, Error> {
+    async fn count_all_tasks_by_status(&self) -> Result<Vec<(String, u64)>, Error> {
+        let pipeline = vec![doc! {
+            "$group": {
+                "_id": "$status",
+                "count": { "$sum": 1 }
+            }
+        }];
+
+        let mut cursor = self.collection.aggregate(pipeline).await?;
+        let mut result: Vec<(String, u64)> = Vec::new();
+
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            if let (Ok(status), Ok(count)) = (doc.get_str("_id"), doc.get_i32("count")) {
+                result.push((status.to_string(), count as u64));
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn set_assignee(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+        assignee_id: Option<&ObjectId>,
+    ) -> Result<bool, Error> {
+        let filter = scope.filter_by_id(task_id);
+        let update = doc! { "$set": { "assignee_id": assignee_id } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn unassign_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<u64, Error> {
+        let filter = doc! { "workspace_id": workspace_id, "assignee_id": user_id };
+        let update = doc! { "$set": { "assignee_id": Bson::Null } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn get_all_not_sent_notifications(&self, due_by: DateTime<Utc>) -> Result<Vec<Task>, Error> {
         let filter = doc! {
-            "notification.scheduled_time": { "$gte": greater_than.to_rfc3339(), "$lte": last_than_or_equals.to_rfc3339() },
+            "notification.scheduled_time": { "$lte": to_bson_datetime(due_by) },
             "notification.sent": false
         };
 
@@ -196,7 +712,7 @@ impl TaskRepository {
         Ok(tasks)
     }
 
-    pub async fn mark_notification_as_sent(&self, task_id: &ObjectId) -> Result<bool, Error> {
+    async fn mark_notification_as_sent(&self, task_id: &ObjectId) -> Result<bool, Error> {
         let filter = doc! { "_id": task_id, "notification.sent": false };
         let update = doc! { "$set": { "notification.sent": true } };
         let result = self.collection.update_one(filter, update).await?;
@@ -204,9 +720,44 @@ impl TaskRepository {
         Ok(result.modified_count > 0)
     }
 
-    pub async fn get_all_with_notifications(&self, user_id: &ObjectId) -> Result<Vec<Task>, Error> {
+    async fn mark_notification_as_missed(&self, task_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "_id": task_id, "notification.sent": false };
+        let update = doc! { "$set": { "notification.sent": true, "notification.missed": true } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn record_failed_attempt(&self, task_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "_id": task_id, "notification.sent": false };
+        let update = doc! { "$inc": { "notification.attempts": 1 } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn reschedule_notification(
+        &self,
+        task_id: &ObjectId,
+        scheduled_time: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": task_id, "notification.sent": false };
+        let update = doc! { "$set": {
+            "notification.scheduled_time": to_bson_datetime(scheduled_time),
+            "notification.viewed": false,
+            "notification.attempts": 0,
+        } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn get_all_with_notifications(&self, user_id: &ObjectId) -> Result<Vec<Task>, Error> {
         let filter = doc! {
-            "user_id": user_id,
+            "$or": [
+                { "assignee_id": user_id },
+                { "user_id": user_id, "assignee_id": Bson::Null },
+            ],
             "notification": { "$ne": null }
         };
 
@@ -219,3 +770,170 @@ impl TaskRepository {
         Ok(notifications)
     }
 }
+
+#[async_trait]
+pub trait TaskCommentRepository: Send + Sync {
+    async fn create_comment(&self, comment: TaskComment) -> Result<ObjectId, Error>;
+
+    async fn find_comment(
+        &self,
+        task_id: &ObjectId,
+        comment_id: &ObjectId,
+    ) -> Result<Option<TaskComment>, Error>;
+
+    /// Comments of a task, oldest first.
+    async fn get_task_comments(&self, task_id: &ObjectId) -> Result<Vec<TaskComment>, Error>;
+
+    async fn delete_comment(&self, comment_id: &ObjectId) -> Result<bool, Error>;
+
+    async fn delete_task_comments(&self, task_id: &ObjectId) -> Result<u64, Error>;
+
+    async fn delete_tasks_comments(&self, task_ids: &[ObjectId]) -> Result<u64, Error>;
+
+    async fn delete_all_user_comments(&self, user_id: &ObjectId) -> Result<u64, Error>;
+}
+
+pub struct MongoTaskCommentRepository {
+    collection: Collection<TaskComment>,
+}
+
+impl MongoTaskCommentRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("task_comments");
+        MongoTaskCommentRepository { collection }
+    }
+}
+
+#[async_trait]
+impl TaskCommentRepository for MongoTaskCommentRepository {
+    async fn create_comment(&self, comment: TaskComment) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(comment).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn find_comment(
+        &self,
+        task_id: &ObjectId,
+        comment_id: &ObjectId,
+    ) -> Result<Option<TaskComment>, Error> {
+        self.collection
+            .find_one(doc! { "_id": comment_id, "task_id": task_id })
+            .await
+    }
+
+    async fn get_task_comments(&self, task_id: &ObjectId) -> Result<Vec<TaskComment>, Error> {
+        let mut cursor = self
+            .collection
+            .find(doc! { "task_id": task_id })
+            .sort(doc! { "_id": 1 })
+            .await?;
+        let mut comments = Vec::new();
+        while cursor.advance().await? {
+            comments.push(cursor.deserialize_current()?);
+        }
+
+        Ok(comments)
+    }
+
+    async fn delete_comment(&self, comment_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.collection.delete_one(doc! { "_id": comment_id }).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn delete_task_comments(&self, task_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.collection.delete_many(doc! { "task_id": task_id }).await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn delete_tasks_comments(&self, task_ids: &[ObjectId]) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .delete_many(doc! { "task_id": { "$in": task_ids } })
+            .await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn delete_all_user_comments(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.collection.delete_many(doc! { "user_id": user_id }).await?;
+        Ok(result.deleted_count)
+    }
+}
+
+/// Append-only: entries are only ever inserted. Deleting an account erases the history of
+/// its own tasks and the author of its changes elsewhere.
+#[async_trait]
+pub trait TaskActivityRepository: Send + Sync {
+    async fn record(&self, activity: TaskActivity) -> Result<ObjectId, Error>;
+
+    /// History of a task, oldest first.
+    async fn get_task_activity(&self, task_id: &ObjectId) -> Result<Vec<TaskActivity>, Error>;
+
+    async fn delete_tasks_activity(&self, task_ids: &[ObjectId]) -> Result<u64, Error>;
+
+    /// Keeps the changes made by the user, without saying who made them.
+    async fn anonymize_user_activity(&self, user_id: &ObjectId) -> Result<u64, Error>;
+}
+
+pub struct MongoTaskActivityRepository {
+    collection: Collection<TaskActivity>,
+}
+
+impl MongoTaskActivityRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("task_activity");
+        MongoTaskActivityRepository { collection }
+    }
+}
+
+#[async_trait]
+impl TaskActivityRepository for MongoTaskActivityRepository {
+    async fn record(&self, activity: TaskActivity) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(activity).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn get_task_activity(&self, task_id: &ObjectId) -> Result<Vec<TaskActivity>, Error> {
+        let mut cursor = self
+            .collection
+            .find(doc! { "task_id": task_id })
+            .sort(doc! { "_id": 1 })
+            .await?;
+        let mut activity = Vec::new();
+        while cursor.advance().await? {
+            activity.push(cursor.deserialize_current()?);
+        }
+
+        Ok(activity)
+    }
+
+    async fn delete_tasks_activity(&self, task_ids: &[ObjectId]) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .delete_many(doc! { "task_id": { "$in": task_ids } })
+            .await?;
+        Ok(result.deleted_count)
+    }
+
+    async fn anonymize_user_activity(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .update_many(doc! { "user_id": user_id }, doc! { "$set": { "user_id": null } })
+            .await?;
+        Ok(result.modified_count)
+    }
+}
+
+fn date_range(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Option<Document> {
+    if from.is_none() && to.is_none() {
+        return None;
+    }
+
+    let mut range = doc! {};
+    if let Some(from) = from {
+        range.insert("$gte", to_bson_datetime(from));
+    }
+    if let Some(to) = to {
+        range.insert("$lte", to_bson_datetime(to));
+    }
+    Some(range)
+}
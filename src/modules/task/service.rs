@@ -1,14 +1,34 @@
-use crate::modules::notification::models::{Notification, TimeUnit};
+use crate::modules::notification::models::Notification;
 
-use std::collections::HashMap;
-use chrono::Duration;
+use std::{collections::HashMap, sync::Arc};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use log::error;
 use mongodb::bson::oid::ObjectId;
 use mongodb::error::Error;
 use thiserror::Error;
 
-use super::dto::{CreateTaskRequest, UpdateTaskRequest};
-use super::models::{Task, TaskStatsByCategory};
-use super::repository::TaskRepository;
+use crate::helpers::{mongo_error_helper::duplicate_key_as, pagination_helper::Cursor};
+use crate::modules::category::repository::CategoryRepository;
+use crate::modules::goal::service::GoalService;
+use crate::modules::workspace::{
+    access::{WorkspaceAccess, WorkspaceAccessError},
+    models::{Access, Scope},
+};
+
+use super::activity;
+use super::dto::{
+    CreateCommentRequest, CreateSubtaskRequest, CreateTaskRequest, TaskListQuery,
+    UpdateOccurrenceRequest, UpdateSubtaskRequest, UpdateTaskRequest,
+};
+use super::models::{
+    ActivityAction, FieldChange, OccurrenceOverride, RecurrenceRule, Subtask, Task, TaskActivity,
+    TaskComment, TaskCountByGoal, TaskFilter, TaskOccurrence, TaskStatsByCategory, TimelineEntry,
+};
+use super::recurrence;
+use super::repository::{TaskActivityRepository, TaskCommentRepository, TaskRepository};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
 
 #[derive(Error, Debug)]
 pub enum TaskServiceError {
@@ -18,84 +38,177 @@ pub enum TaskServiceError {
     #[error("Task not found")]
     TaskNotFound,
 
+    #[error("You do not have permission to access this task")]
+    TaskForbidden,
+
+    #[error("Category not found")]
+    CategoryNotFound,
+
+    #[error("Goal not found")]
+    GoalNotFound,
+
+    #[error("Task is not recurring")]
+    TaskNotRecurring,
+
+    #[error("Occurrence not found")]
+    OccurrenceNotFound,
+
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error("Subtask not found")]
+    SubtaskNotFound,
+
+    #[error("Comment not found")]
+    CommentNotFound,
+
+    #[error("You can only delete your own comments")]
+    CommentForbidden,
+
+    #[error("Only workspace tasks can be assigned")]
+    TaskNotAssignable,
+
+    #[error("The assignee is not a member of this workspace")]
+    AssigneeNotMember,
+
+    #[error(transparent)]
+    Workspace(#[from] WorkspaceAccessError),
+
     #[error("Database error occurred: {0}")]
     DatabaseError(#[from] Error),
 }
 
 pub struct TaskService {
-    repository: TaskRepository,
+    repository: Arc<dyn TaskRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+    comment_repository: Arc<dyn TaskCommentRepository>,
+    activity_repository: Arc<dyn TaskActivityRepository>,
+    goal_service: GoalService,
+    access: WorkspaceAccess,
 }
 
 impl TaskService {
-    pub fn new(repository: TaskRepository) -> Self {
-        TaskService { repository }
+    pub fn new(
+        repository: Arc<dyn TaskRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+        comment_repository: Arc<dyn TaskCommentRepository>,
+        activity_repository: Arc<dyn TaskActivityRepository>,
+        goal_service: GoalService,
+        access: WorkspaceAccess,
+    ) -> Self {
+        TaskService {
+            repository,
+            category_repository,
+            comment_repository,
+            activity_repository,
+            goal_service,
+            access,
+        }
     }
 
     pub async fn create_task_for_user(
         &self,
         &user_id: &ObjectId,
+        time_zone: &Tz,
         task_data: CreateTaskRequest,
     ) -> Result<ObjectId, TaskServiceError> {
+        let start_date = task_data.start_date.to_utc(time_zone);
+        let end_date = task_data.end_date.to_utc(time_zone);
+        let scope = self
+            .access
+            .resolve(&user_id, task_data.workspace_id, Access::Write)
+            .await?;
+
         if let Some(_existing_task) = self
             .repository
-            .get_task_by_title(&user_id, &task_data.title)
+            .get_task_by_title(&scope, &task_data.title)
             .await?
         {
             return Err(TaskServiceError::TaskAlreadyExists);
         }
+        self.ensure_category(&scope, &task_data.category_id).await?;
+        if let Some(goal_id) = &task_data.goal_id {
+            self.ensure_goal(&scope, goal_id).await?;
+        }
+        if let Some(assignee_id) = &task_data.assignee_id {
+            self.ensure_assignable(&scope, assignee_id).await?;
+        }
 
-        let notification: Option<Notification> = {
-            if task_data.notification_time_unit.is_none() || task_data.notification_time_value.is_none()
-            {
-                None
-            } else {
-                let time_value = task_data.notification_time_value.unwrap() as i64;
-                Some(Notification {
-                    id: ObjectId::new(),
-                    sent: false,
-                    scheduled_time: match task_data.notification_time_unit {
-                        Some(TimeUnit::Minute) => task_data.start_date - Duration::minutes(time_value),
-                        Some(TimeUnit::Hour) => task_data.start_date - Duration::hours(time_value),
-                        None => task_data.start_date,
-                    },
-                    time_unit: task_data.notification_time_unit.unwrap(),
-                    time_value: task_data.notification_time_value.unwrap(),
-                    viewed: false,
-                })
-            }
+        let notification: Option<Notification> = match (
+            task_data.notification_time_unit,
+            task_data.notification_time_value,
+        ) {
+            (Some(time_unit), Some(time_value)) => Some(Notification {
+                id: ObjectId::new(),
+                sent: false,
+                scheduled_time: reminder_time(
+                    start_date,
+                    task_data.recurrence.as_ref(),
+                    &[],
+                    time_zone,
+                    time_unit.duration(time_value),
+                ),
+                time_unit,
+                time_value,
+                viewed: false,
+                attempts: 0,
+                missed: false,
+            }),
+            _ => None,
         };
 
         let new_task = Task {
             id: None,
             title: task_data.title,
             description: task_data.description,
-            start_date: task_data.start_date,
-            end_date: task_data.end_date,
+            start_date,
+            end_date,
             status: task_data.status,
             user_id,
+            workspace_id: task_data.workspace_id,
+            assignee_id: task_data.assignee_id,
             category_id: task_data.category_id,
+            goal_id: task_data.goal_id,
             notification,
+            recurrence: task_data.recurrence,
+            occurrence_overrides: Vec::new(),
+            subtasks: Vec::new(),
+            derive_status: task_data.derive_status,
         };
 
-        let result = self.repository.create_task(new_task).await?;
+        let changes = activity::changes(None, Some(&new_task));
+        let has_goal = new_task.goal_id.is_some();
+        let result = self
+            .repository
+            .create_task(new_task)
+            .await
+            .map_err(duplicate_key_as(TaskServiceError::TaskAlreadyExists))?;
+        if has_goal {
+            self.goal_service.sync_progress(&scope).await;
+        }
+        self.record_activity(&user_id, &result, ActivityAction::Created, changes)
+            .await;
         Ok(result)
     }
 
     pub async fn update_user_task(
         &self,
         &user_id: &ObjectId,
+        time_zone: &Tz,
         task_id: &ObjectId,
         task_data: UpdateTaskRequest,
     ) -> Result<bool, TaskServiceError> {
-        let old_data = self.repository.get_task_by_id(task_id).await?;
-        if old_data.is_none() {
-            return Err(TaskServiceError::TaskNotFound);
-        }
-    
+        let old_data = self
+            .get_accessible_task(&user_id, task_id, Access::Write)
+            .await?;
+        let scope = old_data.scope();
+        let start_date = task_data.start_date.map(|date| date.to_utc(time_zone));
+        let end_date = task_data.end_date.map(|date| date.to_utc(time_zone));
+
         if let Some(title) = &task_data.title {
             if let Some(existing_task) = self
                 .repository
-                .get_task_by_title(&user_id, title)
+                .get_task_by_title(&scope, title)
                 .await?
             {
                 if existing_task.id.as_ref() != Some(task_id) {
@@ -103,64 +216,638 @@ impl TaskService {
                 }
             }
         }
+        if let Some(category_id) = &task_data.category_id {
+            self.ensure_category(&scope, category_id).await?;
+        }
+        if let Some(Some(goal_id)) = &task_data.goal_id {
+            self.ensure_goal(&scope, goal_id).await?;
+        }
+
+        let start = start_date.unwrap_or(old_data.start_date);
+        let rule = match &task_data.recurrence {
+            Some(rule) => rule.as_ref(),
+            None => old_data.recurrence.as_ref(),
+        };
+        let schedule_changed =
+            start != old_data.start_date || rule != old_data.recurrence.as_ref();
 
         let notification = match (task_data.notification_time_unit, task_data.notification_time_value) {
             (Some(Some(time_unit)), Some(Some(time_value))) => { // existi and has value
-                let time_value = time_value as i64;
-                let start_date = task_data.start_date.or(Some(old_data.unwrap().start_date)).unwrap();
-                let scheduled_time = match time_unit {
-                    TimeUnit::Minute => start_date - Duration::minutes(time_value),
-                    TimeUnit::Hour => start_date - Duration::hours(time_value),
-                };
+                let scheduled_time = reminder_time(
+                    start,
+                    rule,
+                    &old_data.occurrence_overrides,
+                    time_zone,
+                    time_unit.duration(time_value),
+                );
                 Some(Some(Notification {
                     id: ObjectId::new(),
                     time_unit,
-                    time_value: time_value as u16,
+                    time_value,
                     scheduled_time,
                     sent: false,
                     viewed: false,
+                    attempts: 0,
+                    missed: false,
                 }))
             },
             (Some(None), Some(None)) => Some(None), // Remove notification
-            _ => None,
+            // Moving the task or changing its rule moves its reminder along with it
+            _ => match &old_data.notification {
+                Some(old) if schedule_changed => Some(Some(Notification {
+                    id: old.id,
+                    time_unit: old.time_unit.clone(),
+                    time_value: old.time_value,
+                    scheduled_time: reminder_time(
+                        start,
+                        rule,
+                        &old_data.occurrence_overrides,
+                        time_zone,
+                        old.time_unit.duration(old.time_value),
+                    ),
+                    sent: false,
+                    viewed: false,
+                    attempts: 0,
+                    missed: false,
+                })),
+                _ => None,
+            },
         };
     
-        let result = self
+        let mut result = self
             .repository
             .update_task(
+                &scope,
                 task_id,
                 task_data.title,
                 task_data.description,
-                task_data.start_date,
-                task_data.end_date,
+                start_date,
+                end_date,
                 task_data.status,
                 task_data.category_id,
                 notification,
             )
+            .await
+            .map_err(duplicate_key_as(TaskServiceError::TaskAlreadyExists))?;
+
+        if let Some(goal_id) = task_data.goal_id {
+            result |= self
+                .repository
+                .set_goal(&scope, task_id, goal_id.as_ref())
+                .await?;
+        }
+        if let Some(recurrence) = task_data.recurrence {
+            if recurrence.is_none() && !old_data.occurrence_overrides.is_empty() {
+                result |= self
+                    .repository
+                    .set_occurrence_overrides(&scope, task_id, &[])
+                    .await?;
+            }
+            result |= self
+                .repository
+                .set_recurrence(&scope, task_id, recurrence)
+                .await?;
+        }
+        if let Some(derive_status) = task_data.derive_status {
+            result |= self
+                .repository
+                .set_derive_status(&scope, task_id, derive_status)
+                .await?;
+            if derive_status {
+                result |= self.sync_derived_status(&scope, task_id).await?;
+            }
+        }
+        if result {
+            self.goal_service.sync_progress(&scope).await;
+        }
+
+        self.record_update(&user_id, &old_data).await;
+        Ok(result)
+    }
+
+    pub async fn get_user_task_subtasks(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+    ) -> Result<Vec<Subtask>, TaskServiceError> {
+        let mut subtasks = self
+            .get_accessible_task(user_id, task_id, Access::Read)
+            .await?
+            .subtasks;
+        subtasks.sort_by_key(|subtask| subtask.position);
+        Ok(subtasks)
+    }
+
+    pub async fn add_subtask(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        request: CreateSubtaskRequest,
+    ) -> Result<ObjectId, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let scope = task.scope();
+
+        let id = ObjectId::new();
+        let subtask = Subtask {
+            id,
+            title: request.title,
+            done: false,
+            position: task.subtasks.len() as u32,
+        };
+
+        self.repository
+            .push_subtask(&scope, task_id, &subtask)
+            .await?;
+        self.sync_derived_status(&scope, task_id).await?;
+        self.record_update(user_id, &task).await;
+        Ok(id)
+    }
+
+    pub async fn update_subtask(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+        request: UpdateSubtaskRequest,
+    ) -> Result<bool, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let scope = task.scope();
+        let mut order = subtask_order(&task);
+
+        let index = order
+            .iter()
+            .position(|id| id == subtask_id)
+            .ok_or(TaskServiceError::SubtaskNotFound)?;
+        let mut result = self
+            .repository
+            .update_subtask(
+                &scope,
+                task_id,
+                subtask_id,
+                request.title.as_deref(),
+                request.done,
+            )
             .await?;
-    
+        if let Some(position) = request.position {
+            order.remove(index);
+            order.insert((position as usize).min(order.len()), *subtask_id);
+            result |= self
+                .repository
+                .set_subtask_positions(&scope, task_id, &order)
+                .await?;
+        }
+        if request.done.is_some() {
+            self.sync_derived_status(&scope, task_id).await?;
+        }
+        if result {
+            self.record_update(user_id, &task).await;
+        }
+
+        Ok(result)
+    }
+
+    pub async fn delete_subtask(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        subtask_id: &ObjectId,
+    ) -> Result<bool, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let scope = task.scope();
+        let mut order = subtask_order(&task);
+        if !order.contains(subtask_id) {
+            return Err(TaskServiceError::SubtaskNotFound);
+        }
+
+        let result = self
+            .repository
+            .pull_subtask(&scope, task_id, subtask_id)
+            .await?;
+        order.retain(|id| id != subtask_id);
+        self.repository
+            .set_subtask_positions(&scope, task_id, &order)
+            .await?;
+        self.sync_derived_status(&scope, task_id).await?;
+        if result {
+            self.record_update(user_id, &task).await;
+        }
+
+        Ok(result)
+    }
+
+    /// Moves a task that derives its status to the one implied by its checklist as stored,
+    /// so concurrent checklist edits are taken into account.
+    async fn sync_derived_status(
+        &self,
+        scope: &Scope,
+        task_id: &ObjectId,
+    ) -> Result<bool, TaskServiceError> {
+        let Some(task) = self.repository.get_task_by_id(task_id).await? else {
+            return Ok(false);
+        };
+        if !task.derive_status {
+            return Ok(false);
+        }
+        let Some(status) = Subtask::derived_status(&task.subtasks) else {
+            return Ok(false);
+        };
+
+        let result = self.repository.set_status(scope, task_id, status).await?;
+        if result && task.goal_id.is_some() {
+            self.goal_service.sync_progress(scope).await;
+        }
+        Ok(result)
+    }
+
+    pub async fn get_user_task_occurrences(
+        &self,
+        scope: &Scope,
+        time_zone: &Tz,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<TaskOccurrence>, Error> {
+        let tasks = self.repository.get_all_user_tasks(scope).await?;
+        let mut occurrences = Vec::new();
+
+        for task in tasks {
+            let duration = task.end_date - task.start_date;
+            let starts = match &task.recurrence {
+                Some(rule) => recurrence::expand(task.start_date, rule, time_zone, from, to),
+                None if task.start_date >= from && task.start_date <= to => vec![task.start_date],
+                None => Vec::new(),
+            };
+
+            for start_date in starts {
+                let exception = task
+                    .occurrence_overrides
+                    .iter()
+                    .find(|exception| exception.occurrence_start == start_date);
+                if exception.is_some_and(|exception| exception.cancelled) {
+                    continue;
+                }
+
+                occurrences.push(TaskOccurrence {
+                    task_id: task.id.unwrap(),
+                    title: task.title.clone(),
+                    description: task.description.clone(),
+                    start_date,
+                    end_date: start_date + duration,
+                    status: exception
+                        .and_then(|exception| exception.status.clone())
+                        .unwrap_or_else(|| task.status.clone()),
+                    recurring: task.recurrence.is_some(),
+                });
+            }
+        }
+
+        occurrences.sort_by_key(|occurrence| occurrence.start_date);
+        Ok(occurrences)
+    }
+
+    pub async fn update_user_task_occurrence(
+        &self,
+        user_id: &ObjectId,
+        time_zone: &Tz,
+        task_id: &ObjectId,
+        request: UpdateOccurrenceRequest,
+    ) -> Result<bool, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let rule = task
+            .recurrence
+            .as_ref()
+            .ok_or(TaskServiceError::TaskNotRecurring)?;
+
+        let occurrence = request.occurrence_start;
+        if !recurrence::expand(task.start_date, rule, time_zone, occurrence, occurrence)
+            .contains(&occurrence)
+        {
+            return Err(TaskServiceError::OccurrenceNotFound);
+        }
+
+        let scope = task.scope();
+        let mut overrides = task.occurrence_overrides.clone();
+        overrides.retain(|exception| exception.occurrence_start != occurrence);
+        if request.cancelled || request.status.is_some() {
+            overrides.push(OccurrenceOverride {
+                occurrence_start: occurrence,
+                status: request.status,
+                cancelled: request.cancelled,
+            });
+        }
+
+        let result = self
+            .repository
+            .set_occurrence_overrides(&scope, task_id, &overrides)
+            .await?;
+        if result {
+            self.record_update(user_id, &task).await;
+        }
         Ok(result)
     }
     
-    pub async fn delete_user_task(&self, task_id: &ObjectId) -> Result<bool, TaskServiceError> {
-        if self.repository.get_task_by_id(task_id).await?.is_none() {
-            return Err(TaskServiceError::TaskNotFound);
+    pub async fn delete_user_task(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+    ) -> Result<bool, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+
+        let scope = task.scope();
+        let result = self.repository.delete_task(&scope, task_id).await?;
+        if result {
+            if task.goal_id.is_some() {
+                self.goal_service.sync_progress(&scope).await;
+            }
+            self.comment_repository.delete_task_comments(task_id).await?;
+            let changes = activity::changes(Some(&task), None);
+            self.record_activity(user_id, task_id, ActivityAction::Deleted, changes)
+                .await;
         }
 
-        let result = self.repository.delete_task(task_id).await?;
+        Ok(result)
+    }
+
+    pub async fn get_user_task(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+    ) -> Result<Task, TaskServiceError> {
+        self.get_accessible_task(user_id, task_id, Access::Read)
+            .await
+    }
+
+    /// Assigns a workspace task to one of its members, or unassigns it with `None`.
+    pub async fn assign_task(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        assignee_id: Option<ObjectId>,
+    ) -> Result<bool, TaskServiceError> {
+        let task = self
+            .get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let scope = task.scope();
+        if let Some(assignee_id) = &assignee_id {
+            self.ensure_assignable(&scope, assignee_id).await?;
+        }
 
+        let result = self
+            .repository
+            .set_assignee(&scope, task_id, assignee_id.as_ref())
+            .await?;
+        if result {
+            let changes = vec![FieldChange {
+                field: "assignee_id".to_string(),
+                before: task.assignee_id.map(|id| id.to_hex()).into(),
+                after: assignee_id.map(|id| id.to_hex()).into(),
+            }];
+            self.record_activity(user_id, task_id, ActivityAction::Updated, changes)
+                .await;
+        }
         Ok(result)
     }
 
-    pub async fn get_all_user_tasks(&self, &user_id: &ObjectId) -> Result<Vec<Task>, Error> {
-        self.repository.get_all_user_tasks(&user_id).await
+    pub async fn add_comment(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        request: CreateCommentRequest,
+    ) -> Result<ObjectId, TaskServiceError> {
+        self.get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+
+        let comment = TaskComment {
+            id: None,
+            task_id: *task_id,
+            user_id: *user_id,
+            body: request.body,
+            created_at: Utc::now(),
+        };
+        Ok(self.comment_repository.create_comment(comment).await?)
     }
 
-    pub async fn count_tasks_by_category_and_status(
+    pub async fn get_task_comments(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+    ) -> Result<Vec<TaskComment>, TaskServiceError> {
+        self.get_accessible_task(user_id, task_id, Access::Read)
+            .await?;
+        Ok(self.comment_repository.get_task_comments(task_id).await?)
+    }
+
+    /// Deletes a comment of the caller. Other members' comments are left alone.
+    pub async fn delete_comment(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        comment_id: &ObjectId,
+    ) -> Result<bool, TaskServiceError> {
+        self.get_accessible_task(user_id, task_id, Access::Write)
+            .await?;
+        let comment = self
+            .comment_repository
+            .find_comment(task_id, comment_id)
+            .await?
+            .ok_or(TaskServiceError::CommentNotFound)?;
+        if comment.user_id != *user_id {
+            return Err(TaskServiceError::CommentForbidden);
+        }
+
+        Ok(self.comment_repository.delete_comment(comment_id).await?)
+    }
+
+    /// History and comments of a task, oldest first.
+    pub async fn get_task_timeline(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+    ) -> Result<Vec<TimelineEntry>, TaskServiceError> {
+        self.get_accessible_task(user_id, task_id, Access::Read)
+            .await?;
+
+        let activity = self.activity_repository.get_task_activity(task_id).await?;
+        let comments = self.comment_repository.get_task_comments(task_id).await?;
+        let mut timeline: Vec<TimelineEntry> = activity
+            .into_iter()
+            .map(TimelineEntry::Activity)
+            .chain(comments.into_iter().map(TimelineEntry::Comment))
+            .collect();
+        timeline.sort_by_key(TimelineEntry::created_at);
+        Ok(timeline)
+    }
+
+    /// Appends to the task history. Updates that changed nothing are not recorded. The change
+    /// itself is already saved, so a failure is logged rather than failing the request.
+    async fn record_activity(
+        &self,
+        user_id: &ObjectId,
+        task_id: &ObjectId,
+        action: ActivityAction,
+        changes: Vec<FieldChange>,
+    ) {
+        if action == ActivityAction::Updated && changes.is_empty() {
+            return;
+        }
+
+        let activity = TaskActivity {
+            id: None,
+            task_id: *task_id,
+            user_id: Some(*user_id),
+            action,
+            changes,
+            created_at: Utc::now(),
+        };
+        if let Err(err) = self.activity_repository.record(activity).await {
+            error!("Failed to record activity of task {}: {}", task_id, err);
+        }
+    }
+
+    /// Records the changes between `before` and the task as now stored.
+    async fn record_update(&self, user_id: &ObjectId, before: &Task) {
+        let task_id = before.id.unwrap();
+        match self.repository.get_task_by_id(&task_id).await {
+            Ok(Some(after)) => {
+                let changes = activity::changes(Some(before), Some(&after));
+                self.record_activity(user_id, &task_id, ActivityAction::Updated, changes)
+                    .await
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to record activity of task {}: {}", task_id, err),
+        }
+    }
+
+    /// Checks the category belongs to the same scope as the task. Categories of other
+    /// scopes are reported as missing, so their ids reveal nothing.
+    async fn ensure_category(
+        &self,
+        scope: &Scope,
+        category_id: &ObjectId,
+    ) -> Result<(), TaskServiceError> {
+        match self.category_repository.get_category_by_id(category_id).await? {
+            Some(category) if category.scope() == *scope => Ok(()),
+            _ => Err(TaskServiceError::CategoryNotFound),
+        }
+    }
+
+    /// Checks the goal belongs to the same scope as the task, reporting goals of other
+    /// scopes as missing like `ensure_category`.
+    async fn ensure_goal(
+        &self,
+        scope: &Scope,
+        goal_id: &ObjectId,
+    ) -> Result<(), TaskServiceError> {
+        if self.goal_service.is_in_scope(scope, goal_id).await? {
+            Ok(())
+        } else {
+            Err(TaskServiceError::GoalNotFound)
+        }
+    }
+
+    async fn ensure_assignable(
+        &self,
+        scope: &Scope,
+        assignee_id: &ObjectId,
+    ) -> Result<(), TaskServiceError> {
+        let Scope::Workspace(workspace_id) = scope else {
+            return Err(TaskServiceError::TaskNotAssignable);
+        };
+        if !self.access.is_member(workspace_id, assignee_id).await? {
+            return Err(TaskServiceError::AssigneeNotMember);
+        }
+        Ok(())
+    }
+
+    async fn get_accessible_task(
         &self,
         user_id: &ObjectId,
+        task_id: &ObjectId,
+        access: Access,
+    ) -> Result<Task, TaskServiceError> {
+        let task = self
+            .repository
+            .get_task_by_id(task_id)
+            .await?
+            .ok_or(TaskServiceError::TaskNotFound)?;
+
+        if !self.access.allows(user_id, &task.scope(), access).await? {
+            return Err(TaskServiceError::TaskForbidden);
+        }
+        Ok(task)
+    }
+
+    pub async fn get_user_tasks_page(
+        &self,
+        scope: &Scope,
+        query: TaskListQuery,
+    ) -> Result<(Vec<Task>, Option<String>), TaskServiceError> {
+        let cursor = match &query.cursor {
+            Some(cursor) => Some(
+                Cursor::decode(cursor, query.sort.as_str())
+                    .ok_or(TaskServiceError::InvalidCursor)?,
+            ),
+            None => None,
+        };
+        // Clients that predate pagination send neither and still get every task.
+        let limit = match (query.limit, &cursor) {
+            (None, None) => None,
+            (limit, _) => Some(limit.unwrap_or(DEFAULT_PAGE_SIZE)),
+        };
+        let filter = TaskFilter {
+            status: query.status,
+            category_id: query.category_id,
+            assignee_id: query.assignee_id,
+            start_from: query.start_from,
+            start_to: query.start_to,
+            end_from: query.end_from,
+            end_to: query.end_to,
+            text: query.q,
+        };
+
+        let (tasks, next_cursor) = self
+            .repository
+            .find_user_tasks(
+                scope,
+                &filter,
+                query.sort,
+                query.direction,
+                cursor.as_ref(),
+                limit,
+            )
+            .await?;
+        Ok((tasks, next_cursor.map(|cursor| cursor.encode())))
+    }
+
+    pub async fn get_user_tasks_by_goal(
+        &self,
+        scope: &Scope,
+        goal_id: &ObjectId,
+    ) -> Result<Vec<Task>, Error> {
+        self.repository.get_user_tasks_by_goal(scope, goal_id).await
+    }
+
+    pub async fn count_tasks_by_goal(
+        &self,
+        scope: &Scope,
+    ) -> Result<Vec<TaskCountByGoal>, Error> {
+        self.repository.count_tasks_by_goal(scope).await
+    }
+
+    pub async fn count_tasks_by_category_and_status(
+        &self,
+        scope: &Scope,
     ) -> Result<Vec<TaskStatsByCategory>, Error> {
-        let task_stats = self.repository.count_tasks_by_status(user_id).await?;
+        let task_stats = self.repository.count_tasks_by_status(scope).await?;
 
         let mut category_map: HashMap<String, TaskStatsByCategory> = HashMap::new();
 
@@ -172,18 +859,1001 @@ impl TaskService {
                     completed_count: 0,
                     postponed_count: 0,
                     partially_completed_count: 0,
+                    pending_count: 0,
                 });
 
             match task.status.as_str() {
                 "EXECUTADA" => entry.completed_count += task.count,
                 "ADIADA" => entry.postponed_count += task.count,
                 "PARCIALMENTE_EXECUTADA" => entry.partially_completed_count += task.count,
+                "PENDENTE" => entry.pending_count += task.count,
                 _ => (),
             }
         }
 
-        let result: Vec<TaskStatsByCategory> = category_map.into_iter().map(|(_, v)| v).collect();
+        let result: Vec<TaskStatsByCategory> = category_map.into_values().collect();
 
         Ok(result)
     }
 }
+
+/// Ids of the task's subtasks, in checklist order.
+fn subtask_order(task: &Task) -> Vec<ObjectId> {
+    let mut subtasks: Vec<&Subtask> = task.subtasks.iter().collect();
+    subtasks.sort_by_key(|subtask| subtask.position);
+    subtasks.into_iter().map(|subtask| subtask.id).collect()
+}
+
+/// When a reminder `offset` before the task fires: before its start, or for recurring tasks
+/// before the first non-cancelled occurrence whose reminder is still ahead.
+fn reminder_time(
+    start: DateTime<Utc>,
+    rule: Option<&RecurrenceRule>,
+    overrides: &[OccurrenceOverride],
+    time_zone: &Tz,
+    offset: Duration,
+) -> DateTime<Utc> {
+    let Some(rule) = rule else {
+        return start - offset;
+    };
+
+    let mut current = Utc::now() + offset - Duration::seconds(1);
+    while let Some(next) = recurrence::next_after(start, rule, time_zone, current) {
+        let cancelled = overrides
+            .iter()
+            .any(|exception| exception.occurrence_start == next && exception.cancelled);
+        if !cancelled {
+            return next - offset;
+        }
+        current = next;
+    }
+    // The rule has ended, so there is nothing left to remind about
+    start - offset
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::category::models::{Category, Color};
+    use crate::modules::notification::models::TimeUnit;
+    use crate::modules::task::models::Status;
+    use crate::modules::workspace::{
+        access::WorkspaceAccess,
+        models::{Scope, Workspace, WorkspaceMember, WorkspaceRole},
+    };
+
+    use super::*;
+
+    fn task_service(repositories: &Repositories) -> TaskService {
+        TaskService::new(
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            repositories.task_comments.clone(),
+            repositories.task_activity.clone(),
+            GoalService::new(
+                repositories.goals.clone(),
+                repositories.tasks.clone(),
+                repositories.categories.clone(),
+                WorkspaceAccess::new(repositories.workspaces.clone()),
+            ),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        )
+    }
+
+    async fn create_category(
+        repositories: &Repositories,
+        user_id: ObjectId,
+        workspace_id: Option<ObjectId>,
+        title: &str,
+    ) -> ObjectId {
+        repositories
+            .categories
+            .create_category(Category {
+                id: None,
+                user_id,
+                workspace_id,
+                title: title.to_string(),
+                color: Color::Green,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn create_workspace(
+        repositories: &Repositories,
+        owner_id: ObjectId,
+        members: &[(ObjectId, WorkspaceRole)],
+    ) -> ObjectId {
+        let member = |user_id, role| WorkspaceMember {
+            user_id,
+            role,
+            added_at: Utc::now(),
+        };
+        let mut all_members = vec![member(owner_id, WorkspaceRole::Owner)];
+        all_members.extend(members.iter().map(|&(user_id, role)| member(user_id, role)));
+        repositories
+            .workspaces
+            .create_workspace(Workspace {
+                id: None,
+                name: "Team".to_string(),
+                owner_id,
+                members: all_members,
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap()
+    }
+
+    fn create_request(title: &str, category_id: ObjectId, status: &str) -> CreateTaskRequest {
+        serde_json::from_value(json!({
+            "title": title,
+            "description": "Description",
+            "start_date": "2024-03-01T10:00:00Z",
+            "end_date": "2024-03-01T11:00:00Z",
+            "status": status,
+            "category_id": category_id.to_hex(),
+        }))
+        .unwrap()
+    }
+
+    fn update_request(value: serde_json::Value) -> UpdateTaskRequest {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tasks_of_another_user_are_forbidden() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let owner_id = ObjectId::new();
+        let other_id = ObjectId::new();
+        let category_id = create_category(&repositories, owner_id, None, "Work").await;
+
+        let task_id = service
+            .create_task_for_user(
+                &owner_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service
+                .get_user_task(&owner_id, &task_id)
+                .await
+                .unwrap()
+                .title,
+            "Report"
+        );
+        assert!(matches!(
+            service.get_user_task(&other_id, &task_id).await,
+            Err(TaskServiceError::TaskForbidden)
+        ));
+        assert!(matches!(
+            service
+                .update_user_task(
+                    &other_id,
+                    &Tz::UTC,
+                    &task_id,
+                    update_request(json!({ "title": "Mine" }))
+                )
+                .await,
+            Err(TaskServiceError::TaskForbidden)
+        ));
+        assert!(matches!(
+            service.delete_user_task(&other_id, &task_id).await,
+            Err(TaskServiceError::TaskForbidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn categories_of_another_scope_are_rejected() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let other_id = ObjectId::new();
+        let own_category = create_category(&repositories, user_id, None, "Work").await;
+        let other_category = create_category(&repositories, other_id, None, "Theirs").await;
+
+        assert!(matches!(
+            service
+                .create_task_for_user(
+                    &user_id,
+                    &Tz::UTC,
+                    create_request("Report", other_category, "ADIADA")
+                )
+                .await,
+            Err(TaskServiceError::CategoryNotFound)
+        ));
+
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", own_category, "ADIADA"),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .update_user_task(
+                    &user_id,
+                    &Tz::UTC,
+                    &task_id,
+                    update_request(json!({ "category_id": other_category.to_hex() }))
+                )
+                .await,
+            Err(TaskServiceError::CategoryNotFound)
+        ));
+        assert_eq!(
+            service
+                .get_user_task(&user_id, &task_id)
+                .await
+                .unwrap()
+                .category_id,
+            own_category
+        );
+    }
+
+    #[tokio::test]
+    async fn goals_of_another_scope_are_rejected() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let other_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let other_goal = GoalService::new(
+            repositories.goals.clone(),
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        )
+        .create_goal_for_user(
+            other_id,
+            serde_json::from_value(json!({
+                "title": "Theirs",
+                "description": "Description",
+                "priority": "HIGH",
+            }))
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut request = create_request("Report", category_id, "ADIADA");
+        request.goal_id = Some(other_goal);
+        assert!(matches!(
+            service
+                .create_task_for_user(&user_id, &Tz::UTC, request)
+                .await,
+            Err(TaskServiceError::GoalNotFound)
+        ));
+
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .update_user_task(
+                    &user_id,
+                    &Tz::UTC,
+                    &task_id,
+                    update_request(json!({ "goal_id": other_goal.to_hex() }))
+                )
+                .await,
+            Err(TaskServiceError::GoalNotFound)
+        ));
+        assert!(service
+            .get_user_task(&user_id, &task_id)
+            .await
+            .unwrap()
+            .goal_id
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn duplicate_titles_are_rejected_within_a_scope() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+
+        service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service
+                .create_task_for_user(
+                    &user_id,
+                    &Tz::UTC,
+                    create_request("Report", category_id, "ADIADA")
+                )
+                .await,
+            Err(TaskServiceError::TaskAlreadyExists)
+        ));
+    }
+
+    #[tokio::test]
+    async fn workspace_viewers_can_read_but_not_write() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let owner_id = ObjectId::new();
+        let viewer_id = ObjectId::new();
+        let workspace_id = create_workspace(
+            &repositories,
+            owner_id,
+            &[(viewer_id, WorkspaceRole::Viewer)],
+        )
+        .await;
+        let category_id =
+            create_category(&repositories, owner_id, Some(workspace_id), "Shared").await;
+
+        let mut request = create_request("Plan", category_id, "ADIADA");
+        request.workspace_id = Some(workspace_id);
+        let task_id = service
+            .create_task_for_user(&owner_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+
+        assert!(service.get_user_task(&viewer_id, &task_id).await.is_ok());
+        assert!(matches!(
+            service.delete_user_task(&viewer_id, &task_id).await,
+            Err(TaskServiceError::TaskForbidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn workspace_tasks_only_take_workspace_categories() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let owner_id = ObjectId::new();
+        let workspace_id = create_workspace(&repositories, owner_id, &[]).await;
+        let other_workspace_id = create_workspace(&repositories, owner_id, &[]).await;
+        let personal_category = create_category(&repositories, owner_id, None, "Mine").await;
+        let shared_category =
+            create_category(&repositories, owner_id, Some(workspace_id), "Shared").await;
+        let other_category =
+            create_category(&repositories, owner_id, Some(other_workspace_id), "Other").await;
+
+        for category_id in [personal_category, other_category] {
+            let mut request = create_request("Plan", category_id, "ADIADA");
+            request.workspace_id = Some(workspace_id);
+            assert!(matches!(
+                service
+                    .create_task_for_user(&owner_id, &Tz::UTC, request)
+                    .await,
+                Err(TaskServiceError::CategoryNotFound)
+            ));
+        }
+        assert!(matches!(
+            service
+                .create_task_for_user(
+                    &owner_id,
+                    &Tz::UTC,
+                    create_request("Plan", shared_category, "ADIADA")
+                )
+                .await,
+            Err(TaskServiceError::CategoryNotFound)
+        ));
+
+        let mut request = create_request("Plan", shared_category, "ADIADA");
+        request.workspace_id = Some(workspace_id);
+        let task_id = service
+            .create_task_for_user(&owner_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+        for category_id in [personal_category, other_category] {
+            assert!(matches!(
+                service
+                    .update_user_task(
+                        &owner_id,
+                        &Tz::UTC,
+                        &task_id,
+                        update_request(json!({ "category_id": category_id.to_hex() }))
+                    )
+                    .await,
+                Err(TaskServiceError::CategoryNotFound)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn changes_are_recorded_in_the_timeline() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "status": "EXECUTADA" })),
+            )
+            .await
+            .unwrap();
+        // Nothing changes, so nothing is recorded.
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "status": "EXECUTADA" })),
+            )
+            .await
+            .unwrap();
+
+        let actions: Vec<ActivityAction> = service
+            .get_task_timeline(&user_id, &task_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TimelineEntry::Activity(activity) => Some(activity.action),
+                TimelineEntry::Comment(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            vec![ActivityAction::Created, ActivityAction::Updated]
+        );
+    }
+
+    #[tokio::test]
+    async fn checklist_and_occurrence_edits_are_recorded() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let mut request = create_request("Report", category_id, "ADIADA");
+        request.recurrence = Some(serde_json::from_value(json!({ "frequency": "DAILY" })).unwrap());
+        let task_id = service
+            .create_task_for_user(&user_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+
+        let subtask_id = service
+            .add_subtask(
+                &user_id,
+                &task_id,
+                serde_json::from_value(json!({ "title": "Draft" })).unwrap(),
+            )
+            .await
+            .unwrap();
+        service
+            .update_subtask(
+                &user_id,
+                &task_id,
+                &subtask_id,
+                serde_json::from_value(json!({ "done": true })).unwrap(),
+            )
+            .await
+            .unwrap();
+        service
+            .delete_subtask(&user_id, &task_id, &subtask_id)
+            .await
+            .unwrap();
+        service
+            .update_user_task_occurrence(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                serde_json::from_value(json!({
+                    "occurrence_start": "2024-03-02T10:00:00Z",
+                    "cancelled": true,
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let fields: Vec<Vec<String>> = service
+            .get_task_timeline(&user_id, &task_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                TimelineEntry::Activity(activity) if activity.action == ActivityAction::Updated => {
+                    Some(
+                        activity
+                            .changes
+                            .into_iter()
+                            .map(|change| change.field)
+                            .collect(),
+                    )
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                vec!["subtasks"],
+                vec!["subtasks"],
+                vec!["subtasks"],
+                vec!["occurrence_overrides"],
+            ]
+        );
+    }
+
+    /// Fails every write, like a history collection that is unavailable.
+    struct FailingActivityRepository;
+
+    #[async_trait::async_trait]
+    impl TaskActivityRepository for FailingActivityRepository {
+        async fn record(&self, _activity: TaskActivity) -> Result<ObjectId, Error> {
+            Err(std::io::Error::other("unavailable").into())
+        }
+
+        async fn get_task_activity(&self, _task_id: &ObjectId) -> Result<Vec<TaskActivity>, Error> {
+            Ok(Vec::new())
+        }
+
+        async fn delete_tasks_activity(&self, _task_ids: &[ObjectId]) -> Result<u64, Error> {
+            Ok(0)
+        }
+
+        async fn anonymize_user_activity(&self, _user_id: &ObjectId) -> Result<u64, Error> {
+            Ok(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn saved_changes_succeed_when_the_history_fails() {
+        let repositories = Repositories::in_memory();
+        let service = TaskService::new(
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            repositories.task_comments.clone(),
+            Arc::new(FailingActivityRepository),
+            GoalService::new(
+                repositories.goals.clone(),
+                repositories.tasks.clone(),
+                repositories.categories.clone(),
+                WorkspaceAccess::new(repositories.workspaces.clone()),
+            ),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        );
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+        assert!(service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "status": "EXECUTADA" })),
+            )
+            .await
+            .unwrap());
+        assert!(service.delete_user_task(&user_id, &task_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn null_clears_the_recurrence_rule() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                create_request("Report", category_id, "ADIADA"),
+            )
+            .await
+            .unwrap();
+        async fn recurrence(
+            service: &TaskService,
+            user_id: ObjectId,
+            task_id: ObjectId,
+        ) -> Option<RecurrenceRule> {
+            service
+                .get_user_task(&user_id, &task_id)
+                .await
+                .unwrap()
+                .recurrence
+        }
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "recurrence": { "frequency": "DAILY" } })),
+            )
+            .await
+            .unwrap();
+        assert!(recurrence(&service, user_id, task_id).await.is_some());
+
+        // A missing field leaves the rule alone
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "title": "Daily report" })),
+            )
+            .await
+            .unwrap();
+        assert!(recurrence(&service, user_id, task_id).await.is_some());
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "recurrence": null })),
+            )
+            .await
+            .unwrap();
+        assert!(recurrence(&service, user_id, task_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn moving_a_task_reschedules_its_reminder() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let mut request = create_request("Report", category_id, "ADIADA");
+        request.notification_time_unit = Some(TimeUnit::Minute);
+        request.notification_time_value = Some(30);
+        let task_id = service
+            .create_task_for_user(&user_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+        repositories
+            .tasks
+            .mark_notification_as_sent(&task_id)
+            .await
+            .unwrap();
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "start_date": "2024-03-02T10:00:00Z" })),
+            )
+            .await
+            .unwrap();
+        let notification = service
+            .get_user_task(&user_id, &task_id)
+            .await
+            .unwrap()
+            .notification
+            .unwrap();
+        assert_eq!(
+            notification.scheduled_time.to_rfc3339(),
+            "2024-03-02T09:30:00+00:00"
+        );
+        assert!(!notification.sent);
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({
+                    "notification_time_unit": null,
+                    "notification_time_value": null,
+                })),
+            )
+            .await
+            .unwrap();
+        assert!(service
+            .get_user_task(&user_id, &task_id)
+            .await
+            .unwrap()
+            .notification
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn lists_are_paged_only_on_request() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let scope = Scope::User(user_id);
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        for index in 0..25 {
+            service
+                .create_task_for_user(
+                    &user_id,
+                    &Tz::UTC,
+                    create_request(&format!("Task {index:02}"), category_id, "ADIADA"),
+                )
+                .await
+                .unwrap();
+        }
+        let list_query =
+            |value: serde_json::Value| -> TaskListQuery { serde_json::from_value(value).unwrap() };
+
+        let (tasks, next_cursor) = service
+            .get_user_tasks_page(&scope, list_query(json!({})))
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 25);
+        assert!(next_cursor.is_none());
+
+        let (first_page, next_cursor) = service
+            .get_user_tasks_page(&scope, list_query(json!({ "sort": "title", "limit": 10 })))
+            .await
+            .unwrap();
+        let next_cursor = next_cursor.unwrap();
+        assert_eq!(first_page.len(), 10);
+
+        // Without a limit, a cursor continues with pages of the default size
+        let (second_page, _) = service
+            .get_user_tasks_page(
+                &scope,
+                list_query(json!({ "sort": "title", "cursor": next_cursor })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(second_page.len(), 15);
+        assert_eq!(second_page[0].title, "Task 10");
+
+        assert!(matches!(
+            service
+                .get_user_tasks_page(
+                    &scope,
+                    list_query(json!({ "sort": "status", "cursor": next_cursor })),
+                )
+                .await,
+            Err(TaskServiceError::InvalidCursor)
+        ));
+    }
+
+    #[tokio::test]
+    async fn goal_progress_follows_task_changes() {
+        use crate::modules::goal::models::Status as GoalStatus;
+
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let goals = GoalService::new(
+            repositories.goals.clone(),
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+        );
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let goal_id = goals
+            .create_goal_for_user(
+                user_id,
+                serde_json::from_value(json!({
+                    "title": "Ship",
+                    "description": "Description",
+                    "priority": "HIGH",
+                    "auto_status": true,
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let mut request = create_request("Report", category_id, "ADIADA");
+        request.goal_id = Some(goal_id);
+        let task_id = service
+            .create_task_for_user(&user_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "status": "EXECUTADA" })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            goals.get_user_goal(user_id, goal_id).await.unwrap().status,
+            GoalStatus::Reached
+        );
+
+        service
+            .update_user_task(
+                &user_id,
+                &Tz::UTC,
+                &task_id,
+                update_request(json!({ "goal_id": null })),
+            )
+            .await
+            .unwrap();
+        assert!(service
+            .get_user_task(&user_id, &task_id)
+            .await
+            .unwrap()
+            .goal_id
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn checklist_edits_derive_the_status() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let category_id = create_category(&repositories, user_id, None, "Work").await;
+        let mut request = create_request("Report", category_id, "EXECUTADA");
+        request.derive_status = true;
+        let task_id = service
+            .create_task_for_user(&user_id, &Tz::UTC, request)
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for title in ["Draft", "Review", "Send"] {
+            let request = serde_json::from_value(json!({ "title": title })).unwrap();
+            ids.push(
+                service
+                    .add_subtask(&user_id, &task_id, request)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let set_done = |subtask_id: ObjectId, done: bool| {
+            let service = &service;
+            async move {
+                let request = serde_json::from_value(json!({ "done": done })).unwrap();
+                service
+                    .update_subtask(&user_id, &task_id, &subtask_id, request)
+                    .await
+                    .unwrap();
+                service
+                    .get_user_task(&user_id, &task_id)
+                    .await
+                    .unwrap()
+                    .status
+            }
+        };
+
+        // A fresh checklist has nothing done yet
+        assert_eq!(
+            service
+                .get_user_task(&user_id, &task_id)
+                .await
+                .unwrap()
+                .status,
+            Status::Pendente
+        );
+        assert_eq!(set_done(ids[0], true).await, Status::ParcialmenteExecutada);
+        assert_eq!(set_done(ids[1], true).await, Status::ParcialmenteExecutada);
+        assert_eq!(set_done(ids[2], true).await, Status::Executada);
+        for id in &ids {
+            set_done(*id, false).await;
+        }
+        assert_eq!(
+            service
+                .get_user_task(&user_id, &task_id)
+                .await
+                .unwrap()
+                .status,
+            Status::Pendente
+        );
+
+        let request = serde_json::from_value(json!({ "position": 0 })).unwrap();
+        service
+            .update_subtask(&user_id, &task_id, &ids[2], request)
+            .await
+            .unwrap();
+        service
+            .delete_subtask(&user_id, &task_id, &ids[0])
+            .await
+            .unwrap();
+        let checklist: Vec<(ObjectId, u32)> = service
+            .get_user_task_subtasks(&user_id, &task_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|subtask| (subtask.id, subtask.position))
+            .collect();
+        assert_eq!(checklist, vec![(ids[2], 0), (ids[1], 1)]);
+    }
+
+    /// Mirrors the aggregation: tasks are grouped by category title and status, and tasks
+    /// whose category is gone are dropped by the `$unwind` after the `$lookup`.
+    #[tokio::test]
+    async fn counts_by_status_match_the_aggregation() {
+        let repositories = Repositories::in_memory();
+        let service = task_service(&repositories);
+        let user_id = ObjectId::new();
+        let work = create_category(&repositories, user_id, None, "Work").await;
+        let home = create_category(&repositories, user_id, None, "Home").await;
+        let gone = create_category(&repositories, user_id, None, "Gone").await;
+        let other_user = ObjectId::new();
+        let other_category = create_category(&repositories, other_user, None, "Work").await;
+
+        for (title, category_id, status) in [
+            ("a", work, "EXECUTADA"),
+            ("b", work, "EXECUTADA"),
+            ("c", work, "ADIADA"),
+            ("d", home, "PARCIALMENTE_EXECUTADA"),
+            ("e", gone, "EXECUTADA"),
+        ] {
+            service
+                .create_task_for_user(
+                    &user_id,
+                    &Tz::UTC,
+                    create_request(title, category_id, status),
+                )
+                .await
+                .unwrap();
+        }
+        service
+            .create_task_for_user(
+                &other_user,
+                &Tz::UTC,
+                create_request("f", other_category, "EXECUTADA"),
+            )
+            .await
+            .unwrap();
+        repositories
+            .categories
+            .delete_category(&Scope::User(user_id), gone)
+            .await
+            .unwrap();
+
+        let mut counts: Vec<(String, String, i32)> = repositories
+            .tasks
+            .count_tasks_by_status(&Scope::User(user_id))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|count| (count.category, count.status, count.count))
+            .collect();
+        counts.sort();
+        assert_eq!(
+            counts,
+            vec![
+                ("Home".to_string(), "PARCIALMENTE_EXECUTADA".to_string(), 1),
+                ("Work".to_string(), "ADIADA".to_string(), 1),
+                ("Work".to_string(), "EXECUTADA".to_string(), 2),
+            ]
+        );
+
+        let mut stats = service
+            .count_tasks_by_category_and_status(&Scope::User(user_id))
+            .await
+            .unwrap();
+        stats.sort_by(|a, b| a.category.cmp(&b.category));
+        let work_stats = &stats[1];
+        assert_eq!(stats.len(), 2);
+        assert_eq!(work_stats.category, "Work");
+        assert_eq!(work_stats.completed_count, 2);
+        assert_eq!(work_stats.postponed_count, 1);
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use log::{error, info};
+use mongodb::{bson::oid::ObjectId, error::Error};
+
+use crate::modules::{
+    auth::repository::{LoginAttemptRepository, OneTimeTokenRepository, RefreshTokenRepository},
+    category::repository::CategoryRepository,
+    goal::repository::GoalRepository,
+    notification::repository::InboxRepository,
+    task::repository::{TaskActivityRepository, TaskCommentRepository, TaskRepository},
+    workspace::{models::Scope, service::WorkspaceService},
+};
+
+use super::models::User;
+use super::repository::UserRepository;
+
+/// Deletes accounts and everything they own. The user is marked deleted before anything
+/// else, so an account whose deletion fails part-way can no longer be used; the deletion is
+/// finished by `finish_pending_deletions` on the next start.
+pub struct AccountDeletionService {
+    user_repository: Arc<dyn UserRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+    goal_repository: Arc<dyn GoalRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+    comment_repository: Arc<dyn TaskCommentRepository>,
+    activity_repository: Arc<dyn TaskActivityRepository>,
+    workspace_service: WorkspaceService,
+    inbox_repository: InboxRepository,
+    token_repository: Arc<dyn RefreshTokenRepository>,
+    one_time_token_repository: Arc<dyn OneTimeTokenRepository>,
+    login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+}
+
+impl AccountDeletionService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+        goal_repository: Arc<dyn GoalRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+        comment_repository: Arc<dyn TaskCommentRepository>,
+        activity_repository: Arc<dyn TaskActivityRepository>,
+        workspace_service: WorkspaceService,
+        inbox_repository: InboxRepository,
+        token_repository: Arc<dyn RefreshTokenRepository>,
+        one_time_token_repository: Arc<dyn OneTimeTokenRepository>,
+        login_attempt_repository: Arc<dyn LoginAttemptRepository>,
+    ) -> Self {
+        AccountDeletionService {
+            user_repository,
+            category_repository,
+            goal_repository,
+            task_repository,
+            comment_repository,
+            activity_repository,
+            workspace_service,
+            inbox_repository,
+            token_repository,
+            one_time_token_repository,
+            login_attempt_repository,
+        }
+    }
+
+    pub async fn delete_account(&self, user: &User) -> Result<(), Error> {
+        let user_id = user.id.unwrap();
+        self.user_repository.mark_deleted(&user_id).await?;
+        self.delete_account_data(user).await
+    }
+
+    /// Finishes the deletions that failed part-way, logging the ones that fail again.
+    pub async fn finish_pending_deletions(&self) {
+        let users = match self.user_repository.find_deleted_users().await {
+            Ok(users) => users,
+            Err(err) => {
+                error!("Failed to find accounts pending deletion: {}", err);
+                return;
+            }
+        };
+        for user in users {
+            match self.delete_account_data(&user).await {
+                Ok(()) => info!("Finished deleting user {}", user.id.unwrap()),
+                Err(err) => error!("Failed to delete user {}: {}", user.id.unwrap(), err),
+            }
+        }
+    }
+
+    /// Sessions are revoked first; the user record goes last, so a failure part-way can be
+    /// retried.
+    async fn delete_account_data(&self, user: &User) -> Result<(), Error> {
+        let user_id = user.id.unwrap();
+        let scope = Scope::User(user_id);
+        self.token_repository.delete_user_tokens(&user_id).await?;
+        self.one_time_token_repository
+            .delete_user_tokens(&user_id)
+            .await?;
+        self.login_attempt_repository
+            .clear_email(&user.email)
+            .await?;
+        self.inbox_repository
+            .delete_all_user_messages(&user_id)
+            .await?;
+        self.workspace_service
+            .delete_user_workspaces(&user_id)
+            .await?;
+        let task_ids: Vec<ObjectId> = self
+            .task_repository
+            .get_all_user_tasks(&scope)
+            .await?
+            .into_iter()
+            .filter_map(|task| task.id)
+            .collect();
+        // History of personal tasks goes with them; changes made in workspaces stay there.
+        self.activity_repository
+            .delete_tasks_activity(&task_ids)
+            .await?;
+        self.task_repository.delete_all_user_tasks(&scope).await?;
+        self.comment_repository
+            .delete_all_user_comments(&user_id)
+            .await?;
+        self.activity_repository
+            .anonymize_user_activity(&user_id)
+            .await?;
+        self.goal_repository.delete_all_user_goals(&scope).await?;
+        self.category_repository
+            .delete_all_user_categories(&scope)
+            .await?;
+        self.user_repository.delete_user(&user_id).await?;
+        Ok(())
+    }
+}
@@ -1,20 +1,92 @@
-use serde::Deserialize;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::modules::notification::models::NotificationChannelKind;
+
+use super::models::{Language, Role, User};
+use super::types::{Email, Password, PhoneNumber};
+
 #[derive(Deserialize, Validate)]
 pub struct UserSignUpRequest {
     #[validate(length(min = 3))]
     pub name: String,
-    #[validate(email)]
-    pub email: String,
-    #[validate(length(min = 8))]
-    pub password: String,
-    #[validate(length(min = 10, max = 15))]
-    pub phone: String,
+    pub email: Email,
+    pub password: Password,
+    pub phone: PhoneNumber,
+    pub notification_channel: Option<NotificationChannelKind>,
+    #[validate(url)]
+    pub webhook_url: Option<String>,
+    pub time_zone: Option<Tz>,
+    pub language: Option<Language>,
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize)]
 pub struct UserExistsQuery {
-    #[validate(email)]
-    pub email: String,
-}
\ No newline at end of file
+    pub email: Email,
+}
+
+#[derive(Serialize)]
+pub struct ProfileResponse {
+    pub _id: String,
+    pub name: String,
+    pub email: Email,
+    pub phone: PhoneNumber,
+    pub time_zone: Tz,
+    pub language: Language,
+    pub notification_channel: NotificationChannelKind,
+    pub webhook_url: Option<String>,
+    pub pending_email: Option<Email>,
+    pub verified: bool,
+    pub role: Role,
+}
+
+impl From<User> for ProfileResponse {
+    fn from(user: User) -> Self {
+        ProfileResponse {
+            _id: user.id.unwrap().to_string(),
+            name: user.name,
+            email: user.email,
+            phone: user.phone,
+            time_zone: user.time_zone,
+            language: user.language,
+            notification_channel: user.notification_channel,
+            webhook_url: user.webhook_url,
+            pending_email: user.pending_email.map(|pending| pending.email),
+            verified: user.verified,
+            role: user.role,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateProfileRequest {
+    #[validate(length(min = 3))]
+    pub name: Option<String>,
+    pub phone: Option<PhoneNumber>,
+    pub time_zone: Option<Tz>,
+    pub language: Option<Language>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: Password,
+    pub new_password: Password,
+}
+
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: Email,
+    pub password: Password,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ConfirmEmailRequest {
+    #[validate(length(min = 1))]
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteAccountRequest {
+    pub password: Password,
+}
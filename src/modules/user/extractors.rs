@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use chrono_tz::Tz;
+use log::error;
+
+use super::service::UserService;
+use crate::{helpers::api_response::ApiResponse, modules::auth::dto::AuthState, AppState};
+
+/// Time zone of the authenticated caller, in which their wall-clock dates are read and
+/// shown. Must run behind `auth::middlewares::authorize`.
+pub struct UserTimeZone(pub Tz);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for UserTimeZone {
+    type Rejection = ApiResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let user = parts
+            .extensions
+            .get::<AuthState>()
+            .ok_or_else(|| ApiResponse::unauthorized("Please add the token to the header"))?;
+
+        match UserService::new(state.repositories.users.clone())
+            .get_user_time_zone(&user.id)
+            .await
+        {
+            Ok(time_zone) => Ok(UserTimeZone(time_zone)),
+            Err(err) => {
+                error!("Error loading the time zone of user {}: {}", user.id, err);
+                Err(ApiResponse::server_error(None, None::<()>))
+            }
+        }
+    }
+}
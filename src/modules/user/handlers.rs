@@ -1,54 +1,106 @@
 use axum::extract::{Query, State};
-use axum::response::IntoResponse;
-use axum::{extract::Json, routing::{post, get}, Router};
+use axum::response::{IntoResponse, Response};
+use axum::{middleware, routing::{post, get, put}, Extension, Router};
+use log::error;
 use std::sync::Arc;
 use validator::Validate;
 
 use crate::helpers::api_response::ApiResponse;
+use crate::helpers::json_helper::JsonBody;
+use crate::modules::{
+    audit::{
+        extractors::ClientInfo,
+        handlers::audit_service,
+        models::{AuditAction, AuditEvent, AuditOutcome},
+    },
+    auth::{
+        self,
+        dto::AuthState,
+        handlers::auth_service,
+    },
+    mail::mailer::Mail,
+    notification::repository::InboxRepository,
+    rate_limit::{
+        limiter::{RateLimiter, SIGNUP_PER_IP, USER_EXISTS_PER_IP},
+        middlewares::limit_by_ip,
+    },
+    workspace::handlers::workspace_service,
+};
 use crate::AppState;
 
-use super::dto::{UserSignUpRequest, UserExistsQuery};
-use super::repository::UserRepository;
+use super::dto::{
+    ChangeEmailRequest, ChangePasswordRequest, ConfirmEmailRequest, DeleteAccountRequest,
+    ProfileResponse, UpdateProfileRequest, UserExistsQuery, UserSignUpRequest,
+};
+use super::deletion::AccountDeletionService;
 use super::service::{UserService, UserServiceError};
 
+/// With `user_exists_anti_enumeration` enabled a taken email gets the same answer as a new
+/// one, so signing up cannot be used to discover registered addresses.
 async fn sign_up(
     State(state): State<Arc<AppState>>,
-    Json(mut payload): Json<UserSignUpRequest>,
+    client: ClientInfo,
+    JsonBody(mut payload): JsonBody<UserSignUpRequest>,
 ) -> impl IntoResponse {
     payload.name = payload.name.trim().to_string();
-    payload.email = payload.email.trim().to_string();
-    payload.phone = payload.phone.trim().to_string();
 
     if let Err(errors) = payload.validate() {
         return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
     }
 
-    match UserService::new(UserRepository::new(&state.mongodb))
+    let anti_enumeration = state.config.auth.user_exists_anti_enumeration;
+    let (name, email) = (payload.name.clone(), payload.email.clone());
+    match UserService::new(state.repositories.users.clone())
         .create_user(payload)
         .await
     {
         Ok(id) => {
+            let event = AuditEvent::new(AuditAction::Signup, AuditOutcome::Success, &client);
+            audit_service(&state).record(event.by_owner(id)).await;
+
+            // The account exists either way; a lost email can be sent again.
+            if let Err(err) = auth_service(&state)
+                .send_email_verification(&id, &name, &email)
+                .await
+            {
+                error!("Failed to send verification email to user {}: {}", id, err);
+            }
+            if anti_enumeration {
+                return signup_received_response();
+            }
             ApiResponse::created("User created successfully", Some(id.to_string())).into_response()
         }
+        Err(UserServiceError::UserAlreadyExists) if anti_enumeration => signup_received_response(),
         Err(UserServiceError::UserAlreadyExists) => ApiResponse::unprocessable_entity(
             UserServiceError::UserAlreadyExists.to_string().as_str(),
             None::<()>,
         )
         .into_response(),
+        Err(err @ (UserServiceError::WebhookUrlRequired | UserServiceError::InvalidWebhookUrl(_))) => {
+            ApiResponse::bad_request(err.to_string().as_str(), None::<()>).into_response()
+        }
         Err(err) => {
             ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
         }
     }
 }
 
+fn signup_received_response() -> Response {
+    ApiResponse::created("Check your inbox to finish signing up", None::<()>).into_response()
+}
+
+/// With `user_exists_anti_enumeration` enabled the lookup is skipped and every email gets the
+/// same answer, so the endpoint cannot be used to discover registered addresses.
 async fn user_exists(
     State(state): State<Arc<AppState>>,
     Query(query): Query<UserExistsQuery>,
 ) -> impl IntoResponse {
-    let email = query.email.trim();
+    if state.config.auth.user_exists_anti_enumeration {
+        return ApiResponse::ok("Email check is unavailable", None::<()>).into_response();
+    }
 
-    match UserService::new(UserRepository::new(&state.mongodb))
-        .find_user_by_email(email)
+    match UserService::new(state.repositories.users.clone())
+        .find_user_by_email(&query.email)
         .await
     {
         Ok(Some(_user)) => {
@@ -63,9 +115,218 @@ async fn user_exists(
     }
 }
 
-pub fn handles() -> Router<Arc<AppState>> {
+fn user_error_response(err: UserServiceError) -> Response {
+    match err {
+        UserServiceError::UserNotFound => {
+            ApiResponse::not_found(err.to_string().as_str()).into_response()
+        }
+        UserServiceError::InvalidPassword => {
+            ApiResponse::unauthorized(err.to_string().as_str()).into_response()
+        }
+        UserServiceError::UserAlreadyExists | UserServiceError::InvalidEmailToken => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        err => ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response(),
+    }
+}
+
+async fn get_me(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match UserService::new(state.repositories.users.clone())
+        .get_user(&user.id)
+        .await
+    {
+        Ok(found) => ApiResponse::ok("User retrieved successfully", Some(ProfileResponse::from(found)))
+            .into_response(),
+        Err(err) => user_error_response(err),
+    }
+}
+
+async fn update_me(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    JsonBody(mut payload): JsonBody<UpdateProfileRequest>,
+) -> impl IntoResponse {
+    payload.name = payload.name.map(|name| name.trim().to_string());
+
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match UserService::new(state.repositories.users.clone())
+        .update_profile(&user.id, payload)
+        .await
+    {
+        Ok(result) => ApiResponse::ok("User updated successfully", Some(result)).into_response(),
+        Err(err) => user_error_response(err),
+    }
+}
+
+async fn change_password(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<ChangePasswordRequest>,
+) -> impl IntoResponse {
+    if let Err(err) = UserService::new(state.repositories.users.clone())
+        .change_password(&user.id, &payload.current_password, &payload.new_password)
+        .await
+    {
+        if let UserServiceError::InvalidPassword = err {
+            let event =
+                AuditEvent::new(AuditAction::PasswordChanged, AuditOutcome::Failure, &client)
+                    .detail(err.to_string());
+            audit_service(&state).record(event.by_owner(user.id)).await;
+        }
+        return user_error_response(err);
+    }
+    let event = AuditEvent::new(AuditAction::PasswordChanged, AuditOutcome::Success, &client);
+    audit_service(&state).record(event.by_owner(user.id)).await;
+
+    // Other sessions may belong to whoever knew the old password.
+    match state.repositories.refresh_tokens.clone()
+        .revoke_user_families(&user.id, Some(&user.sid))
+        .await
+    {
+        Ok(_) => ApiResponse::ok("Password changed successfully", None::<()>).into_response(),
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn change_email(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    JsonBody(payload): JsonBody<ChangeEmailRequest>,
+) -> impl IntoResponse {
+    let new_email = payload.email.clone();
+    let token = match UserService::new(state.repositories.users.clone())
+        .request_email_change(&user.id, payload.email, &payload.password)
+        .await
+    {
+        Ok(token) => token,
+        Err(err) => return user_error_response(err),
+    };
+
+    let body = format!(
+        "Use this code to confirm your new PlanIt email address:\n\n{}\n\nIf you did not request this change, ignore this message.",
+        token
+    );
+    let mail = Mail {
+        to_name: None,
+        to: new_email,
+        subject: "Confirm your new email address".to_string(),
+        body,
+    };
+    match state.mailer.send(mail).await {
+        Ok(_) => ApiResponse::ok("Confirmation sent to the new email address", None::<()>)
+            .into_response(),
+        Err(err) => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn confirm_email(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<ConfirmEmailRequest>,
+) -> impl IntoResponse {
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match UserService::new(state.repositories.users.clone())
+        .confirm_email_change(&payload.token)
+        .await
+    {
+        Ok(email) => {
+            let event = AuditEvent::new(AuditAction::EmailChanged, AuditOutcome::Success, &client);
+            audit_service(&state).record_for_email(event, &email).await;
+            ApiResponse::ok("Email changed successfully", Some(email)).into_response()
+        }
+        Err(err) => user_error_response(err),
+    }
+}
+
+pub fn account_deletion_service(state: &AppState) -> AccountDeletionService {
+    AccountDeletionService::new(
+        state.repositories.users.clone(),
+        state.repositories.categories.clone(),
+        state.repositories.goals.clone(),
+        state.repositories.tasks.clone(),
+        state.repositories.task_comments.clone(),
+        state.repositories.task_activity.clone(),
+        workspace_service(state),
+        InboxRepository::new(&state.mongodb),
+        state.repositories.refresh_tokens.clone(),
+        state.repositories.one_time_tokens.clone(),
+        state.repositories.login_attempts.clone(),
+    )
+}
+
+/// Deletes the account and everything it owns.
+async fn delete_me(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    client: ClientInfo,
+    JsonBody(payload): JsonBody<DeleteAccountRequest>,
+) -> impl IntoResponse {
+    let account = match UserService::new(state.repositories.users.clone())
+        .verify_user_password(&user.id, &payload.password)
+        .await
+    {
+        Ok(account) => account,
+        Err(err) => {
+            if let UserServiceError::InvalidPassword = err {
+                let event =
+                    AuditEvent::new(AuditAction::AccountDeleted, AuditOutcome::Failure, &client)
+                        .detail(err.to_string());
+                audit_service(&state).record(event.by_owner(user.id)).await;
+            }
+            return user_error_response(err);
+        }
+    };
+
+    match account_deletion_service(&state).delete_account(&account).await {
+        Ok(()) => {
+            let event = AuditEvent::new(AuditAction::AccountDeleted, AuditOutcome::Success, &client);
+            audit_service(&state).record(event.by_owner(user.id)).await;
+            ApiResponse::ok("User deleted successfully", None::<()>).into_response()
+        }
+        Err(err) => {
+            // The account is already unusable; its deletion is finished on the next start.
+            error!("Failed to delete data of user {}: {}", user.id, err);
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let signup_limit = middleware::from_fn_with_state(
+        RateLimiter::new(state.rate_limits.clone(), SIGNUP_PER_IP, "signup-ip")
+            .trusting_proxy_headers(state.config.rate_limit.trust_proxy_headers),
+        limit_by_ip,
+    );
+    let user_exists_limit = middleware::from_fn_with_state(
+        RateLimiter::new(state.rate_limits.clone(), USER_EXISTS_PER_IP, "user-exists-ip")
+            .trusting_proxy_headers(state.config.rate_limit.trust_proxy_headers),
+        limit_by_ip,
+    );
+
     let v1: Router<Arc<AppState>> = Router::new()
-        .route("/signup", post(sign_up))
-        .route("/user-exists", get(user_exists));
+        .route("/me", get(get_me).patch(update_me).delete(delete_me))
+        .route("/me/password", put(change_password))
+        .route("/me/email", post(change_email))
+        .route_layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
+        .route("/signup", post(sign_up).layer(signup_limit))
+        .route("/user-exists", get(user_exists).layer(user_exists_limit))
+        .route("/me/email/confirm", post(confirm_email));
     Router::new().nest("/v1", v1)
 }
@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use mongodb::bson::{oid::ObjectId, Bson};
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+
+use super::models::{Language, PendingEmailChange, Role, User, UserCounts};
+use super::repository::UserRepository;
+use super::types::{Email, HashedPassword, PhoneNumber};
+
+pub struct InMemoryUserRepository {
+    users: MemoryCollection<User>,
+}
+
+impl Default for InMemoryUserRepository {
+    /// Emails are unique regardless of case, deleted accounts included, like `email_unique`,
+    /// and feed token hashes like `feed_token_hash_unique`.
+    fn default() -> Self {
+        InMemoryUserRepository {
+            users: MemoryCollection::default()
+                .unique("email_unique", |user: &User| {
+                    Some(Bson::String(user.email.as_str().to_lowercase()))
+                })
+                .unique("feed_token_hash_unique", |user: &User| {
+                    user.feed_token_hash.clone().map(Bson::String)
+                }),
+        }
+    }
+}
+
+fn same_email(a: &Email, b: &Email) -> bool {
+    a.as_str().to_lowercase() == b.as_str().to_lowercase()
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create_user(&self, new_user: User) -> Result<ObjectId, Error> {
+        self.users.insert(&new_user)
+    }
+
+    async fn find_user_by_email(&self, email: &Email) -> Result<Option<User>, Error> {
+        self.users
+            .find_one(|user| !user.deleted && same_email(&user.email, email))
+    }
+
+    async fn find_user_by_id(&self, user_id: &ObjectId) -> Result<Option<User>, Error> {
+        self.users
+            .find_one(|user| !user.deleted && user.id.as_ref() == Some(user_id))
+    }
+
+    async fn update_password(
+        &self,
+        user_id: &ObjectId,
+        password: &HashedPassword,
+    ) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.password = password.clone(),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn find_user_by_feed_token_hash(&self, token_hash: &str) -> Result<Option<User>, Error> {
+        self.users
+            .find_one(|user| !user.deleted && user.feed_token_hash.as_deref() == Some(token_hash))
+    }
+
+    async fn set_feed_token_hash(
+        &self,
+        user_id: &ObjectId,
+        token_hash: Option<&str>,
+    ) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.feed_token_hash = token_hash.map(str::to_string),
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn update_profile(
+        &self,
+        user_id: &ObjectId,
+        name: Option<String>,
+        phone: Option<PhoneNumber>,
+        time_zone: Option<Tz>,
+        language: Option<Language>,
+    ) -> Result<bool, Error> {
+        if name.is_none() && phone.is_none() && time_zone.is_none() && language.is_none() {
+            return Ok(false);
+        }
+
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| {
+                if let Some(name) = name {
+                    user.name = name;
+                }
+                if let Some(phone) = phone {
+                    user.phone = phone;
+                }
+                if let Some(time_zone) = time_zone {
+                    user.time_zone = time_zone;
+                }
+                if let Some(language) = language {
+                    user.language = language;
+                }
+            },
+        )?;
+        Ok(result.modified > 0)
+    }
+
+    async fn set_pending_email(
+        &self,
+        user_id: &ObjectId,
+        pending: &PendingEmailChange,
+    ) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.pending_email = Some(pending.clone()),
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn find_user_by_pending_email_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, Error> {
+        self.users.find_one(|user| {
+            !user.deleted
+                && user
+                    .pending_email
+                    .as_ref()
+                    .is_some_and(|pending| pending.token_hash == token_hash)
+        })
+    }
+
+    async fn confirm_pending_email(
+        &self,
+        user_id: &ObjectId,
+        token_hash: &str,
+        email: &Email,
+    ) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| {
+                user.id.as_ref() == Some(user_id)
+                    && user
+                        .pending_email
+                        .as_ref()
+                        .is_some_and(|pending| pending.token_hash == token_hash)
+            },
+            |user| {
+                user.email = email.clone();
+                user.verified = true;
+                user.pending_email = None;
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn mark_deleted(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.deleted = true,
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn find_deleted_users(&self) -> Result<Vec<User>, Error> {
+        self.users.find(|user| user.deleted)
+    }
+
+    async fn delete_user(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .users
+            .delete_one(|user| user.id.as_ref() == Some(user_id))?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn find_users(
+        &self,
+        search: Option<&str>,
+        after: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<User>, Error> {
+        let search = search.map(str::to_lowercase);
+        let mut users = self.users.find(|user| {
+            if user.deleted {
+                return false;
+            }
+            let matches_search = search.as_ref().is_none_or(|search| {
+                user.name.to_lowercase().contains(search)
+                    || user.email.as_str().to_lowercase().contains(search)
+            });
+            let after_cursor = after.is_none_or(|after| user.id.as_ref() > Some(after));
+            matches_search && after_cursor
+        })?;
+        users.sort_by_key(|user| user.id);
+        users.truncate((limit + 1).max(0) as usize);
+
+        Ok(users)
+    }
+
+    async fn set_disabled(&self, user_id: &ObjectId, disabled: bool) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.disabled = disabled,
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn set_role(&self, user_id: &ObjectId, role: Role) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.role = role,
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn count_users(&self) -> Result<UserCounts, Error> {
+        Ok(UserCounts {
+            total: self.users.count(|_| true)?,
+            verified: self.users.count(|user| user.verified)?,
+            disabled: self.users.count(|user| user.disabled)?,
+            admins: self.users.count(|user| user.role == Role::Admin)?,
+        })
+    }
+
+    async fn set_verified(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.users.update_one(
+            |user| user.id.as_ref() == Some(user_id),
+            |user| user.verified = true,
+        )?;
+
+        Ok(result.matched > 0)
+    }
+}
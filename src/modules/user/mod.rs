@@ -1,7 +1,12 @@
+pub mod deletion;
 pub mod dto;
+pub mod extractors;
 pub mod handlers;
+#[cfg(test)]
+pub mod memory;
 pub mod models;
 pub mod repository;
 pub mod service;
+pub mod types;
 
 pub use handlers::handles;
@@ -1,4 +1,9 @@
 use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+use crate::helpers::time_zone_helper::legacy_time_zone;
+use crate::modules::notification::models::NotificationChannelKind;
+use crate::modules::user::types::{Email, HashedPassword, PhoneNumber};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
@@ -12,7 +17,71 @@ pub struct User {
     )]
     pub id: Option<ObjectId>,
     pub name: String,
-    pub email: String, // TODO: create a custom email type
-    pub password: String, // TODO: create a custom password type
-    pub phone: String, // TODO: create a custom phone type
+    #[serde(deserialize_with = "Email::deserialize_stored")]
+    pub email: Email,
+    pub password: HashedPassword,
+    #[serde(deserialize_with = "PhoneNumber::deserialize_stored")]
+    pub phone: PhoneNumber,
+    #[serde(default)]
+    pub notification_channel: NotificationChannelKind,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default = "legacy_time_zone")]
+    pub time_zone: Tz,
+    #[serde(default)]
+    pub feed_token_hash: Option<String>, // SHA-256 of the calendar feed token
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub pending_email: Option<PendingEmailChange>,
+    #[serde(default = "verified_by_default")]
+    pub verified: bool,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub disabled: bool, // Set by an administrator; disabled accounts cannot log in
+    #[serde(default)]
+    pub deleted: bool, // The account is being deleted; lookups no longer find it
+}
+
+// Accounts created before email verification existed are treated as verified.
+fn verified_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    #[default]
+    #[serde(rename = "USER")]
+    User,
+    #[serde(rename = "ADMIN")]
+    Admin,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "pt-BR")]
+    PtBr,
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "es")]
+    Es,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UserCounts {
+    pub total: u64,
+    pub verified: u64,
+    pub disabled: u64,
+    pub admins: u64,
+}
+
+/// An email change waiting for the new address to be confirmed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingEmailChange {
+    #[serde(deserialize_with = "Email::deserialize_stored")]
+    pub email: Email,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
 }
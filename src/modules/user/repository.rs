@@ -1,25 +1,341 @@
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use crate::helpers::pagination_helper::escape_regex;
 use mongodb::error::Error;
-use mongodb::{bson::doc, Collection, Database};
+use mongodb::bson::oid::ObjectId;
+use mongodb::options::{Collation, CollationStrength};
+use mongodb::{
+    bson::{doc, to_bson, Document},
+    Collection, Database,
+};
 
-use super::models::User;
+use super::models::{Language, PendingEmailChange, Role, User, UserCounts};
+use super::types::{Email, HashedPassword, PhoneNumber};
 
-pub struct UserRepository {
+pub fn email_collation() -> Collation {
+    Collation::builder()
+        .locale("en")
+        .strength(CollationStrength::Secondary)
+        .build()
+}
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create_user(&self, new_user: User) -> Result<ObjectId, Error>;
+
+    /// Matches emails case-insensitively, so records stored before emails were lowercased
+    /// are still found.
+    async fn find_user_by_email(&self, email: &Email) -> Result<Option<User>, Error>;
+
+    async fn find_user_by_id(&self, user_id: &ObjectId) -> Result<Option<User>, Error>;
+
+    async fn update_password(
+        &self,
+        user_id: &ObjectId,
+        password: &HashedPassword,
+    ) -> Result<bool, Error>;
+
+    async fn find_user_by_feed_token_hash(&self, token_hash: &str)
+        -> Result<Option<User>, Error>;
+
+    async fn set_feed_token_hash(
+        &self,
+        user_id: &ObjectId,
+        token_hash: Option<&str>,
+    ) -> Result<bool, Error>;
+
+    async fn update_profile(
+        &self,
+        user_id: &ObjectId,
+        name: Option<String>,
+        phone: Option<PhoneNumber>,
+        time_zone: Option<Tz>,
+        language: Option<Language>,
+    ) -> Result<bool, Error>;
+
+    async fn set_pending_email(
+        &self,
+        user_id: &ObjectId,
+        pending: &PendingEmailChange,
+    ) -> Result<bool, Error>;
+
+    async fn find_user_by_pending_email_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, Error>;
+
+    /// Replaces the email with the pending one, if it still matches `token_hash`. Confirming
+    /// proves ownership of the address, so the account becomes verified.
+    async fn confirm_pending_email(
+        &self,
+        user_id: &ObjectId,
+        token_hash: &str,
+        email: &Email,
+    ) -> Result<bool, Error>;
+
+    /// Hides the user from every lookup, before its data is deleted.
+    async fn mark_deleted(&self, user_id: &ObjectId) -> Result<bool, Error>;
+
+    /// Users marked deleted whose deletion has not finished.
+    async fn find_deleted_users(&self) -> Result<Vec<User>, Error>;
+
+    async fn delete_user(&self, user_id: &ObjectId) -> Result<bool, Error>;
+
+    /// Returns up to `limit` users ordered by id, after `after` when given, whose name or
+    /// email contains `search`; one extra user is fetched to tell if there is a next page.
+    async fn find_users(
+        &self,
+        search: Option<&str>,
+        after: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<User>, Error>;
+
+    async fn set_disabled(&self, user_id: &ObjectId, disabled: bool) -> Result<bool, Error>;
+
+    async fn set_role(&self, user_id: &ObjectId, role: Role) -> Result<bool, Error>;
+
+    /// Users without a `verified` field predate email verification and count as verified.
+    async fn count_users(&self) -> Result<UserCounts, Error>;
+
+    async fn set_verified(&self, user_id: &ObjectId) -> Result<bool, Error>;
+}
+
+pub struct MongoUserRepository {
     collection: Collection<User>,
 }
 
-impl UserRepository {
+impl MongoUserRepository {
     pub fn new(db: &Database) -> Self {
         let collection = db.collection("users");
-        UserRepository { collection }
+        MongoUserRepository { collection }
     }
+}
 
-    pub async fn create_user(&self, new_user: User) -> Result<mongodb::bson::oid::ObjectId, Error> {
+#[async_trait]
+impl UserRepository for MongoUserRepository {
+    async fn create_user(&self, new_user: User) -> Result<ObjectId, Error> {
         let result = self.collection.insert_one(new_user).await?;
         Ok(result.inserted_id.as_object_id().unwrap())
     }
 
-    pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, Error> {
-        let user = self.collection.find_one(doc! { "email": email }).await?;
+    async fn find_user_by_email(&self, email: &Email) -> Result<Option<User>, Error> {
+        let user = self
+            .collection
+            .find_one(doc! { "email": email.as_str(), "deleted": { "$ne": true } })
+            .collation(email_collation())
+            .await?;
         Ok(user)
     }
+
+    async fn find_user_by_id(&self, user_id: &ObjectId) -> Result<Option<User>, Error> {
+        let user = self
+            .collection
+            .find_one(doc! { "_id": user_id, "deleted": { "$ne": true } })
+            .await?;
+        Ok(user)
+    }
+
+    async fn update_password(
+        &self,
+        user_id: &ObjectId,
+        password: &HashedPassword,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "password": password.as_str() } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn find_user_by_feed_token_hash(&self, token_hash: &str) -> Result<Option<User>, Error> {
+        let user = self
+            .collection
+            .find_one(doc! { "feed_token_hash": token_hash, "deleted": { "$ne": true } })
+            .await?;
+        Ok(user)
+    }
+
+    async fn set_feed_token_hash(
+        &self,
+        user_id: &ObjectId,
+        token_hash: Option<&str>,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = match token_hash {
+            Some(token_hash) => doc! { "$set": { "feed_token_hash": token_hash } },
+            None => doc! { "$unset": { "feed_token_hash": "" } },
+        };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn update_profile(
+        &self,
+        user_id: &ObjectId,
+        name: Option<String>,
+        phone: Option<PhoneNumber>,
+        time_zone: Option<Tz>,
+        language: Option<Language>,
+    ) -> Result<bool, Error> {
+        let mut update = Document::new();
+        if let Some(name) = name {
+            update.insert("name", name);
+        }
+        if let Some(phone) = phone {
+            update.insert("phone", phone);
+        }
+        if let Some(time_zone) = time_zone {
+            update.insert("time_zone", time_zone.name());
+        }
+        if let Some(language) = language {
+            update.insert("language", to_bson(&language)?);
+        }
+        if update.is_empty() {
+            return Ok(false);
+        }
+
+        let result = self
+            .collection
+            .update_one(doc! { "_id": user_id }, doc! { "$set": update })
+            .await?;
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_pending_email(
+        &self,
+        user_id: &ObjectId,
+        pending: &PendingEmailChange,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "pending_email": to_bson(pending)? } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn find_user_by_pending_email_token(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<User>, Error> {
+        let filter = doc! { "pending_email.token_hash": token_hash, "deleted": { "$ne": true } };
+        self.collection.find_one(filter).await
+    }
+
+    async fn confirm_pending_email(
+        &self,
+        user_id: &ObjectId,
+        token_hash: &str,
+        email: &Email,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id, "pending_email.token_hash": token_hash };
+        let update = doc! {
+            "$set": { "email": email.as_str(), "verified": true },
+            "$unset": { "pending_email": "" },
+        };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn mark_deleted(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "deleted": true } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn find_deleted_users(&self) -> Result<Vec<User>, Error> {
+        let mut cursor = self.collection.find(doc! { "deleted": true }).await?;
+        let mut users = Vec::new();
+        while cursor.advance().await? {
+            users.push(cursor.deserialize_current()?);
+        }
+
+        Ok(users)
+    }
+
+    async fn delete_user(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let result = self.collection.delete_one(doc! { "_id": user_id }).await?;
+
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn find_users(
+        &self,
+        search: Option<&str>,
+        after: Option<&ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<User>, Error> {
+        let mut filter = doc! { "deleted": { "$ne": true } };
+        if let Some(search) = search {
+            let pattern = escape_regex(search);
+            filter.insert(
+                "$or",
+                vec![
+                    doc! { "name": { "$regex": &pattern, "$options": "i" } },
+                    doc! { "email": { "$regex": &pattern, "$options": "i" } },
+                ],
+            );
+        }
+        if let Some(after) = after {
+            filter.insert("_id", doc! { "$gt": after });
+        }
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "_id": 1 })
+            .limit(limit + 1)
+            .await?;
+        let mut users: Vec<User> = Vec::new();
+        while cursor.advance().await? {
+            users.push(cursor.deserialize_current()?);
+        }
+
+        Ok(users)
+    }
+
+    async fn set_disabled(&self, user_id: &ObjectId, disabled: bool) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "disabled": disabled } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn set_role(&self, user_id: &ObjectId, role: Role) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "role": to_bson(&role)? } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn count_users(&self) -> Result<UserCounts, Error> {
+        Ok(UserCounts {
+            total: self.collection.count_documents(doc! {}).await?,
+            verified: self
+                .collection
+                .count_documents(doc! { "verified": { "$ne": false } })
+                .await?,
+            disabled: self
+                .collection
+                .count_documents(doc! { "disabled": true })
+                .await?,
+            admins: self
+                .collection
+                .count_documents(doc! { "role": to_bson(&Role::Admin)? })
+                .await?,
+        })
+    }
+
+    async fn set_verified(&self, user_id: &ObjectId) -> Result<bool, Error> {
+        let filter = doc! { "_id": user_id };
+        let update = doc! { "$set": { "verified": true } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
 }
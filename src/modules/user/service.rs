@@ -1,49 +1,266 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use chrono_tz::Tz;
 use mongodb::bson::oid::ObjectId;
 use thiserror::Error;
 
-use super::dto::UserSignUpRequest;
-use super::models::User;
+use crate::helpers::mongo_error_helper::duplicate_key_as;
+use crate::helpers::time_zone_helper::default_time_zone;
+use crate::helpers::token_helper::{generate_token, hash_token};
+use crate::modules::auth::password::{self, PasswordError, PasswordVerification};
+use crate::modules::notification::{
+    channels::{validate_webhook_url, NotificationChannelError},
+    models::NotificationChannelKind,
+};
+
+use super::dto::{UpdateProfileRequest, UserSignUpRequest};
+use super::models::{PendingEmailChange, Role, User};
 use super::repository::UserRepository;
+use super::types::{Email, Password};
+
+const EMAIL_CHANGE_TTL_HOURS: i64 = 24;
 
 #[derive(Error, Debug)]
 pub enum UserServiceError {
     #[error("User with this email already exists")]
     UserAlreadyExists,
+    #[error("A webhook URL is required for webhook notifications")]
+    WebhookUrlRequired,
+    #[error(transparent)]
+    InvalidWebhookUrl(#[from] NotificationChannelError),
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Invalid password")]
+    InvalidPassword,
+    #[error("Invalid or expired email confirmation token")]
+    InvalidEmailToken,
     #[error("Database error: {0}")]
     DatabaseError(#[from] mongodb::error::Error),
+    #[error(transparent)]
+    PasswordError(#[from] PasswordError),
 }
 
 pub struct UserService {
-    repository: UserRepository,
+    repository: Arc<dyn UserRepository>,
 }
 
 impl UserService {
-    pub fn new(repository: UserRepository) -> Self {
+    pub fn new(repository: Arc<dyn UserRepository>) -> Self {
         UserService { repository }
     }
 
+    /// The password is hashed before the email is looked up, so a taken email is not
+    /// answered faster than a new one.
     pub async fn create_user(&self, data: UserSignUpRequest) -> Result<ObjectId, UserServiceError> {
+        let password = password::hash_password(&data.password).await?;
         if (self.repository.find_user_by_email(&data.email).await?).is_some() {
             return Err(UserServiceError::UserAlreadyExists);
         }
 
+        let notification_channel = data.notification_channel.unwrap_or_default();
+        if notification_channel == NotificationChannelKind::Webhook && data.webhook_url.is_none() {
+            return Err(UserServiceError::WebhookUrlRequired);
+        }
+        if let Some(webhook_url) = &data.webhook_url {
+            validate_webhook_url(webhook_url)?;
+        }
+
         let new_user = User {
             id: None,
             name: data.name,
             email: data.email,
-            password: data.password, // TODO: hash password
+            password,
             phone: data.phone,
+            notification_channel,
+            webhook_url: data.webhook_url,
+            time_zone: data.time_zone.unwrap_or_else(default_time_zone),
+            feed_token_hash: None,
+            language: data.language.unwrap_or_default(),
+            pending_email: None,
+            verified: false,
+            role: Role::User,
+            disabled: false,
+            deleted: false,
         };
         self.repository
             .create_user(new_user)
             .await
-            .map_err(UserServiceError::from)
+            .map_err(duplicate_key_as(UserServiceError::UserAlreadyExists))
     }
 
-    pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, UserServiceError> {
+    pub async fn find_user_by_email(&self, email: &Email) -> Result<Option<User>, UserServiceError> {
         self.repository
             .find_user_by_email(email)
             .await
             .map_err(UserServiceError::from)
     }
+
+    pub async fn find_user_by_id(&self, user_id: &ObjectId) -> Result<Option<User>, UserServiceError> {
+        self.repository
+            .find_user_by_id(user_id)
+            .await
+            .map_err(UserServiceError::from)
+    }
+
+    pub async fn get_user_time_zone(&self, user_id: &ObjectId) -> Result<Tz, UserServiceError> {
+        let user = self.find_user_by_id(user_id).await?;
+        Ok(user.map(|user| user.time_zone).unwrap_or_else(default_time_zone))
+    }
+
+    pub async fn update_password(
+        &self,
+        user_id: &ObjectId,
+        password: &Password,
+    ) -> Result<bool, UserServiceError> {
+        let hash = password::hash_password(password).await?;
+        self.repository
+            .update_password(user_id, &hash)
+            .await
+            .map_err(UserServiceError::from)
+    }
+
+    pub async fn get_user(&self, user_id: &ObjectId) -> Result<User, UserServiceError> {
+        self.repository
+            .find_user_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFound)
+    }
+
+    pub async fn update_profile(
+        &self,
+        user_id: &ObjectId,
+        data: UpdateProfileRequest,
+    ) -> Result<bool, UserServiceError> {
+        self.get_user(user_id).await?;
+        self.repository
+            .update_profile(user_id, data.name, data.phone, data.time_zone, data.language)
+            .await
+            .map_err(UserServiceError::from)
+    }
+
+    /// Loads the user after checking `password` against the stored one.
+    pub async fn verify_user_password(
+        &self,
+        user_id: &ObjectId,
+        password: &Password,
+    ) -> Result<User, UserServiceError> {
+        let user = self.get_user(user_id).await?;
+        match password::verify_password(password, &user.password).await {
+            PasswordVerification::Valid | PasswordVerification::ValidLegacy => Ok(user),
+            PasswordVerification::Invalid => Err(UserServiceError::InvalidPassword),
+        }
+    }
+
+    pub async fn change_password(
+        &self,
+        user_id: &ObjectId,
+        current_password: &Password,
+        new_password: &Password,
+    ) -> Result<(), UserServiceError> {
+        self.verify_user_password(user_id, current_password).await?;
+        self.update_password(user_id, new_password).await?;
+        Ok(())
+    }
+
+    /// Starts an email change and returns the confirmation token to send to the new address.
+    /// The current email stays in use until the token is confirmed.
+    pub async fn request_email_change(
+        &self,
+        user_id: &ObjectId,
+        new_email: Email,
+        password: &Password,
+    ) -> Result<String, UserServiceError> {
+        self.verify_user_password(user_id, password).await?;
+        if self.repository.find_user_by_email(&new_email).await?.is_some() {
+            return Err(UserServiceError::UserAlreadyExists);
+        }
+
+        let token = generate_token();
+        let pending = PendingEmailChange {
+            email: new_email,
+            token_hash: hash_token(&token),
+            expires_at: Utc::now() + Duration::hours(EMAIL_CHANGE_TTL_HOURS),
+        };
+        if !self.repository.set_pending_email(user_id, &pending).await? {
+            return Err(UserServiceError::UserNotFound);
+        }
+        Ok(token)
+    }
+
+    pub async fn confirm_email_change(&self, token: &str) -> Result<Email, UserServiceError> {
+        let token_hash = hash_token(token);
+        let user = self
+            .repository
+            .find_user_by_pending_email_token(&token_hash)
+            .await?
+            .ok_or(UserServiceError::InvalidEmailToken)?;
+        let pending = user
+            .pending_email
+            .filter(|pending| pending.expires_at > Utc::now())
+            .ok_or(UserServiceError::InvalidEmailToken)?;
+
+        // The address may have been taken since the change was requested.
+        if self
+            .repository
+            .find_user_by_email(&pending.email)
+            .await?
+            .is_some()
+        {
+            return Err(UserServiceError::UserAlreadyExists);
+        }
+
+        if !self
+            .repository
+            .confirm_pending_email(&user.id.unwrap(), &token_hash, &pending.email)
+            .await
+            .map_err(duplicate_key_as(UserServiceError::UserAlreadyExists))?
+        {
+            return Err(UserServiceError::InvalidEmailToken);
+        }
+        Ok(pending.email)
+    }
+
+    pub async fn mark_verified(&self, user_id: &ObjectId) -> Result<bool, UserServiceError> {
+        self.repository
+            .set_verified(user_id)
+            .await
+            .map_err(UserServiceError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+
+    use super::*;
+
+    fn sign_up_request(email: &str) -> UserSignUpRequest {
+        serde_json::from_value(json!({
+            "name": "Ana",
+            "email": email,
+            "password": "Str0ng!Passw0rd",
+            "phone": "+5511999999999",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn emails_of_deleted_accounts_stay_taken() {
+        let repositories = Repositories::in_memory();
+        let service = UserService::new(repositories.users.clone());
+        let user_id = service
+            .create_user(sign_up_request("ana@example.com"))
+            .await
+            .unwrap();
+        repositories.users.mark_deleted(&user_id).await.unwrap();
+
+        // The lookup skips deleted accounts, so only the unique index catches this one.
+        assert!(matches!(
+            service.create_user(sign_up_request("ANA@example.com")).await,
+            Err(UserServiceError::UserAlreadyExists)
+        ));
+    }
 }
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+
+use mongodb::bson::Bson;
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+// Bounds the cost of hashing attacker-supplied input.
+const MAX_PASSWORD_LENGTH: usize = 128;
+// Numbers stored before E.164 normalisation have no country code; PlanIt started in Brazil.
+const DEFAULT_COUNTRY_CODE: &str = "55";
+const MIN_PHONE_DIGITS: usize = 8;
+const MAX_PHONE_DIGITS: usize = 15;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum InvalidValue {
+    #[error("Invalid email address")]
+    Email,
+
+    #[error("Password must be between 8 and 128 characters")]
+    Password,
+
+    #[error("Invalid phone number, expected E.164 format (e.g. +5583999999999)")]
+    PhoneNumber,
+}
+
+/// An email address, trimmed and lowercased so that case variants compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Email(String);
+
+impl Email {
+    pub fn parse(value: &str) -> Result<Self, InvalidValue> {
+        let email = Email::normalize(value);
+        if !validator::validate_email(&email) {
+            return Err(InvalidValue::Email);
+        }
+        Ok(Email(email))
+    }
+
+    pub fn normalize(value: &str) -> String {
+        value.trim().to_lowercase()
+    }
+
+    /// For `deserialize_with` on stored documents: normalises without validating, so an
+    /// address saved before validation existed does not make its document unreadable.
+    pub fn deserialize_stored<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Email(Email::normalize(&value)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = InvalidValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Email::parse(&value)
+    }
+}
+
+impl FromStr for Email {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Email::parse(value)
+    }
+}
+
+impl From<Email> for String {
+    fn from(email: Email) -> Self {
+        email.0
+    }
+}
+
+impl From<Email> for Bson {
+    fn from(email: Email) -> Self {
+        Bson::String(email.0)
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A plaintext password as sent by a client. It can be read but never serialized or
+/// printed, so it cannot leak into a response, a log line or the database.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Password(String);
+
+impl Password {
+    pub fn parse(value: &str) -> Result<Self, InvalidValue> {
+        let length = value.chars().count();
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length) {
+            return Err(InvalidValue::Password);
+        }
+        Ok(Password(value.to_string()))
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Password {
+    type Error = InvalidValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Password::parse(&value)
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(***)")
+    }
+}
+
+/// The stored form of a password: an Argon2id PHC string, or plaintext for legacy records
+/// that have not been rehashed yet.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HashedPassword(String);
+
+impl HashedPassword {
+    pub fn new(hash: String) -> Self {
+        HashedPassword(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for HashedPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HashedPassword(***)")
+    }
+}
+
+/// A phone number in E.164 format (`+` followed by up to 15 digits). Spaces, dots, dashes
+/// and parentheses are dropped; a `00` prefix is read as `+`, and numbers without a
+/// country code get `DEFAULT_COUNTRY_CODE`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    pub fn parse(value: &str) -> Result<Self, InvalidValue> {
+        let value = value.trim();
+        let (international, rest) = match value.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => match value.strip_prefix("00") {
+                Some(rest) => (true, rest),
+                None => (false, value),
+            },
+        };
+
+        let mut digits = String::with_capacity(MAX_PHONE_DIGITS);
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '.' | '-' | '(' | ')' => {}
+                _ => return Err(InvalidValue::PhoneNumber),
+            }
+        }
+        if !international {
+            digits.insert_str(0, DEFAULT_COUNTRY_CODE);
+        }
+
+        if !(MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits.len()) || digits.starts_with('0')
+        {
+            return Err(InvalidValue::PhoneNumber);
+        }
+        Ok(PhoneNumber(format!("+{}", digits)))
+    }
+
+    /// For `deserialize_with` on stored documents: a number that cannot be normalised is
+    /// kept as stored instead of failing the whole document.
+    pub fn deserialize_stored<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(PhoneNumber::parse(&value).unwrap_or(PhoneNumber(value)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for PhoneNumber {
+    type Error = InvalidValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        PhoneNumber::parse(&value)
+    }
+}
+
+impl From<PhoneNumber> for String {
+    fn from(phone: PhoneNumber) -> Self {
+        phone.0
+    }
+}
+
+impl From<PhoneNumber> for Bson {
+    fn from(phone: PhoneNumber) -> Self {
+        Bson::String(phone.0)
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{self, doc, oid::ObjectId};
+
+    use super::*;
+    use crate::modules::user::models::User;
+
+    #[test]
+    fn requests_are_validated_but_stored_users_always_read_back() {
+        let request = serde_json::from_value::<Email>(serde_json::json!("not an email"));
+        assert!(request.is_err());
+        let request = serde_json::from_value::<PhoneNumber>(serde_json::json!("ramal 12"));
+        assert!(request.is_err());
+
+        let user: User = bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "name": "Ana",
+            "email": " Ana@Example.COM ",
+            "password": "secret",
+            "phone": "ramal 12",
+        })
+        .unwrap();
+        assert_eq!(user.email.as_str(), "ana@example.com");
+        assert_eq!(user.phone.as_str(), "ramal 12");
+
+        let user: User = bson::from_document(doc! {
+            "_id": ObjectId::new(),
+            "name": "Ana",
+            "email": "ana@example.com",
+            "password": "secret",
+            "phone": "(83) 99999-9999",
+        })
+        .unwrap();
+        assert_eq!(user.phone.as_str(), "+5583999999999");
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use mongodb::bson::oid::ObjectId;
+use thiserror::Error;
+
+use super::models::{Access, Scope, WorkspaceRole};
+use super::repository::WorkspaceRepository;
+
+#[derive(Error, Debug)]
+pub enum WorkspaceAccessError {
+    #[error("Workspace not found")]
+    WorkspaceNotFound,
+
+    #[error("You do not have permission to do this in this workspace")]
+    WorkspaceForbidden,
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+/// Permission checks shared by every service whose records can belong to a workspace.
+#[derive(Clone)]
+pub struct WorkspaceAccess {
+    repository: Arc<dyn WorkspaceRepository>,
+}
+
+impl WorkspaceAccess {
+    pub fn new(repository: Arc<dyn WorkspaceRepository>) -> Self {
+        WorkspaceAccess { repository }
+    }
+
+    /// Returns the user's role in the workspace if it grants `access`.
+    pub async fn authorize(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        access: Access,
+    ) -> Result<WorkspaceRole, WorkspaceAccessError> {
+        let workspace = self
+            .repository
+            .find_by_id(workspace_id)
+            .await?
+            .ok_or(WorkspaceAccessError::WorkspaceNotFound)?;
+
+        match workspace.member(user_id) {
+            Some(member) if member.role.allows(access) => Ok(member.role),
+            _ => Err(WorkspaceAccessError::WorkspaceForbidden),
+        }
+    }
+
+    /// The scope a request targets: the workspace when one is given and `access` is
+    /// granted there, otherwise the user's own records.
+    pub async fn resolve(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: Option<ObjectId>,
+        access: Access,
+    ) -> Result<Scope, WorkspaceAccessError> {
+        if let Some(workspace_id) = &workspace_id {
+            self.authorize(user_id, workspace_id, access).await?;
+        }
+        Ok(Scope::new(*user_id, workspace_id))
+    }
+
+    /// Whether the user may act on a record owned by `scope`.
+    pub async fn allows(
+        &self,
+        user_id: &ObjectId,
+        scope: &Scope,
+        access: Access,
+    ) -> Result<bool, mongodb::error::Error> {
+        match scope {
+            Scope::User(owner_id) => Ok(owner_id == user_id),
+            Scope::Workspace(workspace_id) => {
+                match self.authorize(user_id, workspace_id, access).await {
+                    Ok(_) => Ok(true),
+                    Err(WorkspaceAccessError::DatabaseError(err)) => Err(err),
+                    Err(_) => Ok(false),
+                }
+            }
+        }
+    }
+
+    pub async fn is_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<bool, WorkspaceAccessError> {
+        let workspace = self
+            .repository
+            .find_by_id(workspace_id)
+            .await?
+            .ok_or(WorkspaceAccessError::WorkspaceNotFound)?;
+        Ok(workspace.member(user_id).is_some())
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::modules::user::types::Email;
+
+use super::models::{Workspace, WorkspaceMember, WorkspaceRole};
+
+/// Selects a workspace for list endpoints; without it the caller's own records are used.
+#[derive(Deserialize)]
+pub struct WorkspaceQuery {
+    pub workspace_id: Option<ObjectId>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateWorkspaceRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct RenameWorkspaceRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AddMemberRequest {
+    pub email: Email,
+    pub role: WorkspaceRole,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMemberRequest {
+    pub role: WorkspaceRole,
+}
+
+#[derive(Serialize)]
+pub struct MemberResponse {
+    pub user_id: String,
+    pub role: WorkspaceRole,
+    pub added_at: DateTime<Utc>,
+}
+
+impl From<WorkspaceMember> for MemberResponse {
+    fn from(member: WorkspaceMember) -> Self {
+        MemberResponse {
+            user_id: member.user_id.to_string(),
+            role: member.role,
+            added_at: member.added_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct WorkspaceResponse {
+    pub _id: String,
+    pub name: String,
+    pub owner_id: String,
+    pub role: Option<WorkspaceRole>,
+    pub members: Vec<MemberResponse>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WorkspaceResponse {
+    pub fn new(workspace: Workspace, user_id: &ObjectId) -> Self {
+        WorkspaceResponse {
+            _id: workspace.id.unwrap().to_string(),
+            role: workspace.member(user_id).map(|member| member.role),
+            name: workspace.name,
+            owner_id: workspace.owner_id.to_string(),
+            members: workspace.members.into_iter().map(Into::into).collect(),
+            created_at: workspace.created_at,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Path, State},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Extension, Router,
+};
+use mongodb::bson::oid::ObjectId;
+use validator::Validate;
+
+use crate::{
+    helpers::api_response::ApiResponse,
+    modules::auth::{self, dto::AuthState},
+    AppState,
+};
+
+use super::{
+    access::{WorkspaceAccess, WorkspaceAccessError},
+    dto::{
+        AddMemberRequest, CreateWorkspaceRequest, RenameWorkspaceRequest, UpdateMemberRequest,
+        WorkspaceResponse,
+    },
+    service::{WorkspaceService, WorkspaceServiceError},
+};
+
+pub fn workspace_access(state: &AppState) -> WorkspaceAccess {
+    WorkspaceAccess::new(state.repositories.workspaces.clone())
+}
+
+pub fn workspace_service(state: &AppState) -> WorkspaceService {
+    WorkspaceService::new(
+        state.repositories.workspaces.clone(),
+        workspace_access(state),
+        state.repositories.users.clone(),
+        state.repositories.categories.clone(),
+        state.repositories.goals.clone(),
+        state.repositories.tasks.clone(),
+        state.repositories.task_comments.clone(),
+        state.repositories.task_activity.clone(),
+    )
+}
+
+/// Maps a failed workspace permission check, for handlers of any module.
+pub fn access_error_response(err: WorkspaceAccessError) -> Response {
+    match err {
+        WorkspaceAccessError::WorkspaceNotFound => {
+            ApiResponse::not_found(err.to_string().as_str()).into_response()
+        }
+        WorkspaceAccessError::WorkspaceForbidden => {
+            ApiResponse::forbidden(err.to_string().as_str()).into_response()
+        }
+        err => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+fn workspace_error_response(err: WorkspaceServiceError) -> Response {
+    match err {
+        WorkspaceServiceError::UserNotFound | WorkspaceServiceError::MemberNotFound => {
+            ApiResponse::not_found(err.to_string().as_str()).into_response()
+        }
+        WorkspaceServiceError::AlreadyMember | WorkspaceServiceError::OwnerRole => {
+            ApiResponse::unprocessable_entity(err.to_string().as_str(), None::<()>).into_response()
+        }
+        WorkspaceServiceError::Access(err) => access_error_response(err),
+        err => {
+            ApiResponse::server_error(Some(err.to_string().as_str()), None::<()>).into_response()
+        }
+    }
+}
+
+async fn list_workspaces(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .get_user_workspaces(&user.id)
+        .await
+    {
+        Ok(workspaces) => {
+            let workspaces: Vec<WorkspaceResponse> = workspaces
+                .into_iter()
+                .map(|workspace| WorkspaceResponse::new(workspace, &user.id))
+                .collect();
+            ApiResponse::ok("Workspaces retrieved successfully", Some(workspaces)).into_response()
+        }
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn create_workspace(
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(mut payload): Json<CreateWorkspaceRequest>,
+) -> impl IntoResponse {
+    payload.name = payload.name.trim().to_string();
+
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match workspace_service(&state)
+        .create_workspace(&user.id, &payload.name)
+        .await
+    {
+        Ok(id) => ApiResponse::created("Workspace created successfully", Some(id.to_string()))
+            .into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn get_workspace(
+    Path(workspace_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .get_workspace(&user.id, &workspace_id)
+        .await
+    {
+        Ok(workspace) => ApiResponse::ok(
+            "Workspace retrieved successfully",
+            Some(WorkspaceResponse::new(workspace, &user.id)),
+        )
+        .into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn rename_workspace(
+    Path(workspace_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(mut payload): Json<RenameWorkspaceRequest>,
+) -> impl IntoResponse {
+    payload.name = payload.name.trim().to_string();
+
+    if let Err(errors) = payload.validate() {
+        return ApiResponse::bad_request("Validation failed", Some(errors)).into_response();
+    }
+
+    match workspace_service(&state)
+        .rename_workspace(&user.id, &workspace_id, &payload.name)
+        .await
+    {
+        Ok(()) => ApiResponse::ok("Workspace updated successfully", None::<()>).into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn delete_workspace(
+    Path(workspace_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .delete_workspace(&user.id, &workspace_id)
+        .await
+    {
+        Ok(()) => ApiResponse::ok("Workspace deleted successfully", None::<()>).into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn add_member(
+    Path(workspace_id): Path<ObjectId>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(payload): Json<AddMemberRequest>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .add_member(&user.id, &workspace_id, &payload.email, payload.role)
+        .await
+    {
+        Ok(member_id) => {
+            ApiResponse::created("Member added successfully", Some(member_id.to_string()))
+                .into_response()
+        }
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn update_member(
+    Path((workspace_id, member_id)): Path<(ObjectId, ObjectId)>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+    Json(payload): Json<UpdateMemberRequest>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .set_member_role(&user.id, &workspace_id, &member_id, payload.role)
+        .await
+    {
+        Ok(()) => ApiResponse::ok("Member updated successfully", None::<()>).into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+async fn remove_member(
+    Path((workspace_id, member_id)): Path<(ObjectId, ObjectId)>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<AuthState>,
+) -> impl IntoResponse {
+    match workspace_service(&state)
+        .remove_member(&user.id, &workspace_id, &member_id)
+        .await
+    {
+        Ok(()) => ApiResponse::ok("Member removed successfully", None::<()>).into_response(),
+        Err(err) => workspace_error_response(err),
+    }
+}
+
+pub fn handles(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/v1/workspaces",
+            post(create_workspace).get(list_workspaces),
+        )
+        .route(
+            "/v1/workspaces/:workspace_id",
+            get(get_workspace)
+                .patch(rename_workspace)
+                .delete(delete_workspace),
+        )
+        .route("/v1/workspaces/:workspace_id/members", post(add_member))
+        .route(
+            "/v1/workspaces/:workspace_id/members/:user_id",
+            put(update_member).delete(remove_member),
+        )
+        .layer(middleware::from_fn_with_state(
+            state,
+            auth::middlewares::authorize,
+        ))
+}
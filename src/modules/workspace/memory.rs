@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::Error;
+
+use crate::helpers::memory_collection_helper::MemoryCollection;
+
+use super::models::{Workspace, WorkspaceMember, WorkspaceRole};
+use super::repository::WorkspaceRepository;
+
+#[derive(Default)]
+pub struct InMemoryWorkspaceRepository {
+    workspaces: MemoryCollection<Workspace>,
+}
+
+#[async_trait]
+impl WorkspaceRepository for InMemoryWorkspaceRepository {
+    async fn create_workspace(&self, workspace: Workspace) -> Result<ObjectId, Error> {
+        self.workspaces.insert(&workspace)
+    }
+
+    async fn find_by_id(&self, workspace_id: &ObjectId) -> Result<Option<Workspace>, Error> {
+        self.workspaces
+            .find_one(|workspace| workspace.id.as_ref() == Some(workspace_id))
+    }
+
+    async fn find_by_member(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error> {
+        let mut workspaces = self
+            .workspaces
+            .find(|workspace| workspace.member(user_id).is_some())?;
+        workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(workspaces)
+    }
+
+    async fn find_by_owner(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error> {
+        self.workspaces
+            .find(|workspace| workspace.owner_id == *user_id)
+    }
+
+    async fn rename(&self, workspace_id: &ObjectId, name: &str) -> Result<bool, Error> {
+        let result = self.workspaces.update_one(
+            |workspace| workspace.id.as_ref() == Some(workspace_id),
+            |workspace| workspace.name = name.to_string(),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn add_member(
+        &self,
+        workspace_id: &ObjectId,
+        member: &WorkspaceMember,
+    ) -> Result<bool, Error> {
+        let result = self.workspaces.update_one(
+            |workspace| {
+                workspace.id.as_ref() == Some(workspace_id)
+                    && workspace.member(&member.user_id).is_none()
+            },
+            |workspace| workspace.members.push(member.clone()),
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn set_member_role(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+        role: WorkspaceRole,
+    ) -> Result<bool, Error> {
+        let result = self.workspaces.update_one(
+            |workspace| {
+                workspace.id.as_ref() == Some(workspace_id) && workspace.member(user_id).is_some()
+            },
+            |workspace| {
+                for member in workspace.members.iter_mut() {
+                    if member.user_id == *user_id {
+                        member.role = role;
+                    }
+                }
+            },
+        )?;
+
+        Ok(result.matched > 0)
+    }
+
+    async fn remove_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<bool, Error> {
+        let result = self.workspaces.update_one(
+            |workspace| workspace.id.as_ref() == Some(workspace_id),
+            |workspace| {
+                workspace
+                    .members
+                    .retain(|member| member.user_id != *user_id)
+            },
+        )?;
+
+        Ok(result.modified > 0)
+    }
+
+    async fn remove_member_everywhere(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let result = self.workspaces.update_many(
+            |workspace| workspace.member(user_id).is_some(),
+            |workspace| {
+                workspace
+                    .members
+                    .retain(|member| member.user_id != *user_id)
+            },
+        )?;
+
+        Ok(result.modified)
+    }
+
+    async fn delete_workspace(&self, workspace_id: &ObjectId) -> Result<bool, Error> {
+        let deleted = self
+            .workspaces
+            .delete_one(|workspace| workspace.id.as_ref() == Some(workspace_id))?;
+
+        Ok(deleted > 0)
+    }
+}
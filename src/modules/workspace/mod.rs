@@ -0,0 +1,10 @@
+pub mod access;
+pub mod dto;
+pub mod handlers;
+#[cfg(test)]
+pub mod memory;
+pub mod models;
+pub mod repository;
+pub mod service;
+
+pub use handlers::handles;
@@ -0,0 +1,95 @@
+use crate::helpers::object_id_helper::{deserialize_option_object_id, serialize_option_object_id};
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkspaceRole {
+    #[serde(rename = "OWNER")]
+    Owner,
+    #[serde(rename = "EDITOR")]
+    Editor,
+    #[serde(rename = "VIEWER")]
+    Viewer,
+}
+
+impl WorkspaceRole {
+    pub fn allows(&self, access: Access) -> bool {
+        match access {
+            Access::Read => true,
+            Access::Write => matches!(self, WorkspaceRole::Owner | WorkspaceRole::Editor),
+            Access::Manage => *self == WorkspaceRole::Owner,
+        }
+    }
+}
+
+/// What a caller wants to do with a workspace or the data it owns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Write,  // Create, change or delete categories, goals and tasks
+    Manage, // Rename the workspace, manage members, delete it
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    pub user_id: ObjectId,
+    pub role: WorkspaceRole,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id",
+        deserialize_with = "deserialize_option_object_id"
+    )]
+    pub id: Option<ObjectId>,
+    pub name: String,
+    pub owner_id: ObjectId,
+    pub members: Vec<WorkspaceMember>, // Includes the owner
+    pub created_at: DateTime<Utc>,
+}
+
+impl Workspace {
+    pub fn member(&self, user_id: &ObjectId) -> Option<&WorkspaceMember> {
+        self.members
+            .iter()
+            .find(|member| &member.user_id == user_id)
+    }
+}
+
+/// Who owns a category, goal or task: a single user, or a workspace shared by its members.
+/// Records of a workspace keep the `user_id` of their creator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    User(ObjectId),
+    Workspace(ObjectId),
+}
+
+impl Scope {
+    pub fn new(user_id: ObjectId, workspace_id: Option<ObjectId>) -> Self {
+        match workspace_id {
+            Some(workspace_id) => Scope::Workspace(workspace_id),
+            None => Scope::User(user_id),
+        }
+    }
+
+    /// Filter matching the records owned by this scope. Personal records have no
+    /// `workspace_id`, or a null one.
+    pub fn filter(&self) -> Document {
+        match self {
+            Scope::User(user_id) => doc! { "user_id": user_id, "workspace_id": Bson::Null },
+            Scope::Workspace(workspace_id) => doc! { "workspace_id": workspace_id },
+        }
+    }
+
+    /// `filter` narrowed down to a single record.
+    pub fn filter_by_id(&self, id: &ObjectId) -> Document {
+        let mut filter = self.filter();
+        filter.insert("_id", id);
+        filter
+    }
+}
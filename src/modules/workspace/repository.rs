@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use mongodb::bson::{doc, oid::ObjectId, to_bson};
+use mongodb::error::Error;
+use mongodb::{Collection, Database};
+
+use super::models::{Workspace, WorkspaceMember, WorkspaceRole};
+
+#[async_trait]
+pub trait WorkspaceRepository: Send + Sync {
+    async fn create_workspace(&self, workspace: Workspace) -> Result<ObjectId, Error>;
+
+    async fn find_by_id(&self, workspace_id: &ObjectId) -> Result<Option<Workspace>, Error>;
+
+    /// Workspaces the user belongs to, by name.
+    async fn find_by_member(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error>;
+
+    async fn find_by_owner(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error>;
+
+    async fn rename(&self, workspace_id: &ObjectId, name: &str) -> Result<bool, Error>;
+
+    /// Adds the member unless the user already belongs to the workspace.
+    async fn add_member(
+        &self,
+        workspace_id: &ObjectId,
+        member: &WorkspaceMember,
+    ) -> Result<bool, Error>;
+
+    async fn set_member_role(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+        role: WorkspaceRole,
+    ) -> Result<bool, Error>;
+
+    async fn remove_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<bool, Error>;
+
+    /// Removes the user from every workspace they are a member of.
+    async fn remove_member_everywhere(&self, user_id: &ObjectId) -> Result<u64, Error>;
+
+    async fn delete_workspace(&self, workspace_id: &ObjectId) -> Result<bool, Error>;
+}
+
+pub struct MongoWorkspaceRepository {
+    collection: Collection<Workspace>,
+}
+
+impl MongoWorkspaceRepository {
+    pub fn new(db: &Database) -> Self {
+        let collection = db.collection("workspaces");
+        MongoWorkspaceRepository { collection }
+    }
+}
+
+#[async_trait]
+impl WorkspaceRepository for MongoWorkspaceRepository {
+    async fn create_workspace(&self, workspace: Workspace) -> Result<ObjectId, Error> {
+        let result = self.collection.insert_one(workspace).await?;
+        Ok(result.inserted_id.as_object_id().unwrap())
+    }
+
+    async fn find_by_id(&self, workspace_id: &ObjectId) -> Result<Option<Workspace>, Error> {
+        self.collection.find_one(doc! { "_id": workspace_id }).await
+    }
+
+    async fn find_by_member(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error> {
+        let mut cursor = self
+            .collection
+            .find(doc! { "members.user_id": user_id })
+            .sort(doc! { "name": 1 })
+            .await?;
+        let mut workspaces: Vec<Workspace> = Vec::new();
+        while cursor.advance().await? {
+            workspaces.push(cursor.deserialize_current()?);
+        }
+
+        Ok(workspaces)
+    }
+
+    async fn find_by_owner(&self, user_id: &ObjectId) -> Result<Vec<Workspace>, Error> {
+        let mut cursor = self.collection.find(doc! { "owner_id": user_id }).await?;
+        let mut workspaces: Vec<Workspace> = Vec::new();
+        while cursor.advance().await? {
+            workspaces.push(cursor.deserialize_current()?);
+        }
+
+        Ok(workspaces)
+    }
+
+    async fn rename(&self, workspace_id: &ObjectId, name: &str) -> Result<bool, Error> {
+        let filter = doc! { "_id": workspace_id };
+        let update = doc! { "$set": { "name": name } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn add_member(
+        &self,
+        workspace_id: &ObjectId,
+        member: &WorkspaceMember,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": workspace_id, "members.user_id": { "$ne": member.user_id } };
+        let update = doc! { "$push": { "members": to_bson(member)? } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn set_member_role(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+        role: WorkspaceRole,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": workspace_id, "members.user_id": user_id };
+        let update = doc! { "$set": { "members.$.role": to_bson(&role)? } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.matched_count > 0)
+    }
+
+    async fn remove_member(
+        &self,
+        workspace_id: &ObjectId,
+        user_id: &ObjectId,
+    ) -> Result<bool, Error> {
+        let filter = doc! { "_id": workspace_id };
+        let update = doc! { "$pull": { "members": { "user_id": user_id } } };
+        let result = self.collection.update_one(filter, update).await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn remove_member_everywhere(&self, user_id: &ObjectId) -> Result<u64, Error> {
+        let filter = doc! { "members.user_id": user_id };
+        let update = doc! { "$pull": { "members": { "user_id": user_id } } };
+        let result = self.collection.update_many(filter, update).await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_workspace(&self, workspace_id: &ObjectId) -> Result<bool, Error> {
+        let result = self
+            .collection
+            .delete_one(doc! { "_id": workspace_id })
+            .await?;
+
+        Ok(result.deleted_count > 0)
+    }
+}
@@ -0,0 +1,411 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+use thiserror::Error;
+
+use crate::modules::{
+    category::repository::CategoryRepository,
+    goal::repository::GoalRepository,
+    task::repository::{TaskActivityRepository, TaskCommentRepository, TaskRepository},
+    user::{repository::UserRepository, types::Email},
+};
+
+use super::access::{WorkspaceAccess, WorkspaceAccessError};
+use super::models::{Access, Scope, Workspace, WorkspaceMember, WorkspaceRole};
+use super::repository::WorkspaceRepository;
+
+#[derive(Error, Debug)]
+pub enum WorkspaceServiceError {
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Member not found")]
+    MemberNotFound,
+
+    #[error("User is already a member of this workspace")]
+    AlreadyMember,
+
+    #[error("A workspace has exactly one owner")]
+    OwnerRole,
+
+    #[error(transparent)]
+    Access(#[from] WorkspaceAccessError),
+
+    #[error("Database error occurred: {0}")]
+    DatabaseError(#[from] mongodb::error::Error),
+}
+
+pub struct WorkspaceService {
+    repository: Arc<dyn WorkspaceRepository>,
+    access: WorkspaceAccess,
+    user_repository: Arc<dyn UserRepository>,
+    category_repository: Arc<dyn CategoryRepository>,
+    goal_repository: Arc<dyn GoalRepository>,
+    task_repository: Arc<dyn TaskRepository>,
+    comment_repository: Arc<dyn TaskCommentRepository>,
+    activity_repository: Arc<dyn TaskActivityRepository>,
+}
+
+impl WorkspaceService {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        repository: Arc<dyn WorkspaceRepository>,
+        access: WorkspaceAccess,
+        user_repository: Arc<dyn UserRepository>,
+        category_repository: Arc<dyn CategoryRepository>,
+        goal_repository: Arc<dyn GoalRepository>,
+        task_repository: Arc<dyn TaskRepository>,
+        comment_repository: Arc<dyn TaskCommentRepository>,
+        activity_repository: Arc<dyn TaskActivityRepository>,
+    ) -> Self {
+        WorkspaceService {
+            repository,
+            access,
+            user_repository,
+            category_repository,
+            goal_repository,
+            task_repository,
+            comment_repository,
+            activity_repository,
+        }
+    }
+
+    pub async fn create_workspace(
+        &self,
+        owner_id: &ObjectId,
+        name: &str,
+    ) -> Result<ObjectId, WorkspaceServiceError> {
+        let now = Utc::now();
+        let workspace = Workspace {
+            id: None,
+            name: name.to_string(),
+            owner_id: *owner_id,
+            members: vec![WorkspaceMember {
+                user_id: *owner_id,
+                role: WorkspaceRole::Owner,
+                added_at: now,
+            }],
+            created_at: now,
+        };
+
+        Ok(self.repository.create_workspace(workspace).await?)
+    }
+
+    pub async fn get_user_workspaces(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<Vec<Workspace>, WorkspaceServiceError> {
+        Ok(self.repository.find_by_member(user_id).await?)
+    }
+
+    pub async fn get_workspace(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+    ) -> Result<Workspace, WorkspaceServiceError> {
+        self.get_authorized_workspace(user_id, workspace_id, Access::Read)
+            .await
+    }
+
+    pub async fn rename_workspace(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        name: &str,
+    ) -> Result<(), WorkspaceServiceError> {
+        self.access
+            .authorize(user_id, workspace_id, Access::Manage)
+            .await?;
+        self.repository.rename(workspace_id, name).await?;
+        Ok(())
+    }
+
+    /// Adds an existing user by email. Ownership cannot be granted this way.
+    pub async fn add_member(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        email: &Email,
+        role: WorkspaceRole,
+    ) -> Result<ObjectId, WorkspaceServiceError> {
+        if role == WorkspaceRole::Owner {
+            return Err(WorkspaceServiceError::OwnerRole);
+        }
+        self.access
+            .authorize(user_id, workspace_id, Access::Manage)
+            .await?;
+
+        let member_id = self
+            .user_repository
+            .find_user_by_email(email)
+            .await?
+            .and_then(|user| user.id)
+            .ok_or(WorkspaceServiceError::UserNotFound)?;
+        let member = WorkspaceMember {
+            user_id: member_id,
+            role,
+            added_at: Utc::now(),
+        };
+        if !self.repository.add_member(workspace_id, &member).await? {
+            return Err(WorkspaceServiceError::AlreadyMember);
+        }
+        Ok(member_id)
+    }
+
+    pub async fn set_member_role(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        member_id: &ObjectId,
+        role: WorkspaceRole,
+    ) -> Result<(), WorkspaceServiceError> {
+        let workspace = self
+            .get_authorized_workspace(user_id, workspace_id, Access::Manage)
+            .await?;
+        if role == WorkspaceRole::Owner || workspace.owner_id == *member_id {
+            return Err(WorkspaceServiceError::OwnerRole);
+        }
+        if workspace.member(member_id).is_none() {
+            return Err(WorkspaceServiceError::MemberNotFound);
+        }
+
+        self.repository
+            .set_member_role(workspace_id, member_id, role)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a member, which the owner may do for anyone and members may do for themselves.
+    /// Tasks assigned to the member are left unassigned.
+    pub async fn remove_member(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        member_id: &ObjectId,
+    ) -> Result<(), WorkspaceServiceError> {
+        let access = if user_id == member_id {
+            Access::Read
+        } else {
+            Access::Manage
+        };
+        let workspace = self
+            .get_authorized_workspace(user_id, workspace_id, access)
+            .await?;
+        if workspace.owner_id == *member_id {
+            return Err(WorkspaceServiceError::OwnerRole);
+        }
+
+        if !self
+            .repository
+            .remove_member(workspace_id, member_id)
+            .await?
+        {
+            return Err(WorkspaceServiceError::MemberNotFound);
+        }
+        self.task_repository
+            .unassign_member(workspace_id, member_id)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_workspace(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+    ) -> Result<(), WorkspaceServiceError> {
+        self.access
+            .authorize(user_id, workspace_id, Access::Manage)
+            .await?;
+        self.delete_workspace_data(workspace_id).await?;
+        Ok(())
+    }
+
+    /// Deletes the workspaces the user owns, with their data, and leaves all the others.
+    pub async fn delete_user_workspaces(
+        &self,
+        user_id: &ObjectId,
+    ) -> Result<(), mongodb::error::Error> {
+        for workspace in self.repository.find_by_owner(user_id).await? {
+            self.delete_workspace_data(&workspace.id.unwrap()).await?;
+        }
+        for workspace in self.repository.find_by_member(user_id).await? {
+            self.task_repository
+                .unassign_member(&workspace.id.unwrap(), user_id)
+                .await?;
+        }
+        self.repository.remove_member_everywhere(user_id).await?;
+        Ok(())
+    }
+
+    /// Data is removed before the workspace itself, so a failure part-way can be retried.
+    async fn delete_workspace_data(
+        &self,
+        workspace_id: &ObjectId,
+    ) -> Result<(), mongodb::error::Error> {
+        let scope = Scope::Workspace(*workspace_id);
+        let task_ids: Vec<ObjectId> = self
+            .task_repository
+            .get_all_user_tasks(&scope)
+            .await?
+            .into_iter()
+            .filter_map(|task| task.id)
+            .collect();
+        self.comment_repository
+            .delete_tasks_comments(&task_ids)
+            .await?;
+        self.activity_repository
+            .delete_tasks_activity(&task_ids)
+            .await?;
+        self.task_repository.delete_all_user_tasks(&scope).await?;
+        self.goal_repository.delete_all_user_goals(&scope).await?;
+        self.category_repository
+            .delete_all_user_categories(&scope)
+            .await?;
+        self.repository.delete_workspace(workspace_id).await?;
+        Ok(())
+    }
+
+    async fn get_authorized_workspace(
+        &self,
+        user_id: &ObjectId,
+        workspace_id: &ObjectId,
+        access: Access,
+    ) -> Result<Workspace, WorkspaceServiceError> {
+        self.access.authorize(user_id, workspace_id, access).await?;
+        self.repository
+            .find_by_id(workspace_id)
+            .await?
+            .ok_or(WorkspaceAccessError::WorkspaceNotFound.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Tz;
+    use serde_json::json;
+
+    use crate::config::repositories::Repositories;
+    use crate::modules::category::models::{Category, Color};
+    use crate::modules::goal::service::GoalService;
+    use crate::modules::task::service::TaskService;
+
+    use super::*;
+
+    fn workspace_service(repositories: &Repositories) -> WorkspaceService {
+        WorkspaceService::new(
+            repositories.workspaces.clone(),
+            WorkspaceAccess::new(repositories.workspaces.clone()),
+            repositories.users.clone(),
+            repositories.categories.clone(),
+            repositories.goals.clone(),
+            repositories.tasks.clone(),
+            repositories.task_comments.clone(),
+            repositories.task_activity.clone(),
+        )
+    }
+
+    fn task_service(repositories: &Repositories) -> TaskService {
+        let access = WorkspaceAccess::new(repositories.workspaces.clone());
+        TaskService::new(
+            repositories.tasks.clone(),
+            repositories.categories.clone(),
+            repositories.task_comments.clone(),
+            repositories.task_activity.clone(),
+            GoalService::new(
+                repositories.goals.clone(),
+                repositories.tasks.clone(),
+                repositories.categories.clone(),
+                access.clone(),
+            ),
+            access,
+        )
+    }
+
+    /// A task of the scope with a comment, and so some history.
+    async fn commented_task(
+        repositories: &Repositories,
+        user_id: ObjectId,
+        workspace_id: Option<ObjectId>,
+    ) -> ObjectId {
+        let category_id = repositories
+            .categories
+            .create_category(Category {
+                id: None,
+                user_id,
+                workspace_id,
+                title: "Work".to_string(),
+                color: Color::Green,
+            })
+            .await
+            .unwrap();
+        let service = task_service(repositories);
+        let task_id = service
+            .create_task_for_user(
+                &user_id,
+                &Tz::UTC,
+                serde_json::from_value(json!({
+                    "title": "Report",
+                    "description": "Description",
+                    "start_date": "2024-03-01T10:00:00Z",
+                    "end_date": "2024-03-01T11:00:00Z",
+                    "status": "ADIADA",
+                    "category_id": category_id.to_hex(),
+                    "workspace_id": workspace_id.map(|id| id.to_hex()),
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        service
+            .add_comment(
+                &user_id,
+                &task_id,
+                serde_json::from_value(json!({ "body": "Done soon" })).unwrap(),
+            )
+            .await
+            .unwrap();
+        task_id
+    }
+
+    #[tokio::test]
+    async fn deleting_a_workspace_deletes_the_comments_and_history_of_its_tasks() {
+        let repositories = Repositories::in_memory();
+        let service = workspace_service(&repositories);
+        let owner_id = ObjectId::new();
+        let workspace_id = service.create_workspace(&owner_id, "Team").await.unwrap();
+        let shared_task = commented_task(&repositories, owner_id, Some(workspace_id)).await;
+        let personal_task = commented_task(&repositories, owner_id, None).await;
+
+        service
+            .delete_workspace(&owner_id, &workspace_id)
+            .await
+            .unwrap();
+
+        let comments = &repositories.task_comments;
+        let activity = &repositories.task_activity;
+        assert!(comments
+            .get_task_comments(&shared_task)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(activity
+            .get_task_activity(&shared_task)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            comments
+                .get_task_comments(&personal_task)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(!activity
+            .get_task_activity(&personal_task)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}